@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_client::browser_client::BrowserClient;
+use rustafarian_client::client::Client;
+use rustafarian_client::sim::VirtualNetwork;
+use rustafarian_shared::messages::commander_messages::{
+    SimControllerCommand, SimControllerMessage, SimControllerResponseWrapper,
+};
+use rustafarian_shared::messages::general_messages::ServerType;
+use wg_2024::network::NodeId;
+
+const USAGE: &str = "Usage: rustafarian-browse [--out <directory>] [--server <id> --file <id>] [--mirror]
+
+Runs a browser client in a virtual network started in this process (drones 10, 11, 12,
+text servers 30 and 31, media server 40), lists the files of every server and downloads:
+  - the text file <file> of <server>, with all its referenced media (default: the first text file found)
+  - with --mirror, every file of every text and media server";
+
+/// How long to wait for a single response of the client
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The options read from the command line
+struct Options {
+    output_directory: PathBuf,
+    /// The text file to download, as (`server_id`, `file_id`)
+    file: Option<(NodeId, u8)>,
+    mirror: bool,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        output_directory: PathBuf::from("downloads"),
+        file: None,
+        mirror: false,
+    };
+    let mut server_id = None;
+    let mut file_id = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--out" => options.output_directory = PathBuf::from(value()?),
+            "--server" => {
+                let id = value()?
+                    .parse()
+                    .map_err(|err| format!("Invalid server id: {err}"))?;
+                server_id = Some(id);
+            }
+            "--file" => {
+                let id = value()?
+                    .parse()
+                    .map_err(|err| format!("Invalid file id: {err}"))?;
+                file_id = Some(id);
+            }
+            "--mirror" => options.mirror = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown argument '{arg}'\n{USAGE}")),
+        }
+    }
+    options.file = match (server_id, file_id) {
+        (Some(server_id), Some(file_id)) => Some((server_id, file_id)),
+        (None, None) => None,
+        _ => return Err("--server and --file must be used together".to_string()),
+    };
+    Ok(options)
+}
+
+/// Start the virtual network and the browser client, returns the channels to control the client
+fn start_virtual_network() -> (
+    Sender<SimControllerCommand>,
+    Receiver<SimControllerResponseWrapper>,
+) {
+    let client_id = 1;
+    let mut network = VirtualNetwork::new();
+    network
+        .add_drone(10)
+        .add_drone(11)
+        .add_drone(12)
+        .add_text_server(
+            30,
+            HashMap::from([
+                (1, "ref=1,2\nA page with two pictures".to_string()),
+                (2, "A page with only text".to_string()),
+            ]),
+        )
+        .add_text_server(
+            31,
+            HashMap::from([(3, "ref=3\nAnother page with a picture".to_string())]),
+        )
+        .add_media_server(
+            40,
+            HashMap::from([
+                (1, b"first picture".to_vec()),
+                (2, b"second picture".to_vec()),
+                (3, b"third picture".to_vec()),
+            ]),
+        )
+        .add_client(client_id)
+        .connect(10, 11)
+        .connect(11, 12)
+        .connect(12, 10)
+        .connect(30, 11)
+        .connect(31, 12)
+        .connect(40, 12)
+        .connect(client_id, 10);
+    network.start();
+
+    let (command_sender, command_receiver) = unbounded();
+    let (response_sender, response_receiver) = unbounded();
+    let mut client = BrowserClient::with_transport(
+        client_id,
+        Box::new(network.transport(client_id)),
+        command_receiver,
+        response_sender,
+        false,
+    );
+    thread::spawn(move || client.run(u64::MAX));
+    (command_sender, response_receiver)
+}
+
+/// Wait for a message of the client matching `filter`, ignoring the others
+fn wait_for<T>(
+    responses: &Receiver<SimControllerResponseWrapper>,
+    filter: impl Fn(SimControllerMessage) -> Option<T>,
+) -> Option<T> {
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    while let Ok(response) = responses.recv_deadline(deadline) {
+        if let SimControllerResponseWrapper::Message(message) = response {
+            if let Some(found) = filter(message) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Collect the content servers discovered by the first flood
+fn discover_servers(
+    commands: &Sender<SimControllerCommand>,
+    responses: &Receiver<SimControllerResponseWrapper>,
+) -> BTreeMap<NodeId, ServerType> {
+    // Give the flood and the server type requests some time to complete
+    thread::sleep(Duration::from_millis(500));
+    let _res = commands.send(SimControllerCommand::KnownServers);
+    wait_for(responses, |message| match message {
+        SimControllerMessage::KnownServers(servers) => Some(servers.into_iter().collect()),
+        _ => None,
+    })
+    .unwrap_or_default()
+}
+
+/// The client that downloads the files and writes them to the output directory
+struct Downloader {
+    commands: Sender<SimControllerCommand>,
+    responses: Receiver<SimControllerResponseWrapper>,
+    output_directory: PathBuf,
+}
+
+impl Downloader {
+    fn request_file_list(&self, server_id: NodeId) -> Option<Vec<u8>> {
+        let _res = self
+            .commands
+            .send(SimControllerCommand::RequestFileList(server_id));
+        wait_for(&self.responses, |message| match message {
+            SimControllerMessage::FileListResponse(id, files) if id == server_id => Some(files),
+            _ => None,
+        })
+    }
+
+    /// Download a text file and the media it references
+    fn download_text_file(&self, server_id: NodeId, file_id: u8) -> Result<(), String> {
+        let _res = self
+            .commands
+            .send(SimControllerCommand::RequestTextFile(file_id, server_id));
+        let (text, media_files) = wait_for(&self.responses, |message| match message {
+            SimControllerMessage::TextFileResponse(id, text) if id == file_id => {
+                Some((text, HashMap::new()))
+            }
+            SimControllerMessage::TextWithReferences(id, text, media_files) if id == file_id => {
+                Some((text, media_files))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            format!("Timed out waiting for text file {file_id} of server {server_id}")
+        })?;
+
+        let path = self
+            .output_directory
+            .join(format!("server_{server_id}"))
+            .join(format!("{file_id}.txt"));
+        write_file(&path, text.as_bytes())?;
+        for (media_id, content) in media_files {
+            self.write_media_file(media_id, &content)?;
+        }
+        Ok(())
+    }
+
+    fn download_media_file(&self, server_id: NodeId, file_id: u8) -> Result<(), String> {
+        let _res = self
+            .commands
+            .send(SimControllerCommand::RequestMediaFile(file_id, server_id));
+        let content = wait_for(&self.responses, |message| match message {
+            SimControllerMessage::MediaFileResponse(id, content) if id == file_id => Some(content),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            format!("Timed out waiting for media file {file_id} of server {server_id}")
+        })?;
+        self.write_media_file(file_id, &content)
+    }
+
+    fn write_media_file(&self, file_id: u8, content: &[u8]) -> Result<(), String> {
+        let path = self
+            .output_directory
+            .join("media")
+            .join(format!("{file_id}.bin"));
+        write_file(&path, content)
+    }
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .map_err(|err| format!("Couldn't create {}: {err}", directory.display()))?;
+    }
+    fs::write(path, content).map_err(|err| format!("Couldn't write {}: {err}", path.display()))?;
+    println!("Saved {}", path.display());
+    Ok(())
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let (commands, responses) = start_virtual_network();
+    let servers = discover_servers(&commands, &responses);
+    if servers.is_empty() {
+        return Err("No content server found".to_string());
+    }
+    let downloader = Downloader {
+        commands,
+        responses,
+        output_directory: options.output_directory.clone(),
+    };
+
+    // List the files of every server, and write the index
+    let mut index = String::new();
+    let mut files = BTreeMap::new();
+    for (server_id, server_type) in &servers {
+        let file_list = downloader.request_file_list(*server_id).unwrap_or_default();
+        let line = format!("Server {server_id} ({server_type:?}): {file_list:?}");
+        println!("{line}");
+        index.push_str(&line);
+        index.push('\n');
+        files.insert(*server_id, file_list);
+    }
+    write_file(
+        &options.output_directory.join("index.txt"),
+        index.as_bytes(),
+    )?;
+
+    if options.mirror {
+        for (server_id, server_type) in &servers {
+            for file_id in files.get(server_id).cloned().unwrap_or_default() {
+                let result = match server_type {
+                    ServerType::Text => downloader.download_text_file(*server_id, file_id),
+                    ServerType::Media => downloader.download_media_file(*server_id, file_id),
+                    ServerType::Chat => Ok(()),
+                };
+                // Keep mirroring the other files
+                if let Err(err) = result {
+                    eprintln!("{err}");
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let (server_id, file_id) = match options.file {
+        Some(file) => file,
+        None => servers
+            .iter()
+            .filter(|(_, server_type)| matches!(server_type, ServerType::Text))
+            .find_map(|(server_id, _)| {
+                let file_id = files.get(server_id)?.first()?;
+                Some((*server_id, *file_id))
+            })
+            .ok_or("No text file found")?,
+    };
+    downloader.download_text_file(server_id, file_id)
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    });
+    if let Err(err) = run(&options) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::net::SocketAddr;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_client::chat_client::ChatClient;
+use rustafarian_client::client::Client;
+use rustafarian_client::sim::VirtualNetwork;
+use rustafarian_client::transport::{Transport, UdpTransport};
+use rustafarian_shared::messages::commander_messages::{
+    SimControllerCommand, SimControllerMessage, SimControllerResponseWrapper,
+};
+use wg_2024::network::NodeId;
+
+const USAGE: &str = "Usage: rustafarian-chat [--id <client-id>] [--udp <local-address> --peer <neighbor-id>=<address>...]
+
+Without --udp, the client runs in a virtual network started in this process:
+drones 10, 11, 12, chat servers 20 and 21, and the echo clients 2 and 3.";
+
+const HELP: &str = "Commands:
+  /servers                        list the known chat servers
+  /register <server>              register to a chat server
+  /who <server>                   list the clients registered to a server
+  /msg <server> <client> <text>   send a message to a client
+  /registered                     list the servers the client is registered to
+  /topology                       show the topology known by the client
+  /flood                          send a flood request
+  /help                           show this help
+  /quit                           exit";
+
+/// The options read from the command line
+struct Options {
+    client_id: NodeId,
+    /// The address to bind, when using the UDP transport
+    udp_address: Option<SocketAddr>,
+    /// The neighbors reachable through UDP
+    peers: HashMap<NodeId, SocketAddr>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        client_id: 1,
+        udp_address: None,
+        peers: HashMap::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--id" => {
+                options.client_id = value()?
+                    .parse()
+                    .map_err(|err| format!("Invalid client id: {err}"))?;
+            }
+            "--udp" => {
+                let address = value()?
+                    .parse()
+                    .map_err(|err| format!("Invalid address: {err}"))?;
+                options.udp_address = Some(address);
+            }
+            "--peer" => {
+                let peer = value()?;
+                let (id, address) = peer
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid peer '{peer}', expected <id>=<address>"))?;
+                let id = id
+                    .parse()
+                    .map_err(|err| format!("Invalid peer id: {err}"))?;
+                let address = address
+                    .parse()
+                    .map_err(|err| format!("Invalid peer address: {err}"))?;
+                options.peers.insert(id, address);
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown argument '{arg}'\n{USAGE}")),
+        }
+    }
+    Ok(options)
+}
+
+/// Start the virtual network, with two echo clients registered to the chat servers
+fn start_virtual_network(client_id: NodeId) -> Box<dyn Transport> {
+    let mut network = VirtualNetwork::new();
+    network
+        .add_drone(10)
+        .add_drone(11)
+        .add_drone(12)
+        .add_chat_server(20)
+        .add_chat_server(21)
+        .add_client(client_id)
+        .add_client(2)
+        .add_client(3)
+        .connect(10, 11)
+        .connect(11, 12)
+        .connect(12, 10)
+        .connect(20, 11)
+        .connect(21, 12)
+        .connect(client_id, 10)
+        .connect(2, 11)
+        .connect(3, 12);
+    network.start();
+
+    start_echo_client(2, Box::new(network.transport(2)), &[20]);
+    start_echo_client(3, Box::new(network.transport(3)), &[20, 21]);
+
+    Box::new(network.transport(client_id))
+}
+
+/// Start a client that registers to the given servers and sends back every message it receives
+fn start_echo_client(client_id: NodeId, transport: Box<dyn Transport>, servers: &[NodeId]) {
+    let (command_sender, command_receiver) = unbounded();
+    let (response_sender, response_receiver) = unbounded();
+    let mut client = ChatClient::with_transport(
+        client_id,
+        transport,
+        command_receiver,
+        response_sender,
+        false,
+    );
+    thread::spawn(move || client.run(u64::MAX));
+
+    for server_id in servers {
+        let _res = command_sender.send(SimControllerCommand::Register(*server_id));
+    }
+    thread::spawn(move || {
+        for response in response_receiver {
+            if let SimControllerResponseWrapper::Message(SimControllerMessage::MessageReceived(
+                server_id,
+                from,
+                text,
+            )) = response
+            {
+                let echo = format!("echo from {client_id}: {text}");
+                let _res =
+                    command_sender.send(SimControllerCommand::SendMessage(echo, server_id, from));
+            }
+        }
+    });
+}
+
+/// Print the responses of the client to the terminal
+fn print_responses(responses: &Receiver<SimControllerResponseWrapper>) {
+    for response in responses {
+        let SimControllerResponseWrapper::Message(message) = response else {
+            continue;
+        };
+        let line = match message {
+            SimControllerMessage::MessageReceived(server_id, from, text) => {
+                format!("[{server_id}] {from}: {text}")
+            }
+            SimControllerMessage::ClientListResponse(server_id, clients) => {
+                format!("Clients registered to {server_id}: {clients:?}")
+            }
+            SimControllerMessage::KnownServers(servers) => {
+                let mut servers = servers.keys().copied().collect::<Vec<NodeId>>();
+                servers.sort_unstable();
+                format!("Known chat servers: {servers:?}")
+            }
+            SimControllerMessage::RegisteredServersResponse(servers) => {
+                format!("Registered to: {servers:?}")
+            }
+            SimControllerMessage::ServerTypeResponse(server_id, server_type) => {
+                format!("Server {server_id} is of type {server_type:?}")
+            }
+            SimControllerMessage::TopologyResponse(topology) => {
+                format!("Topology: {:?}", topology.edges())
+            }
+            _ => continue,
+        };
+        println!("{line}");
+    }
+}
+
+/// Parse a line typed by the user into a command for the client
+fn parse_command(line: &str) -> Result<SimControllerCommand, String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let mut node_id = |name: &str| -> Result<NodeId, String> {
+        words
+            .next()
+            .ok_or_else(|| format!("Missing {name}"))?
+            .parse()
+            .map_err(|err| format!("Invalid {name}: {err}"))
+    };
+    match command {
+        "/servers" => Ok(SimControllerCommand::KnownServers),
+        "/register" => Ok(SimControllerCommand::Register(node_id("server")?)),
+        "/who" => Ok(SimControllerCommand::ClientList(node_id("server")?)),
+        "/msg" => {
+            let server_id = node_id("server")?;
+            let client_id = node_id("client")?;
+            let text = words.collect::<Vec<&str>>().join(" ");
+            if text.is_empty() {
+                return Err("Missing text".to_string());
+            }
+            Ok(SimControllerCommand::SendMessage(
+                text, server_id, client_id,
+            ))
+        }
+        "/registered" => Ok(SimControllerCommand::RegisteredServers),
+        "/topology" => Ok(SimControllerCommand::Topology),
+        "/flood" => Ok(SimControllerCommand::FloodRequest),
+        _ => Err(format!("Unknown command '{command}', type /help")),
+    }
+}
+
+fn run_repl(commands: &Sender<SimControllerCommand>) {
+    println!("{HELP}");
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let line = line.trim();
+        match line {
+            "" => {}
+            "/quit" => return,
+            "/help" => println!("{HELP}"),
+            _ => match parse_command(line) {
+                Ok(command) => {
+                    if commands.send(command).is_err() {
+                        eprintln!("The client stopped");
+                        return;
+                    }
+                }
+                Err(err) => eprintln!("{err}"),
+            },
+        }
+        let _res = io::stdout().flush();
+    }
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    });
+
+    let transport: Box<dyn Transport> = match options.udp_address {
+        Some(address) => match UdpTransport::bind(address, options.peers) {
+            Ok(transport) => Box::new(transport),
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(1);
+            }
+        },
+        None => start_virtual_network(options.client_id),
+    };
+
+    let (command_sender, command_receiver) = unbounded();
+    let (response_sender, response_receiver) = unbounded();
+    let mut client = ChatClient::with_transport(
+        options.client_id,
+        transport,
+        command_receiver,
+        response_sender,
+        false,
+    );
+    thread::spawn(move || client.run(u64::MAX));
+    thread::spawn(move || print_responses(&response_receiver));
+
+    // Give the first flood some time to discover the servers
+    thread::sleep(Duration::from_millis(200));
+    run_repl(&command_sender);
+}
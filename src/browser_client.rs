@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet};
-use std::process;
 
 use crate::client::Client;
+use crate::transport::{CrossbeamTransport, Transport};
 use rustafarian_shared::assembler::{assembler::Assembler, disassembler::Disassembler};
 use rustafarian_shared::logger::{LogLevel, Logger};
 use rustafarian_shared::messages::browser_messages::{
@@ -18,25 +18,9 @@ use rustafarian_shared::topology::Topology;
 use crossbeam_channel::{Receiver, Sender};
 use wg_2024::{network::NodeId, packet::Packet};
 
-pub struct BrowserClient {
-    // Used for general client
-    client_id: u8,
-    senders: HashMap<u8, Sender<Packet>>,
-    receiver: Receiver<Packet>,
-    topology: Topology,
-    sim_controller_receiver: Receiver<SimControllerCommand>,
-    sim_controller_sender: Sender<SimControllerResponseWrapper>,
-    sent_packets: HashMap<u64, Vec<Packet>>,
-    acked_packets: HashMap<u64, Vec<bool>>,
-    assembler: Assembler,
-    disassembler: Disassembler,
-    running: bool,
-    packets_to_send: HashMap<u8, Packet>,
-    sent_flood_ids: Vec<u64>,
-    last_flood_timestamp: u128,
-    logger: Logger,
-
-    // Specific to browser client
+/// The data of the browser application layer
+#[derive(Default)]
+pub struct BrowserState {
     /// The text files available from Text Content Servers
     available_text_files: HashMap<NodeId, Vec<u8>>,
     /// The media files available from Media Content Servers
@@ -55,46 +39,17 @@ pub struct BrowserClient {
     references_files: HashMap<u8, HashSet<u8>>,
 }
 
-impl BrowserClient {
-    #[must_use]
-    pub fn new(
-        client_id: u8,
-        senders: HashMap<u8, Sender<Packet>>,
-        receiver: Receiver<Packet>,
-        sim_controller_receiver: Receiver<SimControllerCommand>,
-        sim_controller_sender: Sender<SimControllerResponseWrapper>,
-        debug: bool,
-    ) -> Self {
-        BrowserClient {
-            client_id,
-            senders,
-            receiver,
-            topology: Topology::new(),
-            sim_controller_receiver,
-            sim_controller_sender,
-            sent_packets: HashMap::new(),
-            acked_packets: HashMap::new(),
-            assembler: Assembler::new(),
-            disassembler: Disassembler::new(),
-            running: false,
-            packets_to_send: HashMap::new(),
-            sent_flood_ids: Vec::new(),
-            last_flood_timestamp: 0,
-            logger: Logger::new("BrowserClient".to_string(), client_id, debug),
-
-            available_text_files: HashMap::new(),
-            available_media_files: HashMap::new(),
-            obtained_text_files: HashMap::new(),
-            obtained_media_files: HashMap::new(),
-            available_servers: HashMap::new(),
-            pending_referenced_files: HashMap::new(),
-            references_files: HashMap::new(),
-        }
-    }
+/// The browser application layer, used to communicate with Text and Media Content Servers.
+/// Implemented by every client that can browse, on top of the network part of `Client`
+pub trait BrowserLayer: Client {
+    /// The data of the browser layer
+    fn browser_state(&self) -> &BrowserState;
+    /// The data of the browser layer, mutable
+    fn browser_state_mut(&mut self) -> &mut BrowserState;
 
     /// Requests a text file from a server
-    pub fn request_text_file(&mut self, file_id: u8, server_id: NodeId) {
-        self.logger.log(
+    fn request_text_file(&mut self, file_id: u8, server_id: NodeId) {
+        self.logger().log(
             &format!("Requesting text file {file_id} from server {server_id}"),
             LogLevel::DEBUG,
         );
@@ -104,8 +59,8 @@ impl BrowserClient {
     }
 
     /// Requests a media file from a server
-    pub fn request_media_file(&mut self, file_id: u8, server_id: NodeId) {
-        self.logger.log(
+    fn request_media_file(&mut self, file_id: u8, server_id: NodeId) {
+        self.logger().log(
             &format!("Requesting media file {file_id} from server {server_id}"),
             LogLevel::DEBUG,
         );
@@ -115,8 +70,8 @@ impl BrowserClient {
     }
 
     /// Requests a list of files from a server
-    pub fn request_file_list(&mut self, server_id: NodeId) {
-        self.logger.log(
+    fn request_file_list(&mut self, server_id: NodeId) {
+        self.logger().log(
             &format!("Requesting file list from server {server_id}"),
             LogLevel::DEBUG,
         );
@@ -125,44 +80,76 @@ impl BrowserClient {
         self.send_message(server_id, request_json);
     }
 
+    /// When a `ServerTypeResponse` says that a server is a content server
+    /// Behavior: add it to the available servers (as a key of available files). Chat servers are ignored
+    fn on_content_server_discovered(&mut self, server_id: NodeId, server_type: &ServerType) {
+        let state = self.browser_state_mut();
+        match server_type {
+            ServerType::Text => {
+                state.available_servers.insert(server_id, ServerType::Text);
+                state.available_text_files.insert(server_id, vec![]);
+            }
+            ServerType::Media => {
+                state.available_servers.insert(server_id, ServerType::Media);
+                state.available_media_files.insert(server_id, vec![]);
+            }
+            ServerType::Chat => {
+                self.logger().log(
+                    &format!("Server type 'Chat' not added to available servers: {server_id}"),
+                    LogLevel::DEBUG,
+                );
+            }
+        }
+    }
+
     /// Handle a response from a server
     fn handle_browser_response(&mut self, response: BrowserResponse, server_id: NodeId) {
         match response {
             // If the response is a list of files, add it to the available files
             BrowserResponse::FileList(files) => {
-                match self.available_servers.get(&server_id) {
-                    Some(server_type) => {
-                        if matches!(server_type, ServerType::Text) {
-                            self.available_text_files.insert(server_id, files.clone());
-                        } else if matches!(server_type, ServerType::Media) {
-                            self.available_media_files.insert(server_id, files.clone());
-                        }
+                let server_type = self
+                    .browser_state()
+                    .available_servers
+                    .get(&server_id)
+                    .cloned();
+                match server_type {
+                    Some(ServerType::Text) => {
+                        self.browser_state_mut()
+                            .available_text_files
+                            .insert(server_id, files.clone());
                     }
+                    Some(ServerType::Media) => {
+                        self.browser_state_mut()
+                            .available_media_files
+                            .insert(server_id, files.clone());
+                    }
+                    Some(ServerType::Chat) => {}
                     None => {
-                        self.logger.log(
+                        self.logger().log(
                             &format!("Server type not found for server_id: {server_id}"),
                             LogLevel::ERROR,
                         );
                     }
                 }
-                self.logger.log(
+                self.logger().log(
                     &format!("Received file list from server {server_id}: {files:?}"),
                     LogLevel::DEBUG,
                 );
 
                 // Send the list of files to the sim controller
                 let _res = self
-                    .sim_controller_sender
+                    .sim_controller_sender()
                     .send(SimControllerResponseWrapper::Message(
                         SimControllerMessage::FileListResponse(server_id, files),
                     ));
             }
             // If the response is a text file, add it to the obtained text files
             BrowserResponse::TextFile(file_id, text) => {
-                self.obtained_text_files
+                self.browser_state_mut()
+                    .obtained_text_files
                     .insert((server_id, file_id), text.clone());
 
-                self.logger.log(
+                self.logger().log(
                     &format!(
                         "Received text file from {server_id}: {}...",
                         &text.chars().take(10).collect::<String>()
@@ -175,8 +162,10 @@ impl BrowserClient {
             }
             // If the response is a media file, add it to the obtained media files
             BrowserResponse::MediaFile(file_id, media) => {
-                self.obtained_media_files.insert(file_id, media.clone());
-                self.logger.log(
+                self.browser_state_mut()
+                    .obtained_media_files
+                    .insert(file_id, media.clone());
+                self.logger().log(
                     &format!("Received media file from {server_id}"),
                     LogLevel::DEBUG,
                 );
@@ -191,7 +180,7 @@ impl BrowserClient {
 
                 // Send the media file to the sim controller
                 let _res = self
-                    .sim_controller_sender
+                    .sim_controller_sender()
                     .send(SimControllerResponseWrapper::Message(
                         SimControllerMessage::MediaFileResponse(file_id, media),
                     ));
@@ -203,22 +192,18 @@ impl BrowserClient {
     /// In that case, if all the references are obtained, send the text file to the sim controller with the attached media files
     fn check_referenced_media_received(&mut self, media_file_id: u8) -> bool {
         // Browse the pending referenced files and check if the obtained media file is referenced
-        let mut is_reference = false;
+        let mut referencing_text_files = vec![];
         let mut completed_text_files = vec![];
-        self.logger.log(
+        self.logger().log(
             &format!(
                 "Checking if media file is a reference in text files: {:?}",
-                self.pending_referenced_files
+                self.browser_state().pending_referenced_files
             ),
             LogLevel::DEBUG,
         );
-        for (file_id, references) in &mut self.pending_referenced_files {
+        for (file_id, references) in &mut self.browser_state_mut().pending_referenced_files {
             if references.contains(&media_file_id) {
-                self.logger.log(
-                    &format!("Media file {media_file_id} is a reference in text file {file_id}"),
-                    LogLevel::DEBUG,
-                );
-                is_reference = true;
+                referencing_text_files.push(*file_id);
                 // Remove the reference from the pending_referenced_files map
                 references.remove(&media_file_id);
                 // If there are no more references, add the file_id to the completed_text_files
@@ -227,27 +212,33 @@ impl BrowserClient {
                 }
             }
         }
-        self.logger.log(
+        for file_id in &referencing_text_files {
+            self.logger().log(
+                &format!("Media file {media_file_id} is a reference in text file {file_id}"),
+                LogLevel::DEBUG,
+            );
+        }
+        self.logger().log(
             &format!("Completed text files: {completed_text_files:?}"),
             LogLevel::DEBUG,
         );
         // Remove all the completed text files from the pending_referenced_files map
         // Then, send the completed file to the simulation controller
         for file_id in completed_text_files {
-            let empty_string = String::new();
             let text = self
+                .browser_state()
                 .obtained_text_files
                 .iter()
                 .find(|k| k.0 .1 == file_id)
-                .unwrap_or((&(0, 0), &empty_string));
-            if text.0 == &(0, 0) {
-                self.logger
+                .map(|(_, text)| text.clone());
+            let Some(text) = text else {
+                self.logger()
                     .log(&format!("Text file {file_id} not found"), LogLevel::ERROR);
                 continue;
-            }
-            self.send_text_file_with_references(file_id, &text.1.clone());
+            };
+            self.send_text_file_with_references(file_id, &text);
         }
-        is_reference
+        !referencing_text_files.is_empty()
     }
 
     /// If there is any reference to media files in the text file, request the media files
@@ -255,7 +246,7 @@ impl BrowserClient {
         // First, look at the media files referenced inside the text file
         let first_line = text.lines().next();
         if first_line.is_none() {
-            self.logger
+            self.logger()
                 .log(&format!("Text file {file_id} is empty"), LogLevel::ERROR);
             return;
         }
@@ -265,13 +256,13 @@ impl BrowserClient {
 
         // If the text file does not have a reference, skip it
         if !has_reference {
-            self.logger.log(
+            self.logger().log(
                 &format!("Text file {file_id} does not have a reference, sending to controller"),
                 LogLevel::DEBUG,
             );
             // Send the text file to the sim controller
             let _res = self
-                .sim_controller_sender
+                .sim_controller_sender()
                 .send(SimControllerResponseWrapper::Message(
                     SimControllerMessage::TextFileResponse(file_id, text.to_string()),
                 ));
@@ -279,27 +270,30 @@ impl BrowserClient {
         }
 
         // Then, find a server of type media in the available servers
-        let available_servers = self.get_available_servers().clone();
-        let server_id = available_servers
+        let server_id = self
+            .browser_state()
+            .available_servers
             .iter()
-            .find(|s| matches!(s.1, ServerType::Media));
+            .find(|s| matches!(s.1, ServerType::Media))
+            .map(|s| *s.0);
 
         // If no media server is found, skip the text file
         if server_id.is_none() {
-            self.logger.log(
+            self.logger().log(
                 &format!("No media server found in available servers, cannot send media file references for text file {file_id}"),
                 LogLevel::ERROR,
             );
             return;
         }
 
-        let server_id = server_id.unwrap().0; // Impossible for a panic to happen, as it was just checked
+        let server_id = server_id.unwrap(); // Impossible for a panic to happen, as it was just checked
 
         let references = first_line.split('=').collect::<Vec<&str>>()[1];
         let references = references.split(',').collect::<Vec<&str>>();
 
         // Add the file_id to the pending_referenced_files map
-        self.pending_referenced_files
+        self.browser_state_mut()
+            .pending_referenced_files
             .insert(file_id, HashSet::new());
 
         // Whether it needs to wait for at least one reference before sending, or if all references have already been obtained.
@@ -308,7 +302,7 @@ impl BrowserClient {
         for reference in references {
             let reference = reference.parse::<u8>();
             if reference.is_err() {
-                self.logger.log(
+                self.logger().log(
                     &format!("Invalid reference in text file {file_id}"),
                     LogLevel::ERROR,
                 );
@@ -317,14 +311,19 @@ impl BrowserClient {
             let reference = reference.unwrap(); // Impossible for a panic to happen, as it was just checked
 
             // Add the references to the references_files map
-            self.references_files
+            self.browser_state_mut()
+                .references_files
                 .entry(file_id)
                 .or_default()
                 .insert(reference);
 
             // If the media file is already obtained, skip it
-            if self.obtained_media_files.keys().any(|k| *k == reference) {
-                self.logger.log(
+            if self
+                .browser_state()
+                .obtained_media_files
+                .contains_key(&reference)
+            {
+                self.logger().log(
                     &format!("Media file {reference} already obtained, not sending request"),
                     LogLevel::DEBUG,
                 );
@@ -333,13 +332,14 @@ impl BrowserClient {
             has_pending_references = true;
 
             // Add the references to the pending_referenced_files map
-            self.pending_referenced_files
-                .get_mut(&file_id)
-                .unwrap_or(&mut HashSet::new())
+            self.browser_state_mut()
+                .pending_referenced_files
+                .entry(file_id)
+                .or_default()
                 .insert(reference);
 
             // Request the media file
-            self.request_media_file(reference, *server_id);
+            self.request_media_file(reference, server_id);
         }
 
         // If there are no pending references, send the text file to the sim controller with all the references
@@ -350,9 +350,10 @@ impl BrowserClient {
 
     /// Send a text file with all the references to the controller
     fn send_text_file_with_references(&mut self, file_id: u8, text: &str) {
-        self.pending_referenced_files.remove(&file_id);
+        let state = self.browser_state_mut();
+        state.pending_referenced_files.remove(&file_id);
         // Get the attached media files from the references, and get the obtained content
-        let attached_media_files = self
+        let attached_media_files = state
             .references_files
             .remove(&file_id)
             .unwrap_or_default()
@@ -360,7 +361,8 @@ impl BrowserClient {
             .map(|file_id| {
                 (
                     *file_id,
-                    self.obtained_media_files
+                    state
+                        .obtained_media_files
                         .get(file_id)
                         .unwrap_or(&Vec::new())
                         .clone(),
@@ -375,7 +377,7 @@ impl BrowserClient {
             );
             return;
         }
-        self.logger.log(
+        self.logger().log(
             &format!(
                 "Sending text file {file_id} to sim controller, with attached media files: {:?}",
                 attached_media_files.keys()
@@ -384,7 +386,7 @@ impl BrowserClient {
         );
         // Send to the simulation controller
         let _res = self
-            .sim_controller_sender
+            .sim_controller_sender()
             .send(SimControllerResponseWrapper::Message(
                 SimControllerMessage::TextWithReferences(
                     file_id,
@@ -394,29 +396,145 @@ impl BrowserClient {
             ));
     }
 
+    /// Handle the commands sent by the controller to the browser layer.
+    /// Returns the command back if it's not a browser command
+    fn handle_browser_command(
+        &mut self,
+        command: SimControllerCommand,
+    ) -> Option<SimControllerCommand> {
+        match command {
+            // If the command is a request for the file list, send the request
+            SimControllerCommand::RequestFileList(server_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Requesting file list from server {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.request_file_list(server_id);
+            }
+            // If the command is a request for a text file, send the request
+            SimControllerCommand::RequestTextFile(file_id, server_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Requesting text file {file_id} from server {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.request_text_file(file_id, server_id);
+            }
+            // If the command is a request for a media file, send the request
+            SimControllerCommand::RequestMediaFile(file_id, server_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Requesting media file {file_id} from server {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.request_media_file(file_id, server_id);
+            }
+            command => return Some(command),
+        }
+        None
+    }
+
+    #[must_use]
+    fn get_available_text_files(&self) -> &HashMap<NodeId, Vec<u8>> {
+        &self.browser_state().available_text_files
+    }
+
+    #[must_use]
+    fn get_available_media_files(&self) -> &HashMap<NodeId, Vec<u8>> {
+        &self.browser_state().available_media_files
+    }
+
     #[must_use]
-    pub fn get_available_text_files(&self) -> &HashMap<NodeId, Vec<u8>> {
-        &self.available_text_files
+    fn get_obtained_text_files(&self) -> &HashMap<(NodeId, u8), String> {
+        &self.browser_state().obtained_text_files
     }
 
     #[must_use]
-    pub fn get_available_media_files(&self) -> &HashMap<NodeId, Vec<u8>> {
-        &self.available_media_files
+    fn get_obtained_media_files(&mut self) -> &mut HashMap<u8, Vec<u8>> {
+        &mut self.browser_state_mut().obtained_media_files
     }
 
     #[must_use]
-    pub fn get_obtained_text_files(&self) -> &HashMap<(NodeId, u8), String> {
-        &self.obtained_text_files
+    fn get_available_servers(&self) -> &HashMap<NodeId, ServerType> {
+        &self.browser_state().available_servers
     }
+}
+
+pub struct BrowserClient {
+    // Used for general client
+    client_id: u8,
+    transport: Box<dyn Transport>,
+    topology: Topology,
+    sim_controller_receiver: Receiver<SimControllerCommand>,
+    sim_controller_sender: Sender<SimControllerResponseWrapper>,
+    sent_packets: HashMap<u64, Vec<Packet>>,
+    acked_packets: HashMap<u64, Vec<bool>>,
+    assembler: Assembler,
+    disassembler: Disassembler,
+    running: bool,
+    packets_to_send: HashMap<u8, Packet>,
+    sent_flood_ids: Vec<u64>,
+    last_flood_timestamp: u128,
+    logger: Logger,
 
+    // Specific to browser client
+    browser_state: BrowserState,
+}
+
+impl BrowserClient {
     #[must_use]
-    pub fn get_obtained_media_files(&mut self) -> &mut HashMap<u8, Vec<u8>> {
-        &mut self.obtained_media_files
+    pub fn new(
+        client_id: u8,
+        senders: HashMap<u8, Sender<Packet>>,
+        receiver: Receiver<Packet>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+    ) -> Self {
+        Self::with_transport(
+            client_id,
+            Box::new(CrossbeamTransport::new(senders, receiver)),
+            sim_controller_receiver,
+            sim_controller_sender,
+            debug,
+        )
     }
 
+    /// Create a client that exchanges packets through a custom transport (e.g. UDP)
     #[must_use]
-    pub fn get_available_servers(&self) -> &HashMap<NodeId, ServerType> {
-        &self.available_servers
+    pub fn with_transport(
+        client_id: u8,
+        transport: Box<dyn Transport>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+    ) -> Self {
+        BrowserClient {
+            client_id,
+            transport,
+            topology: Topology::new(),
+            sim_controller_receiver,
+            sim_controller_sender,
+            sent_packets: HashMap::new(),
+            acked_packets: HashMap::new(),
+            assembler: Assembler::new(),
+            disassembler: Disassembler::new(),
+            running: false,
+            packets_to_send: HashMap::new(),
+            sent_flood_ids: Vec::new(),
+            last_flood_timestamp: 0,
+            logger: Logger::new("BrowserClient".to_string(), client_id, debug),
+
+            browser_state: BrowserState::default(),
+        }
+    }
+}
+
+impl BrowserLayer for BrowserClient {
+    fn browser_state(&self) -> &BrowserState {
+        &self.browser_state
+    }
+
+    fn browser_state_mut(&mut self) -> &mut BrowserState {
+        &mut self.browser_state
     }
 }
 
@@ -428,12 +546,12 @@ impl Client for BrowserClient {
         self.client_id
     }
 
-    fn senders(&self) -> &HashMap<u8, Sender<Packet>> {
-        &self.senders
+    fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
     }
 
-    fn receiver(&self) -> &Receiver<Packet> {
-        &self.receiver
+    fn transport_mut(&mut self) -> &mut dyn Transport {
+        self.transport.as_mut()
     }
 
     fn topology(&mut self) -> &mut Topology {
@@ -457,24 +575,7 @@ impl Client for BrowserClient {
                     LogLevel::DEBUG,
                 );
                 // If it's not a chat server, add it to the available servers (as a key of available_files)
-                match server_response {
-                    ServerType::Text => {
-                        self.available_servers.insert(server_id, ServerType::Text);
-                        self.available_text_files.insert(server_id, vec![]);
-                    }
-                    ServerType::Media => {
-                        self.available_servers.insert(server_id, ServerType::Media);
-                        self.available_media_files.insert(server_id, vec![]);
-                    }
-                    ServerType::Chat => {
-                        self.logger.log(
-                            &format!(
-                                "Server type 'Chat' not added to available servers: {server_response:?}"
-                            ),
-                            LogLevel::DEBUG,
-                        );
-                    }
-                }
+                self.on_content_server_discovered(server_id, &server_response);
 
                 // Send the server type response to the sim controller
                 let response = SimControllerMessage::ServerTypeResponse(server_id, server_response);
@@ -506,75 +607,19 @@ impl Client for BrowserClient {
     }
 
     fn handle_controller_commands(&mut self, command: SimControllerCommand) {
+        let Some(command) = self.handle_browser_command(command) else {
+            return;
+        };
+        let Some(command) = self.handle_network_command(command) else {
+            return;
+        };
         match command {
-            // If the command is a request for the file list, send the request
-            SimControllerCommand::RequestFileList(server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Requesting file list from server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.request_file_list(server_id);
-            }
-            // If the command is a request for a text file, send the request
-            SimControllerCommand::RequestTextFile(file_id, server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Requesting text file {file_id} from server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.request_text_file(file_id, server_id);
-            }
-            // If the command is a request for a media file, send the request
-            SimControllerCommand::RequestMediaFile(file_id, server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Requesting media file {file_id} from server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.request_media_file(file_id, server_id);
-            }
-            // If the command is a flood request, send the request
-            SimControllerCommand::FloodRequest => {
-                self.logger
-                    .log("COMMAND: Sending flood request", LogLevel::DEBUG);
-                self.send_flood_request();
-            }
-            // If the command is a topology request, send the topology
-            SimControllerCommand::Topology => {
-                let topology = self.topology.clone();
-                self.logger
-                    .log("COMMAND: Sending topology", LogLevel::DEBUG);
-                let response = SimControllerMessage::TopologyResponse(topology);
-                let _res = self
-                    .sim_controller_sender
-                    .send(SimControllerResponseWrapper::Message(response));
-            }
-            // If the command is to add a neighbor, add the neighbor to the map and the topology
-            SimControllerCommand::AddSender(sender_id, sender_channel) => {
-                self.logger.log(
-                    &format!("COMMAND: Adding sender {sender_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.senders.insert(sender_id, sender_channel);
-                self.topology.add_node(sender_id);
-                self.topology.set_node_type(sender_id, "drone".to_string());
-                self.topology.add_edge(self.client_id, sender_id);
-                // Send a flood request to the new neighbor
-                self.send_flood_request();
-            }
-            // If the command is to remove a neighbor, remove the neighbor from the map and the topology
-            SimControllerCommand::RemoveSender(sender_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Removing sender {sender_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.topology.remove_edges(self.client_id, sender_id);
-                self.senders.remove(&sender_id);
-            }
             // If the command wants the servers known by the client, send the known servers
             SimControllerCommand::KnownServers => {
                 self.logger.log(
                     &format!(
                         "COMMAND: Sending known servers ({:?})",
-                        self.available_servers
+                        self.browser_state.available_servers
                     ),
                     LogLevel::DEBUG,
                 );
@@ -586,24 +631,12 @@ impl Client for BrowserClient {
                     }
                 }
                 // Then, send the response
-                let known_servers = self.available_servers.clone();
+                let known_servers = self.browser_state.available_servers.clone();
                 let response = SimControllerMessage::KnownServers(known_servers);
                 let _res = self
                     .sim_controller_sender
                     .send(SimControllerResponseWrapper::Message(response));
             }
-            SimControllerCommand::RequestServerType(server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Requesting server type from server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.send_server_type_request(server_id);
-            }
-            // The simulation controller wants the client to shut down
-            SimControllerCommand::Shutdown => {
-                self.logger.log("COMMAND: Shutting down", LogLevel::DEBUG);
-                process::exit(0);
-            }
             // Commands related to the Chat Client
             _ => {
                 self.logger.log(
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// What a client is doing in a conversation, sent as a `ChatPayload::Activity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Activity {
+    Typing,
+    Idle,
+}
+
+/// The peers typing in every conversation (`server_id`, `peer_id`).
+/// A peer is idle again when it says so, when its message arrives, or after `timeout_ms` without news
+#[derive(Debug)]
+pub struct ActivityTracker {
+    /// Value: when the last `Typing` arrived
+    typing: HashMap<(NodeId, NodeId), u128>,
+    timeout_ms: u128,
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new(5000)
+    }
+}
+
+impl ActivityTracker {
+    #[must_use]
+    pub fn new(timeout_ms: u64) -> Self {
+        ActivityTracker {
+            typing: HashMap::new(),
+            timeout_ms: u128::from(timeout_ms),
+        }
+    }
+
+    /// An activity arrived from a peer. Returns true if its activity changed,
+    /// a `Typing` while already typing only restarts the timeout
+    pub fn on_activity(
+        &mut self,
+        server_id: NodeId,
+        peer_id: NodeId,
+        activity: Activity,
+        now: u128,
+    ) -> bool {
+        match activity {
+            Activity::Typing => self.typing.insert((server_id, peer_id), now).is_none(),
+            Activity::Idle => self.typing.remove(&(server_id, peer_id)).is_some(),
+        }
+    }
+
+    /// A message arrived from a peer, so it stopped typing. Returns true if it was typing
+    pub fn on_message(&mut self, server_id: NodeId, peer_id: NodeId) -> bool {
+        self.typing.remove(&(server_id, peer_id)).is_some()
+    }
+
+    /// Remove and return the peers typing without news for longer than the timeout, sorted
+    pub fn timed_out(&mut self, now: u128) -> Vec<(NodeId, NodeId)> {
+        let mut expired = self
+            .typing
+            .iter()
+            .filter(|(_, since)| **since + self.timeout_ms <= now)
+            .map(|(conversation, _)| *conversation)
+            .collect::<Vec<(NodeId, NodeId)>>();
+        expired.sort_unstable();
+        for conversation in &expired {
+            self.typing.remove(conversation);
+        }
+        expired
+    }
+
+    #[must_use]
+    pub fn is_typing(&self, server_id: NodeId, peer_id: NodeId) -> bool {
+        self.typing.contains_key(&(server_id, peer_id))
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// How often the auto-join policy is applied again, to join the servers that became reachable
+pub const AUTO_JOIN_CHECK_INTERVAL_MS: u128 = 1000;
+
+/// Which discovered chat servers the client registers to on its own
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoJoinPolicy {
+    /// Register only when asked
+    #[default]
+    Off,
+    /// Every chat server
+    All,
+    /// The N chat servers with the shortest route
+    Nearest(usize),
+    /// Only the listed chat servers
+    Allowlist(Vec<NodeId>),
+}
+
+impl AutoJoinPolicy {
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        *self != AutoJoinPolicy::Off
+    }
+
+    /// Select the servers to join, given the length of the route to every discovered chat server
+    /// (`None` if it's unreachable). The unreachable servers are never selected
+    #[must_use]
+    pub fn select(&self, servers: &[(NodeId, Option<usize>)]) -> Vec<NodeId> {
+        let mut reachable = servers
+            .iter()
+            .filter_map(|(server_id, route_length)| Some((*server_id, (*route_length)?)))
+            .collect::<Vec<(NodeId, usize)>>();
+        reachable.sort_unstable_by_key(|(server_id, route_length)| (*route_length, *server_id));
+        let reachable = reachable.into_iter().map(|(server_id, _)| server_id);
+        match self {
+            AutoJoinPolicy::Off => vec![],
+            AutoJoinPolicy::All => reachable.collect(),
+            AutoJoinPolicy::Nearest(count) => reachable.take(*count).collect(),
+            AutoJoinPolicy::Allowlist(allowed) => reachable
+                .filter(|server_id| allowed.contains(server_id))
+                .collect(),
+        }
+    }
+}
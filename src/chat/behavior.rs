@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use rand::distributions::Alphanumeric;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use wg_2024::network::NodeId;
+
+use super::payload::MessageContent;
+
+/// What a behavior sees of the client when it's invoked
+#[derive(Debug, Clone, Default)]
+pub struct BehaviorContext {
+    pub client_id: NodeId,
+    /// Milliseconds since the UNIX epoch
+    pub now: u128,
+    pub registered_servers: Vec<NodeId>,
+    /// Key: `server_id`, value: the latest client list of the server
+    pub available_clients: HashMap<NodeId, Vec<NodeId>>,
+}
+
+impl BehaviorContext {
+    /// The other clients listed by the registered servers, as (`server_id`, `client_id`), sorted
+    #[must_use]
+    pub fn reachable_clients(&self) -> Vec<(NodeId, NodeId)> {
+        let mut clients = self
+            .registered_servers
+            .iter()
+            .flat_map(|server_id| {
+                self.available_clients
+                    .get(server_id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|client_id| **client_id != self.client_id)
+                    .map(|client_id| (*server_id, *client_id))
+            })
+            .collect::<Vec<_>>();
+        clients.sort_unstable();
+        clients
+    }
+}
+
+/// What a behavior asks the client to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BehaviorAction {
+    /// Send a text message with `send_chat_message`
+    Send {
+        server_id: NodeId,
+        peer_id: NodeId,
+        text: String,
+    },
+    /// Send a text message to every client listed by the registered servers, with `send_to`
+    Broadcast(String),
+    Register(NodeId),
+    /// Report something to the chat controller, like a measure
+    Report(String),
+}
+
+/// A scripted chat client, run by the chat layer on the incoming messages and on every tick
+pub trait ChatBehavior: Send {
+    /// The name used in the logs and in the reports
+    fn name(&self) -> &str;
+
+    /// A message from another client arrived
+    fn on_message(
+        &mut self,
+        _context: &BehaviorContext,
+        _server_id: NodeId,
+        _from: NodeId,
+        _content: &MessageContent,
+    ) -> Vec<BehaviorAction> {
+        vec![]
+    }
+
+    /// Called on every tick of the client
+    fn on_tick(&mut self, _context: &BehaviorContext) -> Vec<BehaviorAction> {
+        vec![]
+    }
+}
+
+/// Sent before the text of the echoed messages, which aren't echoed again
+pub const ECHO_PREFIX: &str = "echo: ";
+
+/// Answers every text message with the same text
+#[derive(Debug, Default)]
+pub struct Echo;
+
+impl ChatBehavior for Echo {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn on_message(
+        &mut self,
+        _context: &BehaviorContext,
+        server_id: NodeId,
+        from: NodeId,
+        content: &MessageContent,
+    ) -> Vec<BehaviorAction> {
+        match content {
+            MessageContent::Text(text) if !text.starts_with(ECHO_PREFIX) => {
+                vec![BehaviorAction::Send {
+                    server_id,
+                    peer_id: from,
+                    text: format!("{ECHO_PREFIX}{text}"),
+                }]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Registers to every discovered server, then sends a random text of `message_size` characters
+/// to a random reachable client every `interval_ms`
+#[derive(Debug)]
+pub struct Chatter {
+    interval_ms: u64,
+    message_size: usize,
+    last_message: u128,
+    joined: Vec<NodeId>,
+}
+
+impl Chatter {
+    #[must_use]
+    pub fn new(interval_ms: u64, message_size: usize) -> Self {
+        Chatter {
+            interval_ms,
+            message_size,
+            last_message: 0,
+            joined: vec![],
+        }
+    }
+}
+
+impl ChatBehavior for Chatter {
+    fn name(&self) -> &str {
+        "chatter"
+    }
+
+    fn on_tick(&mut self, context: &BehaviorContext) -> Vec<BehaviorAction> {
+        let mut actions = vec![];
+        for server_id in context.available_clients.keys() {
+            if !context.registered_servers.contains(server_id) && !self.joined.contains(server_id) {
+                self.joined.push(*server_id);
+                actions.push(BehaviorAction::Register(*server_id));
+            }
+        }
+        if self.last_message + u128::from(self.interval_ms) > context.now {
+            return actions;
+        }
+        let mut rng = rand::thread_rng();
+        if let Some((server_id, peer_id)) = context.reachable_clients().choose(&mut rng) {
+            self.last_message = context.now;
+            let text = (&mut rng)
+                .sample_iter(&Alphanumeric)
+                .take(self.message_size)
+                .map(char::from)
+                .collect();
+            actions.push(BehaviorAction::Send {
+                server_id: *server_id,
+                peer_id: *peer_id,
+                text,
+            });
+        }
+        actions
+    }
+}
+
+pub const PING_PREFIX: &str = "ping ";
+pub const PONG_PREFIX: &str = "pong ";
+/// How long a ping waits for its pong before it's reported as lost, in milliseconds
+pub const PING_TIMEOUT_MS: u128 = 10_000;
+
+/// Answers every `ping <n>` with `pong <n>`. With a target, it also sends a ping
+/// every `interval_ms`, and reports the round trip time of every pong.
+/// A ping without a pong after `PING_TIMEOUT_MS` is reported as lost
+#[derive(Debug)]
+pub struct PingPong {
+    /// (`server_id`, `peer_id`) to ping
+    target: Option<(NodeId, NodeId)>,
+    interval_ms: u64,
+    next_ping: u64,
+    last_ping: u128,
+    /// When every ping waiting for its pong was sent
+    sent: HashMap<u64, u128>,
+    rtts: Vec<u128>,
+    lost: u64,
+}
+
+impl PingPong {
+    /// A behavior that only answers the pings
+    #[must_use]
+    pub fn responder() -> Self {
+        PingPong {
+            target: None,
+            interval_ms: 0,
+            next_ping: 0,
+            last_ping: 0,
+            sent: HashMap::new(),
+            rtts: vec![],
+            lost: 0,
+        }
+    }
+
+    /// A behavior that pings `peer_id` through `server_id` every `interval_ms`
+    #[must_use]
+    pub fn measurer(server_id: NodeId, peer_id: NodeId, interval_ms: u64) -> Self {
+        PingPong {
+            target: Some((server_id, peer_id)),
+            interval_ms,
+            ..Self::responder()
+        }
+    }
+
+    /// The round trip times measured, in milliseconds
+    #[must_use]
+    pub fn rtts(&self) -> &[u128] {
+        &self.rtts
+    }
+
+    /// How many pings never got their pong
+    #[must_use]
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+}
+
+impl ChatBehavior for PingPong {
+    fn name(&self) -> &str {
+        "ping-pong"
+    }
+
+    fn on_message(
+        &mut self,
+        context: &BehaviorContext,
+        server_id: NodeId,
+        from: NodeId,
+        content: &MessageContent,
+    ) -> Vec<BehaviorAction> {
+        let MessageContent::Text(text) = content else {
+            return vec![];
+        };
+        if let Some(n) = text.strip_prefix(PING_PREFIX) {
+            return vec![BehaviorAction::Send {
+                server_id,
+                peer_id: from,
+                text: format!("{PONG_PREFIX}{n}"),
+            }];
+        }
+        let sent = text
+            .strip_prefix(PONG_PREFIX)
+            .and_then(|n| n.parse::<u64>().ok())
+            .and_then(|n| self.sent.remove(&n));
+        match sent {
+            Some(sent) => {
+                let rtt = context.now.saturating_sub(sent);
+                self.rtts.push(rtt);
+                vec![BehaviorAction::Report(format!("RTT to {from}: {rtt} ms"))]
+            }
+            None => vec![],
+        }
+    }
+
+    fn on_tick(&mut self, context: &BehaviorContext) -> Vec<BehaviorAction> {
+        let Some((server_id, peer_id)) = self.target else {
+            return vec![];
+        };
+        let mut lost = self
+            .sent
+            .iter()
+            .filter(|(_, sent)| **sent + PING_TIMEOUT_MS <= context.now)
+            .map(|(n, _)| *n)
+            .collect::<Vec<u64>>();
+        lost.sort_unstable();
+        let mut actions = vec![];
+        for n in lost {
+            self.sent.remove(&n);
+            self.lost += 1;
+            actions.push(BehaviorAction::Report(format!(
+                "Ping {n} to {peer_id} lost"
+            )));
+        }
+        if self.last_ping + u128::from(self.interval_ms) > context.now {
+            return actions;
+        }
+        self.last_ping = context.now;
+        let n = self.next_ping;
+        self.next_ping += 1;
+        self.sent.insert(n, context.now);
+        actions.push(BehaviorAction::Send {
+            server_id,
+            peer_id,
+            text: format!("{PING_PREFIX}{n}"),
+        });
+        actions
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::receipts::DeliveryStatus;
+
+/// A message sent to every client of a server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Broadcast {
+    pub id: u64,
+    pub server_id: NodeId,
+    pub text: String,
+    /// When it was requested, or sent if `recipients` is set
+    pub since: u128,
+    /// The recipients with the id of their message. `None` while waiting for a fresh client list
+    pub recipients: Option<Vec<(NodeId, u64)>>,
+}
+
+/// How a broadcast went for every recipient, sorted by recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BroadcastResult {
+    pub broadcast_id: u64,
+    pub server_id: NodeId,
+    /// The last status of the message to every recipient
+    pub outcomes: Vec<(NodeId, DeliveryStatus)>,
+}
+
+impl BroadcastResult {
+    /// The recipients that got the message
+    #[must_use]
+    pub fn delivered(&self) -> Vec<NodeId> {
+        self.outcomes
+            .iter()
+            .filter(|(_, status)| *status == DeliveryStatus::Delivered)
+            .map(|(recipient, _)| *recipient)
+            .collect()
+    }
+}
+
+/// The broadcasts waiting for a client list, or for the outcome of their messages
+#[derive(Debug)]
+pub struct Broadcasts {
+    broadcasts: Vec<Broadcast>,
+    /// How long to wait for the client list, and then for the receipts
+    timeout_ms: u128,
+}
+
+impl Default for Broadcasts {
+    fn default() -> Self {
+        Self::new(5000)
+    }
+}
+
+impl Broadcasts {
+    #[must_use]
+    pub fn new(timeout_ms: u64) -> Self {
+        Broadcasts {
+            broadcasts: vec![],
+            timeout_ms: u128::from(timeout_ms),
+        }
+    }
+
+    /// Add a broadcast waiting for a fresh client list
+    pub fn start(&mut self, id: u64, server_id: NodeId, text: String, now: u128) {
+        self.broadcasts.push(Broadcast {
+            id,
+            server_id,
+            text,
+            since: now,
+            recipients: None,
+        });
+    }
+
+    #[must_use]
+    pub fn get(&self, id: u64) -> Option<&Broadcast> {
+        self.broadcasts.iter().find(|broadcast| broadcast.id == id)
+    }
+
+    /// The broadcast was sent to `recipients`
+    pub fn on_sent(&mut self, id: u64, recipients: Vec<(NodeId, u64)>, now: u128) {
+        if let Some(broadcast) = self
+            .broadcasts
+            .iter_mut()
+            .find(|broadcast| broadcast.id == id)
+        {
+            broadcast.recipients = Some(recipients);
+            broadcast.since = now;
+        }
+    }
+
+    /// The broadcasts through a server still waiting for its client list
+    #[must_use]
+    pub fn waiting(&self, server_id: NodeId) -> Vec<u64> {
+        self.broadcasts
+            .iter()
+            .filter(|broadcast| broadcast.server_id == server_id && broadcast.recipients.is_none())
+            .map(|broadcast| broadcast.id)
+            .collect()
+    }
+
+    /// The broadcasts that waited for the client list for too long
+    #[must_use]
+    pub fn waiting_timed_out(&self, now: u128) -> Vec<u64> {
+        self.broadcasts
+            .iter()
+            .filter(|broadcast| {
+                broadcast.recipients.is_none() && broadcast.since + self.timeout_ms <= now
+            })
+            .map(|broadcast| broadcast.id)
+            .collect()
+    }
+
+    /// Remove the broadcasts whose messages all have a final status, or that timed out,
+    /// and return their results. `status` gives the current status of a message
+    pub fn take_finished<F>(&mut self, now: u128, status: F) -> Vec<BroadcastResult>
+    where
+        F: Fn(u64) -> Option<DeliveryStatus>,
+    {
+        let mut results = vec![];
+        let timeout_ms = self.timeout_ms;
+        self.broadcasts.retain(|broadcast| {
+            let Some(recipients) = &broadcast.recipients else {
+                return true;
+            };
+            let mut outcomes = recipients
+                .iter()
+                .map(|(recipient, id)| (*recipient, status(*id).unwrap_or(DeliveryStatus::Failed)))
+                .collect::<Vec<_>>();
+            let finished = outcomes.iter().all(|(_, status)| status.is_final());
+            if !finished && broadcast.since + timeout_ms > now {
+                return true;
+            }
+            outcomes.sort_unstable_by_key(|(recipient, _)| *recipient);
+            results.push(BroadcastResult {
+                broadcast_id: broadcast.id,
+                server_id: broadcast.server_id,
+                outcomes,
+            });
+            false
+        });
+        results
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::auto_join::AutoJoinPolicy;
+use super::filter::SenderPolicy;
+
+/// The settings of the chat layer. The missing fields of a saved configuration get their default value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+    /// Where the conversations are saved, as JSON lines. If `None`, they are only kept in memory
+    pub history_path: Option<PathBuf>,
+    /// How long to wait for `ClientRegistered` before sending the registration again
+    pub registration_timeout_ms: u64,
+    /// How many registration requests are sent to a server before giving up
+    pub registration_attempts: u32,
+    /// Which discovered chat servers to register to without being asked
+    pub auto_join: AutoJoinPolicy,
+    /// How often the client list of every registered server is requested, to follow the presence.
+    /// If `None`, `directory_max_age_ms` decides
+    pub presence_poll_interval_ms: Option<u64>,
+    /// How long a message to a client that isn't registered is kept in the outbox. If `None`, until it's delivered
+    pub outbox_expiry_ms: Option<u64>,
+    /// How long `send_to` waits for the receipt before trying another server,
+    /// and a broadcast waits for the client list and then for the receipts
+    pub delivery_timeout_ms: u64,
+    /// How old a client list can be before a broadcast asks for it again
+    pub client_list_max_age_ms: u64,
+    /// How old the client list of a registered server can be before it's requested in the background,
+    /// to keep the directory up to date. If both this and `presence_poll_interval_ms` are `None`,
+    /// the lists are only requested on demand
+    pub directory_max_age_ms: Option<u64>,
+    /// How long a chat server can leave the requests unanswered before it's considered down
+    pub server_timeout_ms: u64,
+    /// How many messages from a client can arrive after a missing one before it's skipped
+    pub reorder_window: u64,
+    /// How long a missing message is waited for before it's skipped
+    pub reorder_timeout_ms: u64,
+    /// The size of the chunks of the files sent
+    pub file_chunk_size: u64,
+    /// How many chunks of a file can be sent before the first one is acknowledged
+    pub file_window: usize,
+    /// How long a file transfer waits for an answer before sending again
+    pub file_timeout_ms: u64,
+    /// How long a peer is typing after its last `Typing`, if it doesn't say it stopped
+    pub typing_timeout_ms: u64,
+    /// The blocked and muted clients, and the rate limit of the messages
+    pub sender_policy: SenderPolicy,
+    /// Where the configuration was loaded from. The changes made with the chat commands are saved there
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        ChatConfig {
+            history_path: None,
+            registration_timeout_ms: 2000,
+            registration_attempts: 3,
+            auto_join: AutoJoinPolicy::Off,
+            presence_poll_interval_ms: None,
+            outbox_expiry_ms: Some(300_000),
+            delivery_timeout_ms: 5000,
+            client_list_max_age_ms: 10_000,
+            directory_max_age_ms: None,
+            server_timeout_ms: 10_000,
+            reorder_window: 16,
+            reorder_timeout_ms: 2000,
+            file_chunk_size: 1024,
+            file_window: 4,
+            file_timeout_ms: 3000,
+            typing_timeout_ms: 5000,
+            sender_policy: SenderPolicy::default(),
+            path: None,
+        }
+    }
+}
+
+impl ChatConfig {
+    /// Load the configuration saved as JSON at `path`, the default one if the file doesn't exist.
+    /// It's saved at `path` when it changes
+    /// # Errors
+    /// Returns an error if the file exists but couldn't be read or parsed
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut config = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str::<ChatConfig>(&content)
+                .map_err(|err| format!("Invalid configuration {}: {err}", path.display()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => ChatConfig::default(),
+            Err(err) => return Err(format!("Couldn't read {}: {err}", path.display())),
+        };
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Save the configuration where it was loaded from. Does nothing if it wasn't loaded from a file
+    /// # Errors
+    /// Returns an error if the file couldn't be written
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(path, content).map_err(|err| format!("Couldn't write {}: {err}", path.display()))
+    }
+
+    /// How old a client list can be before it's requested in the background:
+    /// the shortest of `presence_poll_interval_ms` and `directory_max_age_ms`
+    #[must_use]
+    pub fn list_refresh_ms(&self) -> Option<u64> {
+        match (self.presence_poll_interval_ms, self.directory_max_age_ms) {
+            (Some(poll_interval), Some(max_age)) => Some(poll_interval.min(max_age)),
+            (poll_interval, max_age) => poll_interval.or(max_age),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::activity::Activity;
+use super::auto_join::AutoJoinPolicy;
+use super::broadcast::BroadcastResult;
+use super::directory::Directory;
+use super::export::ExportFormat;
+use super::filter::{RateLimit, SenderPolicy};
+use super::groups::GroupMessage;
+use super::history::HistoryEntry;
+use super::outbox::QueuedMessage;
+use super::payload::MessageContent;
+use super::presence::PresenceChange;
+use super::receipts::DeliveryStatus;
+use super::registration::RegistrationState;
+use super::search::SearchQuery;
+use super::transfer::TransferProgress;
+use super::unread::ConversationSummary;
+
+/// The commands of the chat layer that the simulation controller can't express.
+/// They are received through the channel given to `ChatLayer::attach_chat_controller`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChatCommand {
+    /// Get a page of the conversation with `peer_id` through `server_id`.
+    /// Page 0 contains the most recent messages
+    History {
+        server_id: NodeId,
+        peer_id: NodeId,
+        page: usize,
+        page_size: usize,
+    },
+    /// Get the list of conversations, as (`server_id`, `peer_id`)
+    Conversations,
+    /// Get the conversations with their unread messages and their last message, from the most recently active
+    ConversationList,
+    /// Mark the messages of a conversation as read
+    MarkRead {
+        server_id: NodeId,
+        peer_id: NodeId,
+    },
+    /// Leave a chat server. The server isn't notified, as the protocol has no request for it
+    Unregister(NodeId),
+    /// Get the registration state for every server
+    Registrations,
+    /// Change the servers the client registers to on its own
+    SetAutoJoin(AutoJoinPolicy),
+    /// Change how often the client lists are requested. `None` stops polling,
+    /// unless `directory_max_age_ms` keeps the directory up to date
+    SetPresencePolling(Option<u64>),
+    /// Get the servers through which a client is online
+    Presence(NodeId),
+    /// Get the clients of all the registered servers, merged
+    Directory,
+    /// Get the messages waiting for their recipient to register
+    Outbox,
+    /// Create a group with this client and `members`
+    CreateGroup {
+        name: String,
+        members: Vec<NodeId>,
+    },
+    AddGroupMember {
+        group_id: u64,
+        member: NodeId,
+    },
+    /// Remove a member from a group, or leave it if `member` is this client
+    RemoveGroupMember {
+        group_id: u64,
+        member: NodeId,
+    },
+    SendGroupMessage {
+        group_id: u64,
+        text: String,
+    },
+    /// Get the groups the client is a member of
+    Groups,
+    /// Get all the messages of a group
+    GroupHistory(u64),
+    /// Send a message that isn't only text, like a file, a reply or a reaction
+    SendContent {
+        server_id: NodeId,
+        peer_id: NodeId,
+        content: MessageContent,
+    },
+    /// Send a message removed from the history of both clients `ttl_ms` after being sent
+    SendEphemeral {
+        server_id: NodeId,
+        peer_id: NodeId,
+        content: MessageContent,
+        ttl_ms: u64,
+    },
+    /// Tell a peer whether this client is typing to it
+    SendActivity {
+        server_id: NodeId,
+        peer_id: NodeId,
+        activity: Activity,
+    },
+    /// Offer a file to another client
+    SendFile {
+        server_id: NodeId,
+        peer_id: NodeId,
+        name: String,
+        mime_type: String,
+        data: Vec<u8>,
+    },
+    AcceptFile(u64),
+    RejectFile(u64),
+    /// Continue an interrupted file transfer, sent or received, from the chunks already received
+    ResumeTransfer(u64),
+    /// Get the progress of a file transfer
+    Transfer(u64),
+    /// Drop everything a client sends
+    Block(NodeId),
+    Unblock(NodeId),
+    /// Save the messages of a client in the history, without reporting them
+    Mute(NodeId),
+    Unmute(NodeId),
+    /// Limit how many messages every client can send, `None` to remove the limit
+    SetRateLimit(Option<RateLimit>),
+    /// Get the blocked and muted clients, and the rate limit
+    SenderPolicy,
+    /// Send a message to every client of a server, except this one
+    Broadcast {
+        server_id: NodeId,
+        text: String,
+    },
+    /// Find the messages of the history matching a query
+    Search(SearchQuery),
+    /// Write a conversation to a file
+    Export {
+        server_id: NodeId,
+        peer_id: NodeId,
+        format: ExportFormat,
+        path: PathBuf,
+    },
+}
+
+/// The responses and notifications of the chat layer, sent to the channel given to `ChatLayer::attach_chat_controller`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChatEvent {
+    History {
+        server_id: NodeId,
+        peer_id: NodeId,
+        page: usize,
+        entries: Vec<HistoryEntry>,
+    },
+    Conversations(Vec<(NodeId, NodeId)>),
+    ConversationList(Vec<ConversationSummary>),
+    /// A sent message changed status. The first event of every message is `Pending`, with its id
+    MessageStatus {
+        id: u64,
+        server_id: NodeId,
+        peer_id: NodeId,
+        status: DeliveryStatus,
+    },
+    /// The registration to a server changed state
+    RegistrationChanged {
+        server_id: NodeId,
+        state: RegistrationState,
+    },
+    /// The registration state for every server the client tried to register to
+    Registrations(Vec<(NodeId, RegistrationState)>),
+    /// A client joined or left a server, found by comparing its consecutive client lists
+    PresenceChanged {
+        server_id: NodeId,
+        client_id: NodeId,
+        change: PresenceChange,
+    },
+    /// The servers through which a client is online, empty if it's offline
+    Presence {
+        client_id: NodeId,
+        servers: Vec<NodeId>,
+    },
+    /// The clients of all the registered servers, with the servers listing them
+    Directory(Directory),
+    /// The messages in the outbox, oldest first
+    Outbox(Vec<QueuedMessage>),
+    /// A message sent with `send_to` couldn't be delivered through any shared server
+    SendFailed {
+        id: u64,
+        peer_id: NodeId,
+    },
+    /// A group was created, or its name or members changed
+    GroupChanged {
+        group_id: u64,
+        name: String,
+        members: Vec<NodeId>,
+    },
+    /// The client isn't a member of the group anymore
+    GroupLeft(u64),
+    /// A message or the members of a group weren't sent to these members,
+    /// as they share no server with the client
+    GroupMembersUnreachable {
+        group_id: u64,
+        members: Vec<NodeId>,
+    },
+    /// A message of a group arrived
+    GroupMessage {
+        group_id: u64,
+        message: GroupMessage,
+    },
+    /// The groups, as (`group_id`, name, members)
+    Groups(Vec<(u64, String, Vec<NodeId>)>),
+    GroupHistory {
+        group_id: u64,
+        messages: Vec<GroupMessage>,
+    },
+    /// A chat command couldn't be executed
+    CommandFailed(String),
+    /// A one-to-one message arrived. `id` is `None` for the plain text of older clients
+    /// and for the data that isn't UTF-8, which is received as `application/octet-stream`
+    MessageReceived {
+        server_id: NodeId,
+        from: NodeId,
+        id: Option<u64>,
+        content: MessageContent,
+    },
+    /// An ephemeral message, sent or received, expired and was removed from the history
+    MessageExpired {
+        server_id: NodeId,
+        peer_id: NodeId,
+        id: u64,
+    },
+    /// A peer started or stopped typing. It's `Idle` again when its message arrives,
+    /// or when it doesn't confirm it's typing for `typing_timeout_ms`
+    PeerActivity {
+        server_id: NodeId,
+        peer_id: NodeId,
+        activity: Activity,
+    },
+    /// Another client offers a file, accept it with `ChatCommand::AcceptFile`
+    FileOffered {
+        transfer_id: u64,
+        from: NodeId,
+        name: String,
+        mime_type: String,
+        size: u64,
+    },
+    /// A file transfer, sent or received, progressed or changed state
+    TransferProgress(TransferProgress),
+    /// A file was received completely, and its checksum matches
+    FileReceived {
+        transfer_id: u64,
+        from: NodeId,
+        name: String,
+        mime_type: String,
+        data: Vec<u8>,
+    },
+    /// The blocked and muted clients, and the rate limit, answering `ChatCommand::SenderPolicy`
+    /// and after every change
+    SenderPolicy(SenderPolicy),
+    /// How a broadcast went, once every recipient got it or it timed out
+    BroadcastResult(BroadcastResult),
+    /// The messages matching a `ChatCommand::Search`, from the oldest
+    SearchResults(Vec<HistoryEntry>),
+    /// A conversation was written to the file
+    Exported(PathBuf),
+    /// Something reported by a chat behavior, like a measure
+    BehaviorReport {
+        behavior: String,
+        report: String,
+    },
+    /// The messages from `peer_id` with a sequence number from `first` to `last` never arrived
+    MessagesMissing {
+        peer_id: NodeId,
+        first: u64,
+        last: u64,
+    },
+    /// A registered server can't be reached or stopped answering. Its pending messages
+    /// and transfers were moved to `alternative`, if there is one
+    ServerDown {
+        server_id: NodeId,
+        alternative: Option<NodeId>,
+    },
+    /// A server that was down answered again
+    ServerUp(NodeId),
+}
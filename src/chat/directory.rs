@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// How often the client lists are checked for staleness at most, in milliseconds
+pub const DIRECTORY_CHECK_INTERVAL_MS: u128 = 1000;
+
+/// The clients of all the registered servers, in one view
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Directory {
+    /// Key: client id, value: the registered servers listing it
+    pub clients: BTreeMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl Directory {
+    /// Merge the client lists of `servers`, leaving out `client_id`
+    #[must_use]
+    pub fn merge(
+        lists: &HashMap<NodeId, Vec<NodeId>>,
+        servers: &[NodeId],
+        client_id: NodeId,
+    ) -> Self {
+        let mut clients = BTreeMap::<NodeId, BTreeSet<NodeId>>::new();
+        for server_id in servers {
+            for peer_id in lists.get(server_id).into_iter().flatten() {
+                if *peer_id != client_id {
+                    clients.entry(*peer_id).or_default().insert(*server_id);
+                }
+            }
+        }
+        Directory { clients }
+    }
+
+    /// The servers through which a client can be reached
+    #[must_use]
+    pub fn servers(&self, peer_id: NodeId) -> Option<&BTreeSet<NodeId>> {
+        self.clients.get(&peer_id)
+    }
+
+    /// The clients that can be reached through several servers, sorted
+    #[must_use]
+    pub fn multi_server(&self) -> Vec<NodeId> {
+        self.clients
+            .iter()
+            .filter(|(_, servers)| servers.len() > 1)
+            .map(|(peer_id, _)| *peer_id)
+            .collect()
+    }
+}
+
+/// Decides which client lists are requested again in the background, for the presence and the directory:
+/// the missing ones, and the ones older than `max_age_ms`
+#[derive(Debug, Default)]
+pub struct ListRefresher {
+    /// `None` if the lists are only requested on demand
+    max_age_ms: Option<u64>,
+    /// Key: `server_id`, value: when its list was last requested
+    requested: HashMap<NodeId, u128>,
+    last_check: u128,
+}
+
+impl ListRefresher {
+    #[must_use]
+    pub fn new(max_age_ms: Option<u64>) -> Self {
+        ListRefresher {
+            max_age_ms,
+            requested: HashMap::new(),
+            last_check: 0,
+        }
+    }
+
+    /// Change how old a list can be, checked from the next call to `due`
+    pub fn set_max_age(&mut self, max_age_ms: Option<u64>) {
+        self.max_age_ms = max_age_ms;
+        self.last_check = 0;
+    }
+
+    /// The list of a server was requested, by the refresher or not
+    pub fn on_requested(&mut self, server_id: NodeId, now: u128) {
+        self.requested.insert(server_id, now);
+    }
+
+    /// The servers whose list should be requested now, sorted. A list is stale if it was
+    /// received or requested more than `max_age_ms` ago.
+    /// Checked every `DIRECTORY_CHECK_INTERVAL_MS`, or every `max_age_ms` if it's shorter
+    pub fn due(
+        &mut self,
+        servers: &[NodeId],
+        updated: &HashMap<NodeId, u128>,
+        now: u128,
+    ) -> Vec<NodeId> {
+        let Some(max_age_ms) = self.max_age_ms else {
+            return vec![];
+        };
+        let max_age = u128::from(max_age_ms);
+        if self.last_check + DIRECTORY_CHECK_INTERVAL_MS.min(max_age) > now {
+            return vec![];
+        }
+        self.last_check = now;
+        let mut due = servers
+            .iter()
+            .filter(|server_id| {
+                let last = updated
+                    .get(*server_id)
+                    .into_iter()
+                    .chain(self.requested.get(*server_id))
+                    .max();
+                !matches!(last, Some(last) if last + max_age > now)
+            })
+            .copied()
+            .collect::<Vec<NodeId>>();
+        due.sort_unstable();
+        for server_id in &due {
+            self.requested.insert(*server_id, now);
+        }
+        due
+    }
+}
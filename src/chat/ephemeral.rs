@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use super::history::HistoryEntry;
+
+/// Whether a message with this expiry, if any, is expired
+#[must_use]
+pub fn is_expired(expires_at: Option<u128>, now: u128) -> bool {
+    expires_at.is_some_and(|expires_at| expires_at <= now)
+}
+
+/// A message in the history, removed when its TTL elapses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EphemeralMessage {
+    pub id: u64,
+    /// Its entry in the history
+    pub entry: HistoryEntry,
+    /// Milliseconds since the UNIX epoch
+    pub expires_at: u128,
+}
+
+/// The ephemeral messages sent and received, until they expire
+#[derive(Debug, Default)]
+pub struct Ephemerals {
+    /// Key: id of a message sent, value: when it was sent and its TTL,
+    /// so it keeps them if it's sent again
+    sent: HashMap<u64, (u128, u64)>,
+    /// Sorted by expiry
+    messages: Vec<EphemeralMessage>,
+}
+
+impl Ephemerals {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A message was sent with a TTL
+    pub fn on_sent(&mut self, id: u64, sent_at: u128, ttl_ms: u64) {
+        self.sent.insert(id, (sent_at, ttl_ms));
+    }
+
+    /// When a message sent was sent, and its TTL. `None` if it isn't ephemeral
+    #[must_use]
+    pub fn ttl(&self, id: u64) -> Option<(u128, u64)> {
+        self.sent.get(&id).copied()
+    }
+
+    /// Follow a message saved in the history, until it expires
+    pub fn track(&mut self, message: EphemeralMessage) {
+        let position = self
+            .messages
+            .partition_point(|tracked| tracked.expires_at <= message.expires_at);
+        self.messages.insert(position, message);
+    }
+
+    /// Remove and return the expired messages, the first expired first
+    pub fn take_expired(&mut self, now: u128) -> Vec<EphemeralMessage> {
+        let count = self
+            .messages
+            .partition_point(|message| message.expires_at <= now);
+        let expired = self
+            .messages
+            .drain(..count)
+            .collect::<Vec<EphemeralMessage>>();
+        for message in &expired {
+            self.sent.remove(&message.id);
+        }
+        expired
+    }
+
+    /// The messages not expired yet, the first to expire first
+    #[must_use]
+    pub fn messages(&self) -> &[EphemeralMessage] {
+        &self.messages
+    }
+}
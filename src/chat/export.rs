@@ -0,0 +1,93 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::history::{ConversationStore, Direction, HistoryEntry};
+
+/// The file formats a conversation can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// One line per message
+    Text,
+    /// An array of `HistoryEntry`
+    Json,
+    /// A standalone page, with a list of the messages
+    Html,
+}
+
+/// Who sent a message, as shown in the exports
+fn sender(entry: &HistoryEntry) -> String {
+    match entry.direction {
+        Direction::Sent => "me".to_string(),
+        Direction::Received => entry.peer_id.to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.chars()
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&#39;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+/// Write messages in a format
+/// # Errors
+/// Returns an error if they couldn't be serialized
+pub fn render(entries: &[HistoryEntry], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Text => Ok(entries.iter().fold(String::new(), |mut text, entry| {
+            let _res = writeln!(
+                text,
+                "[{}] {}: {}",
+                entry.timestamp,
+                sender(entry),
+                entry.text
+            );
+            text
+        })),
+        ExportFormat::Json => serde_json::to_string_pretty(entries).map_err(|err| err.to_string()),
+        ExportFormat::Html => {
+            let mut html = String::from(
+                "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Conversation</title></head>\n<body>\n<ul>\n",
+            );
+            for entry in entries {
+                let _res = writeln!(
+                    html,
+                    "<li data-timestamp=\"{}\"><b>{}</b>: {}</li>",
+                    entry.timestamp,
+                    escape_html(&sender(entry)),
+                    escape_html(&entry.text)
+                );
+            }
+            html.push_str("</ul>\n</body>\n</html>\n");
+            Ok(html)
+        }
+    }
+}
+
+impl ConversationStore {
+    /// Write a conversation to a file, from the oldest message
+    /// # Errors
+    /// Returns an error if the file couldn't be written
+    pub fn export(
+        &self,
+        server_id: NodeId,
+        peer_id: NodeId,
+        format: ExportFormat,
+        path: &Path,
+    ) -> Result<(), String> {
+        let content = render(self.conversation(server_id, peer_id), format)?;
+        fs::write(path, content).map_err(|err| format!("Couldn't write {}: {err}", path.display()))
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use wg_2024::network::NodeId;
+
+/// How often the routes to the registered servers are checked, in milliseconds
+pub const FAILOVER_CHECK_INTERVAL_MS: u128 = 1000;
+
+/// Follows whether the chat servers answer. Only the `Register` and `ClientList` requests are followed:
+/// they always get a response, while a message to a client that isn't registered is dropped without one.
+/// A server that doesn't answer anything for too long after one of them is considered down
+#[derive(Debug)]
+pub struct ServerMonitor {
+    /// Key: `server_id`, value: when the oldest request not answered yet was sent
+    awaiting: HashMap<NodeId, u128>,
+    /// The servers down, sorted
+    down: Vec<NodeId>,
+    timeout_ms: u128,
+}
+
+impl Default for ServerMonitor {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
+
+impl ServerMonitor {
+    #[must_use]
+    pub fn new(timeout_ms: u64) -> Self {
+        ServerMonitor {
+            awaiting: HashMap::new(),
+            down: vec![],
+            timeout_ms: u128::from(timeout_ms),
+        }
+    }
+
+    /// A request was sent to a server
+    pub fn on_request_sent(&mut self, server_id: NodeId, now: u128) {
+        self.awaiting.entry(server_id).or_insert(now);
+    }
+
+    /// A response arrived from a server. Returns true if it was down
+    pub fn on_response(&mut self, server_id: NodeId) -> bool {
+        self.awaiting.remove(&server_id);
+        self.mark_up(server_id)
+    }
+
+    /// The servers that didn't answer in time, and aren't down yet, sorted
+    #[must_use]
+    pub fn timed_out(&self, now: u128) -> Vec<NodeId> {
+        let mut servers = self
+            .awaiting
+            .iter()
+            .filter(|(server_id, since)| {
+                *since + self.timeout_ms <= now && !self.is_down(**server_id)
+            })
+            .map(|(server_id, _)| *server_id)
+            .collect::<Vec<NodeId>>();
+        servers.sort_unstable();
+        servers
+    }
+
+    #[must_use]
+    pub fn is_down(&self, server_id: NodeId) -> bool {
+        self.down.binary_search(&server_id).is_ok()
+    }
+
+    /// Returns false if the server was already down
+    pub fn mark_down(&mut self, server_id: NodeId) -> bool {
+        self.awaiting.remove(&server_id);
+        match self.down.binary_search(&server_id) {
+            Ok(_) => false,
+            Err(position) => {
+                self.down.insert(position, server_id);
+                true
+            }
+        }
+    }
+
+    /// Returns false if the server wasn't down
+    pub fn mark_up(&mut self, server_id: NodeId) -> bool {
+        match self.down.binary_search(&server_id) {
+            Ok(position) => {
+                self.down.remove(position);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The servers down, sorted
+    #[must_use]
+    pub fn down(&self) -> &[NodeId] {
+        &self.down
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// At most `max_messages` from the same client in `interval_ms`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub max_messages: u32,
+    pub interval_ms: u64,
+}
+
+/// What the client accepts from the other clients, saved in the configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SenderPolicy {
+    /// Everything they send is dropped, sorted
+    pub blocked: Vec<NodeId>,
+    /// Their messages are saved in the history, but not reported, sorted
+    pub muted: Vec<NodeId>,
+    /// The messages over the limit are dropped. If `None`, there is no limit
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// What to do with a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Deliver,
+    /// Save it, without reporting it
+    Mute,
+    /// Drop it, the sender is blocked
+    Blocked,
+    /// Drop it, the sender sent too many messages
+    RateLimited,
+}
+
+/// Applies the `SenderPolicy` to the received messages
+#[derive(Debug, Default)]
+pub struct SenderFilter {
+    policy: SenderPolicy,
+    /// When the last messages of every client arrived, within the rate limit interval
+    recent: HashMap<NodeId, VecDeque<u128>>,
+}
+
+fn insert_sorted(list: &mut Vec<NodeId>, client_id: NodeId) -> bool {
+    match list.binary_search(&client_id) {
+        Ok(_) => false,
+        Err(position) => {
+            list.insert(position, client_id);
+            true
+        }
+    }
+}
+
+fn remove_sorted(list: &mut Vec<NodeId>, client_id: NodeId) -> bool {
+    match list.binary_search(&client_id) {
+        Ok(position) => {
+            list.remove(position);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+impl SenderFilter {
+    #[must_use]
+    pub fn new(mut policy: SenderPolicy) -> Self {
+        policy.blocked.sort_unstable();
+        policy.blocked.dedup();
+        policy.muted.sort_unstable();
+        policy.muted.dedup();
+        SenderFilter {
+            policy,
+            recent: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn policy(&self) -> &SenderPolicy {
+        &self.policy
+    }
+
+    #[must_use]
+    pub fn is_blocked(&self, client_id: NodeId) -> bool {
+        self.policy.blocked.binary_search(&client_id).is_ok()
+    }
+
+    #[must_use]
+    pub fn is_muted(&self, client_id: NodeId) -> bool {
+        self.policy.muted.binary_search(&client_id).is_ok()
+    }
+
+    /// Returns false if the client was already blocked
+    pub fn block(&mut self, client_id: NodeId) -> bool {
+        insert_sorted(&mut self.policy.blocked, client_id)
+    }
+
+    /// Returns false if the client wasn't blocked
+    pub fn unblock(&mut self, client_id: NodeId) -> bool {
+        remove_sorted(&mut self.policy.blocked, client_id)
+    }
+
+    /// Returns false if the client was already muted
+    pub fn mute(&mut self, client_id: NodeId) -> bool {
+        insert_sorted(&mut self.policy.muted, client_id)
+    }
+
+    /// Returns false if the client wasn't muted
+    pub fn unmute(&mut self, client_id: NodeId) -> bool {
+        remove_sorted(&mut self.policy.muted, client_id)
+    }
+
+    pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.policy.rate_limit = rate_limit;
+        self.recent.clear();
+    }
+
+    /// Decide what to do with a message from `from` arriving at `now`.
+    /// The messages delivered or muted count for the rate limit
+    pub fn check(&mut self, from: NodeId, now: u128) -> Verdict {
+        if self.is_blocked(from) {
+            return Verdict::Blocked;
+        }
+        if let Some(limit) = self.policy.rate_limit {
+            let recent = self.recent.entry(from).or_default();
+            while recent
+                .front()
+                .is_some_and(|arrival| *arrival + u128::from(limit.interval_ms) <= now)
+            {
+                recent.pop_front();
+            }
+            if recent.len() >= limit.max_messages as usize {
+                return Verdict::RateLimited;
+            }
+            recent.push_back(now);
+        }
+        if self.is_muted(from) {
+            Verdict::Mute
+        } else {
+            Verdict::Deliver
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// A message of a group conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMessage {
+    pub from: NodeId,
+    /// Chosen by the sender, increasing for every message it sends to the group
+    pub seq: u64,
+    /// Milliseconds since the UNIX epoch, when it was sent or received
+    pub timestamp: u128,
+    pub text: String,
+}
+
+/// A group conversation, kept by every member.
+/// The protocol has no groups, so every message is sent to each member on its own
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    pub id: u64,
+    pub name: String,
+    /// Every member, including this client
+    pub members: Vec<NodeId>,
+    /// The sequence number of the next message sent by this client
+    next_seq: u64,
+    /// Key: member, value: the sequence number of the first message this client sent them
+    #[serde(default)]
+    member_since: HashMap<NodeId, u64>,
+    messages: Vec<GroupMessage>,
+}
+
+impl Group {
+    #[must_use]
+    pub fn new(id: u64, name: String, members: Vec<NodeId>) -> Self {
+        let mut group = Group {
+            id,
+            name,
+            members: vec![],
+            next_seq: 0,
+            member_since: HashMap::new(),
+            messages: vec![],
+        };
+        group.set_members(members);
+        group
+    }
+
+    /// Replace the members, sorted and without duplicates.
+    /// The new members get the messages sent from now on
+    pub fn set_members(&mut self, mut members: Vec<NodeId>) {
+        members.sort_unstable();
+        members.dedup();
+        self.member_since
+            .retain(|member, _| members.contains(member));
+        for member in &members {
+            self.member_since.entry(*member).or_insert(self.next_seq);
+        }
+        self.members = members;
+    }
+
+    /// The sequence number of the first message sent to `member`, the ones before it never were
+    #[must_use]
+    pub fn since(&self, member: NodeId) -> u64 {
+        self.member_since
+            .get(&member)
+            .copied()
+            .unwrap_or(self.next_seq)
+    }
+
+    /// Take the sequence number for a new message
+    pub fn take_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
+    /// Add a message to the thread. Returns false if it was already there
+    pub fn insert(&mut self, message: GroupMessage) -> bool {
+        let duplicate = self
+            .messages
+            .iter()
+            .any(|other| other.from == message.from && other.seq == message.seq);
+        if !duplicate {
+            self.messages.push(message);
+        }
+        !duplicate
+    }
+
+    /// The messages of the thread, in the order they were sent or received.
+    /// The messages of every sender are in the order it sent them
+    #[must_use]
+    pub fn messages(&self) -> &[GroupMessage] {
+        &self.messages
+    }
+}
+
+/// The groups the client is a member of
+#[derive(Debug, Default)]
+pub struct Groups {
+    groups: HashMap<u64, Group>,
+}
+
+impl Groups {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn get(&self, group_id: u64) -> Option<&Group> {
+        self.groups.get(&group_id)
+    }
+
+    pub fn get_mut(&mut self, group_id: u64) -> Option<&mut Group> {
+        self.groups.get_mut(&group_id)
+    }
+
+    /// Add a group, or update the name and the members of a known one
+    pub fn upsert(&mut self, group_id: u64, name: String, members: Vec<NodeId>) -> &mut Group {
+        let group = self
+            .groups
+            .entry(group_id)
+            .or_insert_with(|| Group::new(group_id, name.clone(), vec![]));
+        group.name = name;
+        group.set_members(members);
+        group
+    }
+
+    pub fn remove(&mut self, group_id: u64) -> Option<Group> {
+        self.groups.remove(&group_id)
+    }
+
+    /// The groups, sorted by id
+    #[must_use]
+    pub fn list(&self) -> Vec<&Group> {
+        let mut groups = self.groups.values().collect::<Vec<&Group>>();
+        groups.sort_unstable_by_key(|group| group.id);
+        groups
+    }
+}
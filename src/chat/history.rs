@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// Whether a message was sent or received by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A message of a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub server_id: NodeId,
+    /// The other client of the conversation
+    pub peer_id: NodeId,
+    pub direction: Direction,
+    /// Milliseconds since the UNIX epoch
+    pub timestamp: u128,
+    pub text: String,
+    /// The id of an ephemeral message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    /// When an ephemeral message expires, in milliseconds since the UNIX epoch.
+    /// Saved so it's still removed after a restart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u128>,
+}
+
+/// The messages exchanged by the client, grouped by conversation (`server_id`, `peer_id`).
+/// If a path is set, every message is appended to it as a JSON line
+#[derive(Debug, Default)]
+pub struct ConversationStore {
+    conversations: HashMap<(NodeId, NodeId), Vec<HistoryEntry>>,
+    path: Option<PathBuf>,
+}
+
+impl ConversationStore {
+    /// A store that only keeps the messages in memory
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the store saved at `path`, which is created when the first message is recorded.
+    /// Invalid lines are skipped
+    /// # Errors
+    /// Returns an error if the file exists but couldn't be read
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut store = ConversationStore {
+            conversations: HashMap::new(),
+            path: Some(path.to_path_buf()),
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(store),
+            Err(err) => return Err(format!("Couldn't read {}: {err}", path.display())),
+        };
+        for line in content.lines() {
+            if let Ok(entry) = serde_json::from_str::<HistoryEntry>(line) {
+                store.insert(entry);
+            }
+        }
+        Ok(store)
+    }
+
+    /// Add a message to its conversation, and append it to the file
+    /// # Errors
+    /// Returns an error if the message couldn't be saved. It's kept in memory anyway
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<(), String> {
+        let result = match &self.path {
+            Some(path) => Self::append(path, &entry),
+            None => Ok(()),
+        };
+        self.insert(entry);
+        result
+    }
+
+    /// Remove a message from its conversation, and rewrite the file without it.
+    /// Returns false if the message isn't in the store
+    /// # Errors
+    /// Returns an error if the file couldn't be rewritten. The message is removed from memory anyway
+    pub fn remove(&mut self, entry: &HistoryEntry) -> Result<bool, String> {
+        let key = (entry.server_id, entry.peer_id);
+        let Some(entries) = self.conversations.get_mut(&key) else {
+            return Ok(false);
+        };
+        let Some(position) = entries.iter().position(|saved| saved == entry) else {
+            return Ok(false);
+        };
+        entries.remove(position);
+        if entries.is_empty() {
+            self.conversations.remove(&key);
+        }
+        match &self.path {
+            Some(path) => self.rewrite(path).map(|()| true),
+            None => Ok(true),
+        }
+    }
+
+    /// All the messages of a conversation, from the oldest
+    #[must_use]
+    pub fn conversation(&self, server_id: NodeId, peer_id: NodeId) -> &[HistoryEntry] {
+        self.conversations
+            .get(&(server_id, peer_id))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// A page of `page_size` messages of a conversation, from the oldest.
+    /// Page 0 contains the most recent messages, page 1 the ones before them, and so on
+    #[must_use]
+    pub fn page(
+        &self,
+        server_id: NodeId,
+        peer_id: NodeId,
+        page: usize,
+        page_size: usize,
+    ) -> Vec<HistoryEntry> {
+        let entries = self.conversation(server_id, peer_id);
+        let end = entries.len().saturating_sub(page.saturating_mul(page_size));
+        let start = end.saturating_sub(page_size);
+        entries[start..end].to_vec()
+    }
+
+    /// The conversations, as (`server_id`, `peer_id`), sorted
+    #[must_use]
+    pub fn conversations(&self) -> Vec<(NodeId, NodeId)> {
+        let mut conversations = self.conversations.keys().copied().collect::<Vec<_>>();
+        conversations.sort_unstable();
+        conversations
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn insert(&mut self, entry: HistoryEntry) {
+        self.conversations
+            .entry((entry.server_id, entry.peer_id))
+            .or_default()
+            .push(entry);
+    }
+
+    /// Write all the messages to the file again, from the oldest
+    fn rewrite(&self, path: &Path) -> Result<(), String> {
+        let mut entries = self.conversations.values().flatten().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.timestamp);
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry).map_err(|err| err.to_string())?);
+            content.push('\n');
+        }
+        fs::write(path, content).map_err(|err| format!("Couldn't write {}: {err}", path.display()))
+    }
+
+    fn append(path: &Path, entry: &HistoryEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry).map_err(|err| err.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("Couldn't open {}: {err}", path.display()))?;
+        writeln!(file, "{line}").map_err(|err| format!("Couldn't write {}: {err}", path.display()))
+    }
+}
@@ -0,0 +1,49 @@
+pub mod activity;
+pub mod auto_join;
+pub mod behavior;
+pub mod broadcast;
+pub mod config;
+pub mod controller;
+pub mod directory;
+pub mod ephemeral;
+pub mod export;
+pub mod failover;
+pub mod filter;
+pub mod groups;
+pub mod history;
+pub mod ordering;
+pub mod outbox;
+pub mod payload;
+pub mod presence;
+pub mod receipts;
+pub mod registration;
+pub mod routing;
+pub mod search;
+pub mod transfer;
+pub mod unread;
+
+pub use activity::{Activity, ActivityTracker};
+pub use auto_join::AutoJoinPolicy;
+pub use behavior::{BehaviorAction, BehaviorContext, ChatBehavior, Chatter, Echo, PingPong};
+pub use broadcast::{Broadcast, BroadcastResult, Broadcasts};
+pub use config::ChatConfig;
+pub use controller::{ChatCommand, ChatEvent};
+pub use directory::{Directory, ListRefresher};
+pub use ephemeral::{EphemeralMessage, Ephemerals};
+pub use export::ExportFormat;
+pub use failover::ServerMonitor;
+pub use filter::{RateLimit, SenderFilter, SenderPolicy, Verdict};
+pub use groups::{Group, GroupMessage, Groups};
+pub use history::{ConversationStore, Direction, HistoryEntry};
+pub use ordering::{Ordered, ReorderBuffer, Sequencer};
+pub use outbox::{Outbox, QueuedMessage};
+pub use payload::{ChatPayload, MessageContent};
+pub use presence::{diff_client_lists, PresenceChange};
+pub use receipts::{DeliveryStatus, ReceiptTracker, TrackedMessage};
+pub use registration::{RegistrationState, RegistrationTimeout, Registrations};
+pub use routing::{RoutedMessage, RoutedMessages};
+pub use search::SearchQuery;
+pub use transfer::{
+    FileMessage, IncomingTransfer, OutgoingTransfer, TransferProgress, TransferState, Transfers,
+};
+pub use unread::{ConversationSummary, UnreadCounters};
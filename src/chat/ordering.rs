@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use wg_2024::network::NodeId;
+
+/// Numbers the messages sent to every peer, starting from 0 in every session.
+/// The session is chosen when the client starts, so the recipients know when the numbering restarts
+#[derive(Debug)]
+pub struct Sequencer {
+    session: u64,
+    /// Key: `peer_id`, value: the sequence number of the next message
+    next: HashMap<NodeId, u64>,
+    /// Key: message id, value: (`peer_id`, its sequence number)
+    assigned: HashMap<u64, (NodeId, u64)>,
+    /// Key: `peer_id`, value: the sequence numbers of the messages that can still be sent again
+    outstanding: HashMap<NodeId, BTreeSet<u64>>,
+}
+
+impl Default for Sequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequencer {
+    #[must_use]
+    pub fn new() -> Self {
+        Sequencer {
+            session: rand::random(),
+            next: HashMap::new(),
+            assigned: HashMap::new(),
+            outstanding: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn session(&self) -> u64 {
+        self.session
+    }
+
+    /// The sequence number of the message `id` to `peer_id`, the same every time it's sent
+    pub fn seq_for(&mut self, peer_id: NodeId, id: u64) -> u64 {
+        if let Some((_, seq)) = self.assigned.get(&id) {
+            return *seq;
+        }
+        let next = self.next.entry(peer_id).or_default();
+        let seq = *next;
+        *next += 1;
+        self.assigned.insert(id, (peer_id, seq));
+        self.outstanding.entry(peer_id).or_default().insert(seq);
+        seq
+    }
+
+    /// The first message `peer_id` has to wait for: the oldest one that can still be sent again.
+    /// The ones before it were delivered or given up
+    #[must_use]
+    pub fn since(&self, peer_id: NodeId) -> u64 {
+        match self.outstanding.get(&peer_id).and_then(BTreeSet::first) {
+            Some(seq) => *seq,
+            None => self.next.get(&peer_id).copied().unwrap_or_default(),
+        }
+    }
+
+    /// The message `id` won't be sent again, forget its sequence number
+    pub fn forget(&mut self, id: u64) {
+        let Some((peer_id, seq)) = self.assigned.remove(&id) else {
+            return;
+        };
+        if let Some(outstanding) = self.outstanding.get_mut(&peer_id) {
+            outstanding.remove(&seq);
+            if outstanding.is_empty() {
+                self.outstanding.remove(&peer_id);
+            }
+        }
+    }
+}
+
+/// What happened to the messages given to a `ReorderBuffer`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ordered<T> {
+    /// The next message from `from`, in order
+    Deliver { from: NodeId, message: T },
+    /// The messages from `first` to `last` (included) never arrived, and are skipped
+    Gap { from: NodeId, first: u64, last: u64 },
+    /// The message was already received
+    Duplicate { from: NodeId, message: T },
+}
+
+/// The messages received from a sender in the current session
+#[derive(Debug)]
+struct SenderQueue<T> {
+    session: u64,
+    /// The sequence number of the next message to deliver
+    next: u64,
+    /// The messages received before the ones preceding them, with when they arrived
+    pending: BTreeMap<u64, (u128, T)>,
+}
+
+/// Puts the messages of every sender back in order.
+/// A missing message is waited for until `window` messages after it arrived, or for `timeout_ms`,
+/// then it's reported as a gap and the following messages are delivered.
+/// The messages before the `since` of the sender aren't waited for: it already gave up sending them
+#[derive(Debug)]
+pub struct ReorderBuffer<T> {
+    senders: HashMap<NodeId, SenderQueue<T>>,
+    window: u64,
+    timeout_ms: u128,
+}
+
+impl<T> Default for ReorderBuffer<T> {
+    fn default() -> Self {
+        Self::new(16, 2000)
+    }
+}
+
+impl<T> ReorderBuffer<T> {
+    #[must_use]
+    pub fn new(window: u64, timeout_ms: u64) -> Self {
+        ReorderBuffer {
+            senders: HashMap::new(),
+            window: window.max(1),
+            timeout_ms: u128::from(timeout_ms),
+        }
+    }
+
+    /// A message arrived. Returns the messages that can be delivered now, in order,
+    /// with the gaps before them
+    pub fn receive(
+        &mut self,
+        from: NodeId,
+        session: u64,
+        seq: u64,
+        since: u64,
+        now: u128,
+        message: T,
+    ) -> Vec<Ordered<T>> {
+        // The first message of a sender, or the sender restarted: the numbering starts from `since`
+        let queue = self.senders.entry(from).or_insert_with(|| SenderQueue {
+            session,
+            next: since,
+            pending: BTreeMap::new(),
+        });
+        if queue.session != session {
+            *queue = SenderQueue {
+                session,
+                next: since,
+                pending: BTreeMap::new(),
+            };
+        }
+        let mut ordered = vec![];
+        // The sender gave up the messages before `since`, deliver the ones that arrived
+        if since > queue.next {
+            let later = queue.pending.split_off(&since);
+            for (_, (_, message)) in std::mem::replace(&mut queue.pending, later) {
+                ordered.push(Ordered::Deliver { from, message });
+            }
+            queue.next = since;
+        }
+        if seq < queue.next || queue.pending.contains_key(&seq) {
+            ordered.push(Ordered::Duplicate { from, message });
+            return ordered;
+        }
+        queue.pending.insert(seq, (now, message));
+
+        Self::deliver_ready(from, queue, &mut ordered);
+        // Too many messages after the missing one
+        while queue
+            .pending
+            .last_key_value()
+            .is_some_and(|(last, _)| *last >= queue.next + self.window)
+        {
+            Self::skip_gap(from, queue, &mut ordered);
+        }
+        ordered
+    }
+
+    /// Whether `receive` would drop this message as already received
+    #[must_use]
+    pub fn is_duplicate(&self, from: NodeId, session: u64, seq: u64, since: u64) -> bool {
+        match self.senders.get(&from) {
+            Some(queue) if queue.session == session => {
+                seq < queue.next.max(since) || queue.pending.contains_key(&seq)
+            }
+            _ => seq < since,
+        }
+    }
+
+    /// Skip the missing messages that were waited for longer than the timeout.
+    /// Returns the gaps and the messages that can be delivered now
+    pub fn check_timeouts(&mut self, now: u128) -> Vec<Ordered<T>> {
+        let mut senders = self.senders.keys().copied().collect::<Vec<NodeId>>();
+        senders.sort_unstable();
+        let mut ordered = vec![];
+        for from in senders {
+            let Some(queue) = self.senders.get_mut(&from) else {
+                continue;
+            };
+            while queue
+                .pending
+                .values()
+                .any(|(received_at, _)| received_at + self.timeout_ms <= now)
+            {
+                Self::skip_gap(from, queue, &mut ordered);
+            }
+        }
+        ordered
+    }
+
+    /// Deliver the pending messages that follow the last delivered one
+    fn deliver_ready(from: NodeId, queue: &mut SenderQueue<T>, ordered: &mut Vec<Ordered<T>>) {
+        while let Some((_, message)) = queue.pending.remove(&queue.next) {
+            queue.next += 1;
+            ordered.push(Ordered::Deliver { from, message });
+        }
+    }
+
+    /// Give up on the messages before the first pending one, and deliver from there
+    fn skip_gap(from: NodeId, queue: &mut SenderQueue<T>, ordered: &mut Vec<Ordered<T>>) {
+        let Some(first_pending) = queue.pending.keys().next().copied() else {
+            return;
+        };
+        if first_pending > queue.next {
+            ordered.push(Ordered::Gap {
+                from,
+                first: queue.next,
+                last: first_pending - 1,
+            });
+            queue.next = first_pending;
+        }
+        Self::deliver_ready(from, queue, ordered);
+    }
+}
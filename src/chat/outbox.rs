@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// A message held until its recipient appears in the client list of the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedMessage {
+    pub id: u64,
+    pub server_id: NodeId,
+    pub peer_id: NodeId,
+    pub text: String,
+    /// Milliseconds since the UNIX epoch
+    pub queued_at: u128,
+}
+
+/// The messages to clients that weren't registered to the server when they were sent.
+/// They are dropped after `expiry_ms`, if set
+#[derive(Debug, Default)]
+pub struct Outbox {
+    messages: Vec<QueuedMessage>,
+    expiry_ms: Option<u64>,
+}
+
+impl Outbox {
+    #[must_use]
+    pub fn new(expiry_ms: Option<u64>) -> Self {
+        Outbox {
+            messages: vec![],
+            expiry_ms,
+        }
+    }
+
+    pub fn push(&mut self, message: QueuedMessage) {
+        self.messages.push(message);
+    }
+
+    /// Remove and return the messages for the clients of the list of a server, oldest first
+    pub fn take_deliverable(
+        &mut self,
+        server_id: NodeId,
+        clients: &[NodeId],
+    ) -> Vec<QueuedMessage> {
+        let (deliverable, held) = self.messages.drain(..).partition(|message| {
+            message.server_id == server_id && clients.contains(&message.peer_id)
+        });
+        self.messages = held;
+        deliverable
+    }
+
+    /// Remove and return the messages queued for longer than the expiry
+    pub fn expire(&mut self, now: u128) -> Vec<QueuedMessage> {
+        let Some(expiry_ms) = self.expiry_ms else {
+            return vec![];
+        };
+        let (expired, held) = self
+            .messages
+            .drain(..)
+            .partition(|message| message.queued_at + u128::from(expiry_ms) <= now);
+        self.messages = held;
+        expired
+    }
+
+    /// Queue the messages for `from` on another server instead. Returns how many were moved
+    pub fn move_server(&mut self, from: NodeId, to: NodeId) -> usize {
+        let mut moved = 0;
+        for message in &mut self.messages {
+            if message.server_id == from {
+                message.server_id = to;
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// The queued messages, oldest first
+    #[must_use]
+    pub fn messages(&self) -> &[QueuedMessage] {
+        &self.messages
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
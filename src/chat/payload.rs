@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::activity::Activity;
+use super::transfer::FileMessage;
+
+/// The content of the `message` field of a chat message, as exchanged between two clients.
+/// The server relays it without looking inside
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatPayload {
+    /// A text message, `id` is chosen by the sender
+    Text { id: u64, text: String },
+    /// Sent back automatically by the recipient of the message `id`
+    Receipt { id: u64 },
+    /// A message that isn't plain text, `id` is chosen by the sender
+    Content { id: u64, content: MessageContent },
+    /// A `Text`, a `Content` or an `Ephemeral` one, with its position among the messages from the sender to the recipient.
+    /// `seq` starts from 0 in every `session` of the sender. The messages before `since` were delivered
+    /// or given up by the sender, so the recipient doesn't wait for them
+    Sequenced {
+        session: u64,
+        seq: u64,
+        #[serde(default)]
+        since: u64,
+        payload: Box<ChatPayload>,
+    },
+    /// A message of a group, sent to every member.
+    /// `seq` is chosen by the sender, increasing for every message it sends to the group in its `session`.
+    /// The messages before `since` were sent before the recipient joined the group
+    GroupText {
+        group_id: u64,
+        #[serde(default)]
+        session: u64,
+        seq: u64,
+        #[serde(default)]
+        since: u64,
+        text: String,
+    },
+    /// The name and the members of a group, sent to every member (old and new) when they change.
+    /// A client that isn't in `members` anymore leaves the group
+    GroupInfo {
+        group_id: u64,
+        name: String,
+        members: Vec<NodeId>,
+    },
+    /// A message of a file transfer
+    File(FileMessage),
+    /// A `Text` or a `Content` that expires `ttl_ms` after `sent_at` (milliseconds since the UNIX epoch).
+    /// The recipient removes it from the history then, and discards it if it arrives later
+    Ephemeral {
+        sent_at: u128,
+        ttl_ms: u64,
+        payload: Box<ChatPayload>,
+    },
+    /// Whether the sender is typing to the recipient. Never saved in the history
+    Activity(Activity),
+}
+
+/// What a one-to-one message contains
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageContent {
+    Text(String),
+    /// Any data, `mime_type` says how to read it
+    Binary {
+        mime_type: String,
+        data: Vec<u8>,
+    },
+    /// A text answering the message `reply_to`
+    Reply {
+        reply_to: u64,
+        text: String,
+    },
+    /// A reaction, like an emoji, to the message `message_id`
+    Reaction {
+        message_id: u64,
+        reaction: String,
+    },
+}
+
+impl MessageContent {
+    /// A readable version of the content, for the controller and the history
+    #[must_use]
+    pub fn summary(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Binary { mime_type, data } => {
+                format!("[{mime_type}, {} bytes]", data.len())
+            }
+            MessageContent::Reply { reply_to, text } => format!("[reply to {reply_to}] {text}"),
+            MessageContent::Reaction {
+                message_id,
+                reaction,
+            } => format!("[reaction to {message_id}] {reaction}"),
+        }
+    }
+}
+
+impl ChatPayload {
+    /// The payload of a message. The text is sent as `Text`, so the clients that only know it can read it
+    #[must_use]
+    pub fn message(id: u64, content: MessageContent) -> Self {
+        match content {
+            MessageContent::Text(text) => ChatPayload::Text { id, text },
+            content => ChatPayload::Content { id, content },
+        }
+    }
+
+    /// The payload without its sequence number, if it has one
+    #[must_use]
+    pub fn unsequenced(self) -> ChatPayload {
+        match self {
+            ChatPayload::Sequenced { payload, .. } => *payload,
+            payload => payload,
+        }
+    }
+
+    /// The payload without its TTL, with when it expires if it's `Ephemeral`
+    #[must_use]
+    pub fn into_expiring(self) -> (ChatPayload, Option<u128>) {
+        match self {
+            ChatPayload::Ephemeral {
+                sent_at,
+                ttl_ms,
+                payload,
+            } => (*payload, Some(sent_at.saturating_add(u128::from(ttl_ms)))),
+            payload => (payload, None),
+        }
+    }
+
+    /// The id and the content of a `Text` or a `Content`, the payload itself otherwise
+    /// # Errors
+    /// Returns the payload if it isn't a message
+    pub fn into_message(self) -> Result<(u64, MessageContent), ChatPayload> {
+        match self {
+            ChatPayload::Text { id, text } => Ok((id, MessageContent::Text(text))),
+            ChatPayload::Content { id, content } => Ok((id, content)),
+            payload => Err(payload),
+        }
+    }
+
+    #[must_use]
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parse a received message. Returns `None` if it's not a payload,
+    /// which is the case for the plain text sent by older clients
+    #[must_use]
+    pub fn decode(raw_content: &str) -> Option<Self> {
+        serde_json::from_str(raw_content).ok()
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// Whether a client appeared in or disappeared from the client list of a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresenceChange {
+    Joined,
+    Left,
+}
+
+/// The changes between two consecutive client lists of a server, sorted by client id
+#[must_use]
+pub fn diff_client_lists(old: &[NodeId], new: &[NodeId]) -> Vec<(NodeId, PresenceChange)> {
+    let mut changes = new
+        .iter()
+        .filter(|client_id| !old.contains(client_id))
+        .map(|client_id| (*client_id, PresenceChange::Joined))
+        .chain(
+            old.iter()
+                .filter(|client_id| !new.contains(client_id))
+                .map(|client_id| (*client_id, PresenceChange::Left)),
+        )
+        .collect::<Vec<(NodeId, PresenceChange)>>();
+    changes.sort_unstable_by_key(|(client_id, _)| *client_id);
+    changes.dedup();
+    changes
+}
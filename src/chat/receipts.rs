@@ -0,0 +1,212 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// Where a sent message is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    /// Held in the outbox, the recipient isn't registered to the server
+    Queued,
+    /// Dropped from the outbox before the recipient registered
+    Expired,
+    /// Sent to the server, no answer yet
+    Pending,
+    /// The server answered with `MessageSent`
+    Accepted,
+    /// The recipient sent back a receipt
+    Delivered,
+    /// Sent with `send_to`, no shared server delivered it
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// Whether the message won't be sent again
+    #[must_use]
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            DeliveryStatus::Expired | DeliveryStatus::Delivered | DeliveryStatus::Failed
+        )
+    }
+}
+
+/// How many messages with a final status are remembered, the oldest ones are forgotten
+pub const FINISHED_RETAINED: usize = 1024;
+
+/// A message sent by the client, with its delivery status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedMessage {
+    pub id: u64,
+    pub server_id: NodeId,
+    pub peer_id: NodeId,
+    pub status: DeliveryStatus,
+}
+
+/// Follow the status of the messages sent by the client.
+/// The `MessageSent` responses don't say which message they refer to,
+/// so they are matched with the messages sent to the same server in order
+#[derive(Debug, Default)]
+pub struct ReceiptTracker {
+    messages: HashMap<u64, TrackedMessage>,
+    /// Key: `server_id`, value: the messages waiting for a `MessageSent`, oldest first, at most
+    /// `FINISHED_RETAINED`. `None` is a payload without a status, also answered with a `MessageSent`
+    awaiting_acceptance: HashMap<NodeId, VecDeque<Option<u64>>>,
+    /// The messages that got a final status, oldest first
+    finished: VecDeque<u64>,
+}
+
+impl ReceiptTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A message was sent, it's pending
+    pub fn on_message_sent(&mut self, id: u64, server_id: NodeId, peer_id: NodeId) {
+        self.messages.insert(
+            id,
+            TrackedMessage {
+                id,
+                server_id,
+                peer_id,
+                status: DeliveryStatus::Pending,
+            },
+        );
+        self.await_acceptance(server_id, Some(id));
+    }
+
+    /// A payload without a delivery status (a receipt, an activity, a group or file message)
+    /// was sent through a server
+    pub fn on_untracked_sent(&mut self, server_id: NodeId) {
+        self.await_acceptance(server_id, None);
+    }
+
+    fn await_acceptance(&mut self, server_id: NodeId, id: Option<u64>) {
+        let awaiting = self.awaiting_acceptance.entry(server_id).or_default();
+        awaiting.push_back(id);
+        // A server that never answers doesn't make the queue grow forever
+        if awaiting.len() > FINISHED_RETAINED {
+            awaiting.pop_front();
+        }
+    }
+
+    /// A `MessageSent` arrived from a server.
+    /// Returns the message that became accepted, if the response refers to a pending message
+    pub fn on_accepted(&mut self, server_id: NodeId) -> Option<TrackedMessage> {
+        let id = self
+            .awaiting_acceptance
+            .get_mut(&server_id)?
+            .pop_front()??;
+        let message = self.messages.get_mut(&id)?;
+        // The receipt can arrive before the response of the server
+        if message.status != DeliveryStatus::Pending || message.server_id != server_id {
+            return None;
+        }
+        message.status = DeliveryStatus::Accepted;
+        Some(message.clone())
+    }
+
+    /// A server went down, its `MessageSent` responses won't arrive
+    pub fn forget_server(&mut self, server_id: NodeId) {
+        self.awaiting_acceptance.remove(&server_id);
+    }
+
+    /// A message was put in the outbox, it isn't sent yet
+    pub fn on_message_queued(&mut self, id: u64, server_id: NodeId, peer_id: NodeId) {
+        self.messages.insert(
+            id,
+            TrackedMessage {
+                id,
+                server_id,
+                peer_id,
+                status: DeliveryStatus::Queued,
+            },
+        );
+    }
+
+    /// A queued message expired. Returns it, if it was still queued
+    pub fn on_expired(&mut self, id: u64) -> Option<TrackedMessage> {
+        let message = self.messages.get_mut(&id)?;
+        if message.status != DeliveryStatus::Queued {
+            return None;
+        }
+        message.status = DeliveryStatus::Expired;
+        let message = message.clone();
+        self.on_finished(id);
+        Some(message)
+    }
+
+    /// No server delivered a message. Returns it, if it wasn't delivered
+    pub fn on_failed(&mut self, id: u64) -> Option<TrackedMessage> {
+        let message = self.messages.get_mut(&id)?;
+        if message.status == DeliveryStatus::Delivered {
+            return None;
+        }
+        let was_final = message.status.is_final();
+        message.status = DeliveryStatus::Failed;
+        let message = message.clone();
+        if !was_final {
+            self.on_finished(id);
+        }
+        Some(message)
+    }
+
+    /// A receipt arrived from `peer_id`.
+    /// Returns the message that became delivered, if the receipt refers to a message sent to that peer
+    pub fn on_delivered(&mut self, peer_id: NodeId, id: u64) -> Option<TrackedMessage> {
+        let message = self.messages.get_mut(&id)?;
+        if message.peer_id != peer_id || message.status == DeliveryStatus::Delivered {
+            return None;
+        }
+        let was_final = message.status.is_final();
+        message.status = DeliveryStatus::Delivered;
+        let message = message.clone();
+        if !was_final {
+            self.on_finished(id);
+        }
+        Some(message)
+    }
+
+    /// The messages sent through a server and still waiting for their receipt, sorted
+    #[must_use]
+    pub fn pending_through(&self, server_id: NodeId) -> Vec<u64> {
+        let mut pending = self
+            .messages
+            .values()
+            .filter(|message| {
+                message.server_id == server_id
+                    && matches!(
+                        message.status,
+                        DeliveryStatus::Pending | DeliveryStatus::Accepted
+                    )
+            })
+            .map(|message| message.id)
+            .collect::<Vec<u64>>();
+        pending.sort_unstable();
+        pending
+    }
+
+    /// A message got a final status: forget the oldest finished messages beyond `FINISHED_RETAINED`
+    fn on_finished(&mut self, id: u64) {
+        self.finished.push_back(id);
+        while self.finished.len() > FINISHED_RETAINED {
+            let Some(oldest) = self.finished.pop_front() else {
+                break;
+            };
+            // Unless it was sent again meanwhile
+            if self
+                .messages
+                .get(&oldest)
+                .is_some_and(|message| message.status.is_final())
+            {
+                self.messages.remove(&oldest);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, id: u64) -> Option<&TrackedMessage> {
+        self.messages.get(&id)
+    }
+}
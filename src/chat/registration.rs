@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// The registration of the client to a chat server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegistrationState {
+    Unregistered,
+    /// Waiting for `ClientRegistered`, `since` is when the last request was sent
+    Pending {
+        since: u128,
+        attempts: u32,
+    },
+    Registered,
+    /// No answer after all the attempts
+    Failed,
+}
+
+/// What to do after a registration timed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistrationTimeout {
+    /// Send the registration request again
+    Retry(NodeId),
+    /// All the attempts are used, the registration failed
+    Failed(NodeId),
+}
+
+/// The registration state of the client for every chat server
+#[derive(Debug)]
+pub struct Registrations {
+    servers: HashMap<NodeId, RegistrationState>,
+    timeout_ms: u128,
+    max_attempts: u32,
+}
+
+impl Default for Registrations {
+    fn default() -> Self {
+        Self::new(2000, 3)
+    }
+}
+
+impl Registrations {
+    #[must_use]
+    pub fn new(timeout_ms: u64, max_attempts: u32) -> Self {
+        Registrations {
+            servers: HashMap::new(),
+            timeout_ms: u128::from(timeout_ms),
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// The state of the registration to a server, `Unregistered` if unknown
+    #[must_use]
+    pub fn state(&self, server_id: NodeId) -> RegistrationState {
+        self.servers
+            .get(&server_id)
+            .copied()
+            .unwrap_or(RegistrationState::Unregistered)
+    }
+
+    /// The states of all the servers the client tried to register to, sorted by server
+    #[must_use]
+    pub fn states(&self) -> Vec<(NodeId, RegistrationState)> {
+        let mut states = self
+            .servers
+            .iter()
+            .map(|(server_id, state)| (*server_id, *state))
+            .collect::<Vec<_>>();
+        states.sort_unstable_by_key(|(server_id, _)| *server_id);
+        states
+    }
+
+    /// A registration request is about to be sent.
+    /// Returns false if the client is already registered, or waiting for the answer
+    pub fn start(&mut self, server_id: NodeId, now: u128) -> bool {
+        match self.state(server_id) {
+            RegistrationState::Registered | RegistrationState::Pending { .. } => false,
+            RegistrationState::Unregistered | RegistrationState::Failed => {
+                self.servers.insert(
+                    server_id,
+                    RegistrationState::Pending {
+                        since: now,
+                        attempts: 1,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    /// `ClientRegistered` arrived from a server. Returns false if the client was already registered
+    pub fn on_registered(&mut self, server_id: NodeId) -> bool {
+        self.servers
+            .insert(server_id, RegistrationState::Registered)
+            != Some(RegistrationState::Registered)
+    }
+
+    /// Forget the registration to a server, returns the previous state
+    pub fn unregister(&mut self, server_id: NodeId) -> RegistrationState {
+        self.servers
+            .remove(&server_id)
+            .unwrap_or(RegistrationState::Unregistered)
+    }
+
+    /// Check the pending registrations that timed out
+    pub fn check_timeouts(&mut self, now: u128) -> Vec<RegistrationTimeout> {
+        let mut timeouts = vec![];
+        for (server_id, state) in &mut self.servers {
+            let RegistrationState::Pending { since, attempts } = *state else {
+                continue;
+            };
+            if since + self.timeout_ms > now {
+                continue;
+            }
+            if attempts < self.max_attempts {
+                *state = RegistrationState::Pending {
+                    since: now,
+                    attempts: attempts + 1,
+                };
+                timeouts.push(RegistrationTimeout::Retry(*server_id));
+            } else {
+                *state = RegistrationState::Failed;
+                timeouts.push(RegistrationTimeout::Failed(*server_id));
+            }
+        }
+        timeouts.sort_unstable_by_key(|timeout| match timeout {
+            RegistrationTimeout::Retry(server_id) | RegistrationTimeout::Failed(server_id) => {
+                *server_id
+            }
+        });
+        timeouts
+    }
+}
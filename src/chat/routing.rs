@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+/// A message sent with `send_to`, which the client picks a server for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutedMessage {
+    pub id: u64,
+    pub peer_id: NodeId,
+    pub text: String,
+    /// The server it was last sent through, `None` while waiting for the client lists
+    pub server_id: Option<NodeId>,
+    /// The servers it was sent through, in order
+    pub tried: Vec<NodeId>,
+    /// When it was last sent, or when the client lists were requested
+    pub since: u128,
+}
+
+/// The messages sent with `send_to` that aren't delivered yet.
+/// A message with no receipt after `timeout_ms` is sent through the next shared server
+#[derive(Debug)]
+pub struct RoutedMessages {
+    messages: HashMap<u64, RoutedMessage>,
+    timeout_ms: u128,
+}
+
+impl Default for RoutedMessages {
+    fn default() -> Self {
+        Self::new(5000)
+    }
+}
+
+impl RoutedMessages {
+    #[must_use]
+    pub fn new(timeout_ms: u64) -> Self {
+        RoutedMessages {
+            messages: HashMap::new(),
+            timeout_ms: u128::from(timeout_ms),
+        }
+    }
+
+    pub fn insert(&mut self, message: RoutedMessage) {
+        self.messages.insert(message.id, message);
+    }
+
+    #[must_use]
+    pub fn get(&self, id: u64) -> Option<&RoutedMessage> {
+        self.messages.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut RoutedMessage> {
+        self.messages.get_mut(&id)
+    }
+
+    /// Stop following a message, because it was delivered or it failed
+    pub fn remove(&mut self, id: u64) -> Option<RoutedMessage> {
+        self.messages.remove(&id)
+    }
+
+    /// The messages waiting for a client list with their recipient, sorted by id
+    #[must_use]
+    pub fn unresolved(&self) -> Vec<u64> {
+        let mut ids = self
+            .messages
+            .values()
+            .filter(|message| message.server_id.is_none())
+            .map(|message| message.id)
+            .collect::<Vec<u64>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The messages last sent through a server, sorted by id
+    #[must_use]
+    pub fn through(&self, server_id: NodeId) -> Vec<u64> {
+        let mut ids = self
+            .messages
+            .values()
+            .filter(|message| message.server_id == Some(server_id))
+            .map(|message| message.id)
+            .collect::<Vec<u64>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The messages waiting for longer than the timeout, sorted by id
+    #[must_use]
+    pub fn timed_out(&self, now: u128) -> Vec<u64> {
+        let mut ids = self
+            .messages
+            .values()
+            .filter(|message| message.since + self.timeout_ms <= now)
+            .map(|message| message.id)
+            .collect::<Vec<u64>>();
+        ids.sort_unstable();
+        ids
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::history::{ConversationStore, HistoryEntry};
+
+/// Which messages of the history to find. The fields that are `None` match every message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    /// Contained in the text, ignoring the case
+    pub text: Option<String>,
+    /// The first timestamp included, in milliseconds since the UNIX epoch
+    pub since: Option<u128>,
+    /// The first timestamp excluded
+    pub until: Option<u128>,
+    pub peer_id: Option<NodeId>,
+    pub server_id: Option<NodeId>,
+}
+
+impl SearchQuery {
+    #[must_use]
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let text_matches = match &self.text {
+            Some(text) => entry.text.to_lowercase().contains(&text.to_lowercase()),
+            None => true,
+        };
+        text_matches
+            && !matches!(self.peer_id, Some(peer_id) if peer_id != entry.peer_id)
+            && !matches!(self.server_id, Some(server_id) if server_id != entry.server_id)
+            && !matches!(self.since, Some(since) if entry.timestamp < since)
+            && !matches!(self.until, Some(until) if entry.timestamp >= until)
+    }
+}
+
+impl ConversationStore {
+    /// The messages of every conversation matching the query, from the oldest
+    #[must_use]
+    pub fn search(&self, query: &SearchQuery) -> Vec<HistoryEntry> {
+        let mut entries = self
+            .conversations()
+            .into_iter()
+            .flat_map(|(server_id, peer_id)| self.conversation(server_id, peer_id))
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect::<Vec<HistoryEntry>>();
+        entries.sort_by_key(|entry| entry.timestamp);
+        entries
+    }
+}
@@ -0,0 +1,565 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::history::Direction;
+
+/// How many times the chunks (or the acceptance) are sent again without an answer,
+/// before the transfer is interrupted
+pub const FILE_MAX_ATTEMPTS: u32 = 5;
+
+/// How many ended transfers are remembered, the oldest ones are forgotten
+pub const FINISHED_TRANSFERS_RETAINED: usize = 1024;
+
+/// The 64 bit FNV-1a hash of the data, used to check the received files
+#[must_use]
+pub fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The messages of the file transfer protocol, exchanged between two clients as a `ChatPayload::File`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileMessage {
+    /// The sender proposes a file. Sent again to resume an interrupted transfer
+    Offer {
+        transfer_id: u64,
+        name: String,
+        mime_type: String,
+        size: u64,
+        chunk_size: u64,
+        checksum: u64,
+    },
+    /// The recipient wants the file, except the chunks it already has
+    Accept {
+        transfer_id: u64,
+        have: Vec<u64>,
+    },
+    Reject {
+        transfer_id: u64,
+    },
+    Chunk {
+        transfer_id: u64,
+        index: u64,
+        data: Vec<u8>,
+    },
+    /// The recipient received a chunk
+    Ack {
+        transfer_id: u64,
+        index: u64,
+    },
+    /// The recipient has the whole file, and the checksum matches
+    Complete {
+        transfer_id: u64,
+    },
+    /// The recipient has all the chunks, but they don't make the offered file
+    Failed {
+        transfer_id: u64,
+        reason: String,
+    },
+}
+
+/// Where a transfer is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferState {
+    /// Waiting for the recipient to accept
+    Offered,
+    Transferring,
+    /// No answer after all the attempts, it can be resumed
+    Interrupted,
+    Completed,
+    Rejected,
+    Failed(String),
+}
+
+impl TransferState {
+    /// Whether the transfer ended, and can't be resumed
+    #[must_use]
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TransferState::Completed | TransferState::Rejected | TransferState::Failed(_)
+        )
+    }
+}
+
+/// The progress of a transfer, reported to the chat controller
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferProgress {
+    pub transfer_id: u64,
+    pub peer_id: NodeId,
+    /// `Sent` if this client sends the file
+    pub direction: Direction,
+    pub name: String,
+    /// The chunks acknowledged, or received
+    pub done_chunks: u64,
+    pub total_chunks: u64,
+    pub state: TransferState,
+}
+
+fn total_chunks(size: u64, chunk_size: u64) -> u64 {
+    size.div_ceil(chunk_size.max(1))
+}
+
+/// A file sent by this client
+#[derive(Debug)]
+pub struct OutgoingTransfer {
+    pub transfer_id: u64,
+    pub server_id: NodeId,
+    pub peer_id: NodeId,
+    pub name: String,
+    pub mime_type: String,
+    pub state: TransferState,
+    /// Released when the transfer ends
+    data: Vec<u8>,
+    size: u64,
+    checksum: u64,
+    chunk_size: u64,
+    acked: BTreeSet<u64>,
+    /// Sent, but not acknowledged yet
+    in_flight: BTreeSet<u64>,
+    last_activity: u128,
+    attempts: u32,
+}
+
+impl OutgoingTransfer {
+    #[must_use]
+    pub fn new(
+        transfer_id: u64,
+        server_id: NodeId,
+        peer_id: NodeId,
+        name: String,
+        mime_type: String,
+        data: Vec<u8>,
+        chunk_size: u64,
+    ) -> Self {
+        OutgoingTransfer {
+            transfer_id,
+            server_id,
+            peer_id,
+            name,
+            mime_type,
+            state: TransferState::Offered,
+            size: data.len() as u64,
+            checksum: checksum(&data),
+            data,
+            chunk_size: chunk_size.max(1),
+            acked: BTreeSet::new(),
+            in_flight: BTreeSet::new(),
+            last_activity: 0,
+            attempts: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn total_chunks(&self) -> u64 {
+        total_chunks(self.size, self.chunk_size)
+    }
+
+    /// The offer of the file, sent at the start and to resume the transfer
+    pub fn offer(&mut self, now: u128) -> FileMessage {
+        self.last_activity = now;
+        FileMessage::Offer {
+            transfer_id: self.transfer_id,
+            name: self.name.clone(),
+            mime_type: self.mime_type.clone(),
+            size: self.size,
+            chunk_size: self.chunk_size,
+            checksum: self.checksum,
+        }
+    }
+
+    /// Offer the file again, so the recipient asks for the chunks it's missing
+    pub fn resume(&mut self, now: u128) -> FileMessage {
+        self.state = TransferState::Offered;
+        self.in_flight.clear();
+        self.attempts = 0;
+        self.offer(now)
+    }
+
+    /// The message with the chunk `index`
+    #[must_use]
+    pub fn chunk(&self, index: u64) -> FileMessage {
+        let start = usize::try_from(index * self.chunk_size).unwrap_or(usize::MAX);
+        let start = start.min(self.data.len());
+        let end = start
+            .saturating_add(usize::try_from(self.chunk_size).unwrap_or(usize::MAX))
+            .min(self.data.len());
+        FileMessage::Chunk {
+            transfer_id: self.transfer_id,
+            index,
+            data: self.data[start..end].to_vec(),
+        }
+    }
+
+    /// The recipient accepted, and has the chunks in `have`. Returns the chunks to send
+    pub fn on_accepted(&mut self, have: &[u64], window: usize, now: u128) -> Vec<u64> {
+        self.state = TransferState::Transferring;
+        let total_chunks = self.total_chunks();
+        self.acked
+            .extend(have.iter().copied().filter(|index| *index < total_chunks));
+        self.in_flight.clear();
+        self.attempts = 0;
+        self.last_activity = now;
+        self.next_chunks(window)
+    }
+
+    /// A chunk was acknowledged. Returns the chunks to send
+    pub fn on_ack(&mut self, index: u64, window: usize, now: u128) -> Vec<u64> {
+        if self.state != TransferState::Transferring || index >= self.total_chunks() {
+            return vec![];
+        }
+        self.in_flight.remove(&index);
+        self.acked.insert(index);
+        self.attempts = 0;
+        self.last_activity = now;
+        self.next_chunks(window)
+    }
+
+    /// No answer for too long. Returns the messages to send again:
+    /// the offer if it wasn't accepted, or the chunks in flight.
+    /// After `FILE_MAX_ATTEMPTS` the transfer is interrupted
+    pub fn on_timeout(&mut self, now: u128) -> Vec<FileMessage> {
+        self.attempts += 1;
+        if self.attempts >= FILE_MAX_ATTEMPTS {
+            self.state = TransferState::Interrupted;
+            self.in_flight.clear();
+            return vec![];
+        }
+        self.last_activity = now;
+        match self.state {
+            TransferState::Offered => vec![self.offer(now)],
+            _ => self
+                .in_flight
+                .iter()
+                .map(|index| self.chunk(*index))
+                .collect(),
+        }
+    }
+
+    /// Whether the transfer waits for an answer
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.state,
+            TransferState::Offered | TransferState::Transferring
+        )
+    }
+
+    #[must_use]
+    pub fn last_activity(&self) -> u128 {
+        self.last_activity
+    }
+
+    #[must_use]
+    pub fn progress(&self) -> TransferProgress {
+        TransferProgress {
+            transfer_id: self.transfer_id,
+            peer_id: self.peer_id,
+            direction: Direction::Sent,
+            name: self.name.clone(),
+            done_chunks: self.acked.len() as u64,
+            total_chunks: self.total_chunks(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// The bytes of the file kept in memory
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.data.len()
+    }
+
+    /// The transfer ended: free the file, the progress is kept
+    pub fn release(&mut self) {
+        self.data = Vec::new();
+        self.in_flight.clear();
+    }
+
+    /// Put the next chunks to send in flight, up to `window`
+    fn next_chunks(&mut self, window: usize) -> Vec<u64> {
+        let available = window.max(1).saturating_sub(self.in_flight.len());
+        let next = (0..self.total_chunks())
+            .filter(|index| !self.acked.contains(index) && !self.in_flight.contains(index))
+            .take(available)
+            .collect::<Vec<u64>>();
+        self.in_flight.extend(next.iter().copied());
+        next
+    }
+}
+
+/// A file offered to this client
+#[derive(Debug)]
+pub struct IncomingTransfer {
+    pub transfer_id: u64,
+    pub server_id: NodeId,
+    pub peer_id: NodeId,
+    pub name: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub state: TransferState,
+    chunk_size: u64,
+    checksum: u64,
+    /// Emptied when the transfer ends, only the indexes are kept
+    chunks: BTreeMap<u64, Vec<u8>>,
+    last_activity: u128,
+    attempts: u32,
+}
+
+impl IncomingTransfer {
+    /// The transfer of an offer, `None` if the message isn't an offer
+    #[must_use]
+    pub fn from_offer(server_id: NodeId, peer_id: NodeId, offer: &FileMessage) -> Option<Self> {
+        let FileMessage::Offer {
+            transfer_id,
+            name,
+            mime_type,
+            size,
+            chunk_size,
+            checksum,
+        } = offer
+        else {
+            return None;
+        };
+        Some(IncomingTransfer {
+            transfer_id: *transfer_id,
+            server_id,
+            peer_id,
+            name: name.clone(),
+            mime_type: mime_type.clone(),
+            size: *size,
+            state: TransferState::Offered,
+            chunk_size: (*chunk_size).max(1),
+            checksum: *checksum,
+            chunks: BTreeMap::new(),
+            last_activity: 0,
+            attempts: 0,
+        })
+    }
+
+    #[must_use]
+    pub fn total_chunks(&self) -> u64 {
+        total_chunks(self.size, self.chunk_size)
+    }
+
+    /// Accept the file, or ask for the missing chunks to resume the transfer
+    pub fn accept(&mut self, now: u128) -> FileMessage {
+        self.state = TransferState::Transferring;
+        self.last_activity = now;
+        FileMessage::Accept {
+            transfer_id: self.transfer_id,
+            have: self.chunks.keys().copied().collect(),
+        }
+    }
+
+    /// Ask again for the missing chunks, after an interruption
+    pub fn resume(&mut self, now: u128) -> FileMessage {
+        self.attempts = 0;
+        self.accept(now)
+    }
+
+    /// A chunk arrived. Returns false if it was already received, or it isn't part of the file
+    pub fn on_chunk(&mut self, index: u64, data: Vec<u8>, now: u128) -> bool {
+        if index >= self.total_chunks() || self.chunks.contains_key(&index) {
+            return false;
+        }
+        if self.state == TransferState::Interrupted {
+            self.state = TransferState::Transferring;
+        }
+        self.chunks.insert(index, data);
+        self.attempts = 0;
+        self.last_activity = now;
+        true
+    }
+
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.chunks.len() as u64 == self.total_chunks()
+    }
+
+    /// Join the chunks, checking the size and the checksum
+    /// # Errors
+    /// Returns an error if the file doesn't match the offer
+    pub fn assemble(&self) -> Result<Vec<u8>, String> {
+        let data = self.chunks.values().flatten().copied().collect::<Vec<u8>>();
+        if data.len() as u64 != self.size {
+            return Err(format!(
+                "Received {} bytes instead of {}",
+                data.len(),
+                self.size
+            ));
+        }
+        if checksum(&data) != self.checksum {
+            return Err("The checksum doesn't match".to_string());
+        }
+        Ok(data)
+    }
+
+    /// No chunk for too long. Returns the acceptance to send again, asking for the missing chunks.
+    /// After `FILE_MAX_ATTEMPTS` the transfer is interrupted
+    pub fn on_timeout(&mut self, now: u128) -> Option<FileMessage> {
+        self.attempts += 1;
+        if self.attempts >= FILE_MAX_ATTEMPTS {
+            self.state = TransferState::Interrupted;
+            return None;
+        }
+        Some(self.accept(now))
+    }
+
+    #[must_use]
+    pub fn last_activity(&self) -> u128 {
+        self.last_activity
+    }
+
+    #[must_use]
+    pub fn progress(&self) -> TransferProgress {
+        TransferProgress {
+            transfer_id: self.transfer_id,
+            peer_id: self.peer_id,
+            direction: Direction::Received,
+            name: self.name.clone(),
+            done_chunks: self.chunks.len() as u64,
+            total_chunks: self.total_chunks(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// The bytes of the received chunks kept in memory
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.chunks.values().map(Vec::len).sum()
+    }
+
+    /// The transfer ended: free the received chunks, the progress is kept
+    pub fn release(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            *chunk = Vec::new();
+        }
+    }
+}
+
+/// The files being sent and received, with the settings of the transfers
+#[derive(Debug)]
+pub struct Transfers {
+    pub outgoing: HashMap<u64, OutgoingTransfer>,
+    pub incoming: HashMap<u64, IncomingTransfer>,
+    /// The size of the chunks of the files sent
+    pub chunk_size: u64,
+    /// How many chunks can be sent without being acknowledged
+    pub window: usize,
+    /// How long to wait for an answer before sending again
+    pub timeout_ms: u128,
+    /// The transfers that ended, oldest first
+    finished: VecDeque<u64>,
+}
+
+impl Default for Transfers {
+    fn default() -> Self {
+        Self::new(1024, 4, 3000)
+    }
+}
+
+impl Transfers {
+    #[must_use]
+    pub fn new(chunk_size: u64, window: usize, timeout_ms: u64) -> Self {
+        Transfers {
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            chunk_size: chunk_size.max(1),
+            window: window.max(1),
+            timeout_ms: u128::from(timeout_ms),
+            finished: VecDeque::new(),
+        }
+    }
+
+    /// The progress of a transfer, sent or received
+    #[must_use]
+    pub fn progress(&self, transfer_id: u64) -> Option<TransferProgress> {
+        self.outgoing
+            .get(&transfer_id)
+            .map(OutgoingTransfer::progress)
+            .or_else(|| {
+                self.incoming
+                    .get(&transfer_id)
+                    .map(IncomingTransfer::progress)
+            })
+    }
+
+    /// The active transfers that didn't get an answer in time, sorted
+    #[must_use]
+    pub fn timed_out(&self, now: u128) -> (Vec<u64>, Vec<u64>) {
+        let expired = |last_activity: u128| last_activity + self.timeout_ms <= now;
+        let mut outgoing = self
+            .outgoing
+            .values()
+            .filter(|transfer| transfer.is_active() && expired(transfer.last_activity()))
+            .map(|transfer| transfer.transfer_id)
+            .collect::<Vec<u64>>();
+        let mut incoming = self
+            .incoming
+            .values()
+            .filter(|transfer| {
+                transfer.state == TransferState::Transferring && expired(transfer.last_activity())
+            })
+            .map(|transfer| transfer.transfer_id)
+            .collect::<Vec<u64>>();
+        outgoing.sort_unstable();
+        incoming.sort_unstable();
+        (outgoing, incoming)
+    }
+
+    /// A transfer ended: free its data, and forget the oldest ended transfers
+    /// beyond `FINISHED_TRANSFERS_RETAINED`
+    pub fn on_finished(&mut self, transfer_id: u64) {
+        if let Some(transfer) = self
+            .outgoing
+            .get_mut(&transfer_id)
+            .filter(|transfer| transfer.state.is_final())
+        {
+            transfer.release();
+        }
+        if let Some(transfer) = self
+            .incoming
+            .get_mut(&transfer_id)
+            .filter(|transfer| transfer.state.is_final())
+        {
+            transfer.release();
+        }
+        self.finished.push_back(transfer_id);
+        while self.finished.len() > FINISHED_TRANSFERS_RETAINED {
+            let Some(oldest) = self.finished.pop_front() else {
+                break;
+            };
+            if self
+                .outgoing
+                .get(&oldest)
+                .is_some_and(|transfer| transfer.state.is_final())
+            {
+                self.outgoing.remove(&oldest);
+            }
+            if self
+                .incoming
+                .get(&oldest)
+                .is_some_and(|transfer| transfer.state.is_final())
+            {
+                self.incoming.remove(&oldest);
+            }
+        }
+    }
+
+    /// Continue the transfers through `from` on another server. They resume on their next timeout
+    pub fn move_server(&mut self, from: NodeId, to: NodeId) {
+        for transfer in self.outgoing.values_mut() {
+            if transfer.server_id == from {
+                transfer.server_id = to;
+            }
+        }
+        for transfer in self.incoming.values_mut() {
+            if transfer.server_id == from {
+                transfer.server_id = to;
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wg_2024::network::NodeId;
+
+use super::history::{ConversationStore, Direction};
+
+/// How many characters of the last message are shown in a conversation list
+pub const PREVIEW_LENGTH: usize = 40;
+
+/// A conversation as shown in a conversation list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub server_id: NodeId,
+    pub peer_id: NodeId,
+    /// The messages received since the conversation was last read
+    pub unread: usize,
+    /// The beginning of the last message
+    pub preview: String,
+    pub last_direction: Direction,
+    /// When the last message was sent or received, milliseconds since the UNIX epoch
+    pub last_activity: u128,
+}
+
+/// The beginning of a text, at most `PREVIEW_LENGTH` characters followed by `…` if it's longer
+#[must_use]
+pub fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// How many messages of every conversation (`server_id`, `peer_id`) weren't read.
+/// The history loaded when the client starts counts as read
+#[derive(Debug, Default)]
+pub struct UnreadCounters {
+    counts: HashMap<(NodeId, NodeId), usize>,
+}
+
+impl UnreadCounters {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A message of the conversation was received
+    pub fn on_received(&mut self, server_id: NodeId, peer_id: NodeId) {
+        *self.counts.entry((server_id, peer_id)).or_default() += 1;
+    }
+
+    /// Returns how many messages were unread
+    pub fn mark_read(&mut self, server_id: NodeId, peer_id: NodeId) -> usize {
+        self.counts
+            .remove(&(server_id, peer_id))
+            .unwrap_or_default()
+    }
+
+    /// Messages were removed from the conversation: there can't be more unread messages
+    /// than the received ones left in `store`
+    pub fn on_removed(&mut self, store: &ConversationStore, server_id: NodeId, peer_id: NodeId) {
+        let received = store
+            .conversation(server_id, peer_id)
+            .iter()
+            .filter(|entry| entry.direction == Direction::Received)
+            .count();
+        if let Some(count) = self.counts.get_mut(&(server_id, peer_id)) {
+            *count = (*count).min(received);
+        }
+    }
+
+    #[must_use]
+    pub fn unread(&self, server_id: NodeId, peer_id: NodeId) -> usize {
+        self.counts
+            .get(&(server_id, peer_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The conversations of `store`, from the most recently active
+    #[must_use]
+    pub fn summaries(&self, store: &ConversationStore) -> Vec<ConversationSummary> {
+        let mut summaries = store
+            .conversations()
+            .into_iter()
+            .filter_map(|(server_id, peer_id)| {
+                let last = store.conversation(server_id, peer_id).last()?;
+                Some(ConversationSummary {
+                    server_id,
+                    peer_id,
+                    unread: self.unread(server_id, peer_id),
+                    preview: preview(&last.text),
+                    last_direction: last.direction,
+                    last_activity: last.timestamp,
+                })
+            })
+            .collect::<Vec<ConversationSummary>>();
+        // Stable, the conversations active at the same time stay sorted by (`server_id`, `peer_id`)
+        summaries.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+        summaries
+    }
+}
@@ -1,8 +1,21 @@
-use core::str;
 use std::collections::HashMap;
-use std::process;
+use std::path::Path;
 
-use crate::client::Client;
+use crate::chat::auto_join::AUTO_JOIN_CHECK_INTERVAL_MS;
+use crate::chat::ephemeral::is_expired;
+use crate::chat::failover::FAILOVER_CHECK_INTERVAL_MS;
+use crate::chat::{
+    diff_client_lists, Activity, ActivityTracker, AutoJoinPolicy, BehaviorAction, BehaviorContext,
+    Broadcasts, ChatBehavior, ChatCommand, ChatConfig, ChatEvent, ChatPayload, ConversationStore,
+    ConversationSummary, DeliveryStatus, Direction, Directory, EphemeralMessage, Ephemerals,
+    ExportFormat, FileMessage, GroupMessage, Groups, HistoryEntry, IncomingTransfer, ListRefresher,
+    MessageContent, Ordered, Outbox, OutgoingTransfer, QueuedMessage, RateLimit, ReceiptTracker,
+    RegistrationState, RegistrationTimeout, Registrations, ReorderBuffer, RoutedMessage,
+    RoutedMessages, SearchQuery, SenderFilter, SenderPolicy, Sequencer, ServerMonitor,
+    TrackedMessage, TransferProgress, TransferState, Transfers, UnreadCounters, Verdict,
+};
+use crate::client::{current_timestamp_ms, Client};
+use crate::transport::{CrossbeamTransport, Transport};
 use rustafarian_shared::assembler::{assembler::Assembler, disassembler::Disassembler};
 use rustafarian_shared::logger::{LogLevel, Logger};
 use rustafarian_shared::messages::chat_messages::{
@@ -14,113 +27,2362 @@ use rustafarian_shared::messages::commander_messages::{
 use rustafarian_shared::messages::general_messages::{
     DroneSend, ServerType, ServerTypeRequest, ServerTypeResponse,
 };
-use rustafarian_shared::topology::Topology;
+use rustafarian_shared::topology::{compute_route, Topology};
 
 use crossbeam_channel::{Receiver, Sender};
 use wg_2024::{network::NodeId, packet::Packet};
 
-pub struct ChatClient {
-    // General data for Client
-    client_id: u8,
-    senders: HashMap<u8, Sender<Packet>>,
-    receiver: Receiver<Packet>,
-    topology: Topology,
-    sim_controller_receiver: Receiver<SimControllerCommand>,
-    sim_controller_sender: Sender<SimControllerResponseWrapper>,
-    sent_packets: HashMap<u64, Vec<Packet>>,
-    acked_packets: HashMap<u64, Vec<bool>>,
-    assembler: Assembler,
-    disassembler: Disassembler,
-    running: bool,
-    packets_to_send: HashMap<u8, Packet>,
-    sent_flood_ids: Vec<u64>,
-    last_flood_timestamp: u128,
-    logger: Logger,
-
-    // Chat-specific data
+/// The data of the chat application layer
+#[derive(Default)]
+pub struct ChatState {
     /// Key: `server_id`, value: list of client ids
     available_clients: HashMap<NodeId, Vec<NodeId>>,
     /// List of servers the client is registered to
     registered_servers: Vec<NodeId>,
+    /// The messages sent and received
+    history: ConversationStore,
+    /// The channels of the chat commands, if attached
+    controller: Option<(Receiver<ChatCommand>, Sender<ChatEvent>)>,
+    /// The delivery status of the sent messages
+    receipts: ReceiptTracker,
+    /// The registration state for every server
+    registrations: Registrations,
+    /// Which discovered servers to register to without being asked
+    auto_join: AutoJoinPolicy,
+    /// When the auto-join policy was last applied
+    last_auto_join_check: u128,
+    /// The messages waiting for their recipient to register
+    outbox: Outbox,
+    /// The messages sent with `send_to`, until they are delivered
+    routed: RoutedMessages,
+    /// The group conversations
+    groups: Groups,
+    /// The sequence numbers of the messages sent
+    sequencer: Sequencer,
+    /// The messages received out of order, as (`server_id`, id, content, expiry)
+    reorder: ReorderBuffer<(NodeId, u64, MessageContent, Option<u128>)>,
+    /// The group messages received out of order. Key: `group_id`
+    group_reorder: HashMap<u64, ReorderBuffer<GroupMessage>>,
+    /// The files being sent and received
+    transfers: Transfers,
+    /// The configuration, saved when it's changed by a chat command
+    config: ChatConfig,
+    /// Which clients are blocked, muted or rate limited
+    filter: SenderFilter,
+    /// The scripted behaviors, run on the incoming messages and on every tick
+    behaviors: Vec<Box<dyn ChatBehavior>>,
+    /// When the client list of every server was last received
+    client_lists_updated: HashMap<NodeId, u128>,
+    /// How old a client list can be before a broadcast asks for it again
+    client_list_max_age_ms: u64,
+    /// Which client lists are requested in the background, for the presence and the directory
+    list_refresher: ListRefresher,
+    /// The broadcasts waiting for a client list, or for their receipts
+    broadcasts: Broadcasts,
+    /// Which chat servers stopped answering
+    monitor: ServerMonitor,
+    /// When the registered servers were last checked
+    last_failover_check: u128,
+    /// The messages without a receipt yet, sent again through another server if theirs goes down.
+    /// Forgotten after `server_timeout_ms`. Key: id, value: (`peer_id`, content, when it was sent)
+    undelivered: HashMap<u64, (NodeId, MessageContent, u128)>,
+    /// The ephemeral messages in the history, until they expire
+    ephemerals: Ephemerals,
+    /// The messages received and not read yet, for every conversation
+    unread: UnreadCounters,
+    /// The peers typing
+    activity: ActivityTracker,
 }
 
-impl ChatClient {
+impl ChatState {
+    /// Build the state from the configuration, loading the saved history
     #[must_use]
-    pub fn new(
-        client_id: u8,
-        senders: HashMap<u8, Sender<Packet>>,
-        receiver: Receiver<Packet>,
-        sim_controller_receiver: Receiver<SimControllerCommand>,
-        sim_controller_sender: Sender<SimControllerResponseWrapper>,
-        debug: bool,
-    ) -> Self {
-        ChatClient {
-            client_id,
-            senders,
-            receiver,
-            topology: Topology::new(),
-            sim_controller_receiver,
-            sim_controller_sender,
-            sent_packets: HashMap::new(),
-            acked_packets: HashMap::new(),
-            assembler: Assembler::new(),
-            disassembler: Disassembler::new(),
-            running: false,
-            packets_to_send: HashMap::new(),
-            sent_flood_ids: Vec::new(),
-            last_flood_timestamp: 0,
-            logger: Logger::new("ChatClient".to_string(), client_id, debug),
+    pub fn from_config(config: &ChatConfig, logger: &Logger) -> Self {
+        let history = match &config.history_path {
+            Some(path) => ConversationStore::open(path).unwrap_or_else(|err| {
+                logger.log(
+                    &format!("Couldn't load the history, starting empty: {err}"),
+                    LogLevel::ERROR,
+                );
+                ConversationStore::new()
+            }),
+            None => ConversationStore::new(),
+        };
+        // The ephemeral messages saved before a restart still expire
+        let mut ephemerals = Ephemerals::new();
+        for (server_id, peer_id) in history.conversations() {
+            for entry in history.conversation(server_id, peer_id) {
+                if let (Some(id), Some(expires_at)) = (entry.id, entry.expires_at) {
+                    ephemerals.track(EphemeralMessage {
+                        id,
+                        entry: entry.clone(),
+                        expires_at,
+                    });
+                }
+            }
+        }
+        ChatState {
+            history,
+            ephemerals,
+            registrations: Registrations::new(
+                config.registration_timeout_ms,
+                config.registration_attempts,
+            ),
+            auto_join: config.auto_join.clone(),
+            outbox: Outbox::new(config.outbox_expiry_ms),
+            routed: RoutedMessages::new(config.delivery_timeout_ms),
+            reorder: ReorderBuffer::new(config.reorder_window, config.reorder_timeout_ms),
+            group_reorder: HashMap::new(),
+            transfers: Transfers::new(
+                config.file_chunk_size,
+                config.file_window,
+                config.file_timeout_ms,
+            ),
+            config: config.clone(),
+            filter: SenderFilter::new(config.sender_policy.clone()),
+            client_list_max_age_ms: config.client_list_max_age_ms,
+            list_refresher: ListRefresher::new(config.list_refresh_ms()),
+            broadcasts: Broadcasts::new(config.delivery_timeout_ms),
+            monitor: ServerMonitor::new(config.server_timeout_ms),
+            activity: ActivityTracker::new(config.typing_timeout_ms),
+            ..Default::default()
+        }
+    }
+}
+
+/// The chat application layer, used to communicate with Chat Servers.
+/// Implemented by every client that can chat, on top of the network part of `Client`
+pub trait ChatLayer: Client {
+    /// The data of the chat layer
+    fn chat_state(&self) -> &ChatState;
+    /// The data of the chat layer, mutable
+    fn chat_state_mut(&mut self) -> &mut ChatState;
+
+    /// Get the list of available clients in the chat server
+    fn get_client_list(&mut self) -> &mut HashMap<NodeId, Vec<NodeId>> {
+        &mut self.chat_state_mut().available_clients
+    }
+
+    /// Get the servers the client is registered to
+    fn get_registered_servers(&mut self) -> &mut Vec<NodeId> {
+        &mut self.chat_state_mut().registered_servers
+    }
+
+    /// Get the available servers
+    fn get_available_clients(&mut self) -> &mut HashMap<NodeId, Vec<NodeId>> {
+        &mut self.chat_state_mut().available_clients
+    }
+
+    /// Get the servers through which a client is online, sorted
+    fn get_presence(&self, client_id: NodeId) -> Vec<NodeId> {
+        let mut servers = self
+            .chat_state()
+            .available_clients
+            .iter()
+            .filter(|(_, clients)| clients.contains(&client_id))
+            .map(|(server_id, _)| *server_id)
+            .collect::<Vec<NodeId>>();
+        servers.sort_unstable();
+        servers
+    }
+
+    /// The clients of all the registered servers, merged: every client with the servers listing it
+    fn get_directory(&self) -> Directory {
+        let state = self.chat_state();
+        Directory::merge(
+            &state.available_clients,
+            &state.registered_servers,
+            self.client_id(),
+        )
+    }
+
+    /// Whether a client is in the client list of at least one server
+    fn is_online(&self, client_id: NodeId) -> bool {
+        self.chat_state()
+            .available_clients
+            .values()
+            .any(|clients| clients.contains(&client_id))
+    }
+
+    /// Get the messages sent and received
+    fn get_history(&self) -> &ConversationStore {
+        &self.chat_state().history
+    }
+
+    /// The conversations with their unread messages and a preview of their last message,
+    /// from the most recently active
+    fn get_conversation_list(&self) -> Vec<ConversationSummary> {
+        let state = self.chat_state();
+        state.unread.summaries(&state.history)
+    }
+
+    /// How many messages of a conversation weren't read
+    fn get_unread(&self, server_id: NodeId, peer_id: NodeId) -> usize {
+        self.chat_state().unread.unread(server_id, peer_id)
+    }
+
+    /// Mark the messages of a conversation as read
+    /// # Errors
+    /// Returns an error if there is no such conversation
+    fn mark_read(&mut self, server_id: NodeId, peer_id: NodeId) -> Result<(), String> {
+        let state = self.chat_state_mut();
+        if state.history.conversation(server_id, peer_id).is_empty() {
+            return Err(format!(
+                "No conversation with {peer_id} through {server_id}"
+            ));
+        }
+        state.unread.mark_read(server_id, peer_id);
+        Ok(())
+    }
+
+    /// Set the channels used to receive the `ChatCommand`s and send the `ChatEvent`s
+    fn attach_chat_controller(
+        &mut self,
+        commands: Receiver<ChatCommand>,
+        events: Sender<ChatEvent>,
+    ) {
+        self.chat_state_mut().controller = Some((commands, events));
+    }
+
+    /// Send an event to the attached chat controller, if any
+    fn send_chat_event(&self, event: ChatEvent) {
+        if let Some((_, events)) = &self.chat_state().controller {
+            let _res = events.send(event);
+        }
+    }
+
+    /// Add a message to the history, returns its entry
+    fn record_message(
+        &mut self,
+        server_id: NodeId,
+        peer_id: NodeId,
+        direction: Direction,
+        text: String,
+    ) -> HistoryEntry {
+        self.record_entry(HistoryEntry {
+            server_id,
+            peer_id,
+            direction,
+            timestamp: current_timestamp_ms(),
+            text,
+            id: None,
+            expires_at: None,
+        })
+    }
+
+    /// Save an entry in the history, and return it
+    fn record_entry(&mut self, entry: HistoryEntry) -> HistoryEntry {
+        if let Err(err) = self.chat_state_mut().history.record(entry.clone()) {
+            self.logger().log(
+                &format!("Couldn't save the message: {err}"),
+                LogLevel::ERROR,
+            );
+        }
+        entry
+    }
+
+    /// Remove an ephemeral message from the history, and report it
+    fn expire_message(&mut self, message: EphemeralMessage) {
+        self.logger()
+            .log(&format!("Message {} expired", message.id), LogLevel::DEBUG);
+        let state = self.chat_state_mut();
+        let result = state.history.remove(&message.entry);
+        let (server_id, peer_id) = (message.entry.server_id, message.entry.peer_id);
+        state.unread.on_removed(&state.history, server_id, peer_id);
+        if let Err(err) = result {
+            self.logger().log(
+                &format!("Couldn't remove the message from the history: {err}"),
+                LogLevel::ERROR,
+            );
+        }
+        self.send_chat_event(ChatEvent::MessageExpired {
+            server_id: message.entry.server_id,
+            peer_id: message.entry.peer_id,
+            id: message.id,
+        });
+    }
+
+    /// Find the messages of the history matching a query, from the oldest
+    fn search_history(&self, query: &SearchQuery) -> Vec<HistoryEntry> {
+        self.chat_state().history.search(query)
+    }
+
+    /// Write a conversation to a file
+    /// # Errors
+    /// Returns an error if the file couldn't be written
+    fn export_conversation(
+        &self,
+        server_id: NodeId,
+        peer_id: NodeId,
+        format: ExportFormat,
+        path: &Path,
+    ) -> Result<(), String> {
+        self.chat_state()
+            .history
+            .export(server_id, peer_id, format, path)
+    }
+
+    /// Called on every tick of the client: retry the registrations that timed out,
+    /// and handle the pending chat commands
+    fn on_chat_tick(&mut self) {
+        let timeouts = self
+            .chat_state_mut()
+            .registrations
+            .check_timeouts(current_timestamp_ms());
+        for timeout in timeouts {
+            match timeout {
+                RegistrationTimeout::Retry(server_id) => {
+                    self.logger().log(
+                        &format!("Registration to {server_id} timed out, retrying"),
+                        LogLevel::DEBUG,
+                    );
+                    self.send_register_request(server_id);
+                }
+                RegistrationTimeout::Failed(server_id) => {
+                    self.logger().log(
+                        &format!("Registration to {server_id} failed"),
+                        LogLevel::ERROR,
+                    );
+                    self.notify_registration(server_id);
+                }
+            }
+        }
+
+        let now = current_timestamp_ms();
+        let state = self.chat_state_mut();
+        if state.last_failover_check + FAILOVER_CHECK_INTERVAL_MS <= now {
+            state.last_failover_check = now;
+            self.check_servers(now);
+            let state = self.chat_state_mut();
+            let timeout = u128::from(state.config.server_timeout_ms);
+            state
+                .undelivered
+                .retain(|_, (_, _, sent_at)| *sent_at + timeout > now);
+        }
+
+        let state = self.chat_state_mut();
+        if state.auto_join.is_enabled()
+            && state.last_auto_join_check + AUTO_JOIN_CHECK_INTERVAL_MS <= now
+        {
+            state.last_auto_join_check = now;
+            self.apply_auto_join();
+        }
+
+        let expired = self.chat_state_mut().outbox.expire(now);
+        for message in expired {
+            self.logger().log(
+                &format!(
+                    "Message {} to {} expired in the outbox",
+                    message.id, message.peer_id
+                ),
+                LogLevel::DEBUG,
+            );
+            if let Some(message) = self.chat_state_mut().receipts.on_expired(message.id) {
+                self.notify_message_status(&message);
+            }
+        }
+
+        for id in self.chat_state().routed.timed_out(now) {
+            self.logger().log(
+                &format!("Message {id} wasn't delivered in time, trying another server"),
+                LogLevel::DEBUG,
+            );
+            if !self.route_message(id) {
+                self.fail_routed_message(id);
+            }
+        }
+
+        let ordered = self.chat_state_mut().reorder.check_timeouts(now);
+        self.on_messages_ordered(ordered);
+        let mut group_ids = self
+            .chat_state()
+            .group_reorder
+            .keys()
+            .copied()
+            .collect::<Vec<u64>>();
+        group_ids.sort_unstable();
+        for group_id in group_ids {
+            let ordered = self
+                .chat_state_mut()
+                .group_reorder
+                .get_mut(&group_id)
+                .map(|reorder| reorder.check_timeouts(now))
+                .unwrap_or_default();
+            self.on_group_messages_ordered(group_id, ordered);
+        }
+
+        for message in self.chat_state_mut().ephemerals.take_expired(now) {
+            self.expire_message(message);
+        }
+
+        for (server_id, peer_id) in self.chat_state_mut().activity.timed_out(now) {
+            self.send_chat_event(ChatEvent::PeerActivity {
+                server_id,
+                peer_id,
+                activity: Activity::Idle,
+            });
+        }
+
+        self.check_transfer_timeouts(now);
+
+        for id in self.chat_state().broadcasts.waiting_timed_out(now) {
+            self.logger().log(
+                &format!("No client list for broadcast {id}, using the last one"),
+                LogLevel::DEBUG,
+            );
+            self.send_broadcast(id);
+        }
+        let state = self.chat_state_mut();
+        let receipts = &state.receipts;
+        let results = state
+            .broadcasts
+            .take_finished(now, |id| receipts.get(id).map(|message| message.status));
+        for result in results {
+            self.send_chat_event(ChatEvent::BroadcastResult(result));
+        }
+
+        self.run_behaviors(|behavior, context| behavior.on_tick(context));
+
+        let state = self.chat_state_mut();
+        let updated = &state.client_lists_updated;
+        let stale = state
+            .list_refresher
+            .due(&state.registered_servers, updated, now);
+        for server_id in stale {
+            self.send_client_list_req(server_id);
+        }
+
+        let commands = match &self.chat_state().controller {
+            Some((commands, _)) => commands.try_iter().collect::<Vec<ChatCommand>>(),
+            None => return,
+        };
+        for command in commands {
+            self.handle_chat_layer_command(command);
+        }
+    }
+
+    /// Handle a command received through the chat controller channel
+    fn handle_chat_layer_command(&mut self, command: ChatCommand) {
+        self.logger()
+            .log(&format!("CHAT COMMAND: {command:?}"), LogLevel::DEBUG);
+        match command {
+            ChatCommand::History {
+                server_id,
+                peer_id,
+                page,
+                page_size,
+            } => {
+                let entries = self
+                    .chat_state()
+                    .history
+                    .page(server_id, peer_id, page, page_size);
+                self.send_chat_event(ChatEvent::History {
+                    server_id,
+                    peer_id,
+                    page,
+                    entries,
+                });
+            }
+            ChatCommand::Conversations => {
+                let conversations = self.chat_state().history.conversations();
+                self.send_chat_event(ChatEvent::Conversations(conversations));
+            }
+            ChatCommand::ConversationList => {
+                let conversations = self.get_conversation_list();
+                self.send_chat_event(ChatEvent::ConversationList(conversations));
+            }
+            ChatCommand::MarkRead { server_id, peer_id } => {
+                if let Err(err) = self.mark_read(server_id, peer_id) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::Unregister(server_id) => self.unregister(server_id),
+            ChatCommand::Registrations => {
+                let states = self.chat_state().registrations.states();
+                self.send_chat_event(ChatEvent::Registrations(states));
+            }
+            ChatCommand::SetAutoJoin(policy) => {
+                let state = self.chat_state_mut();
+                state.config.auto_join = policy.clone();
+                state.auto_join = policy;
+                self.save_config();
+                self.apply_auto_join();
+            }
+            ChatCommand::SetPresencePolling(interval) => {
+                let state = self.chat_state_mut();
+                state.config.presence_poll_interval_ms = interval;
+                let refresh_ms = state.config.list_refresh_ms();
+                state.list_refresher.set_max_age(refresh_ms);
+                self.save_config();
+            }
+            ChatCommand::Presence(client_id) => {
+                let servers = self.get_presence(client_id);
+                self.send_chat_event(ChatEvent::Presence { client_id, servers });
+            }
+            ChatCommand::Directory => {
+                let directory = self.get_directory();
+                self.send_chat_event(ChatEvent::Directory(directory));
+            }
+            ChatCommand::Outbox => {
+                let messages = self.chat_state().outbox.messages().to_vec();
+                self.send_chat_event(ChatEvent::Outbox(messages));
+            }
+            ChatCommand::CreateGroup { name, members } => {
+                self.create_group(name, members);
+            }
+            ChatCommand::AddGroupMember { group_id, member } => {
+                if let Err(err) = self.add_group_member(group_id, member) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::RemoveGroupMember { group_id, member } => {
+                if let Err(err) = self.remove_group_member(group_id, member) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::SendGroupMessage { group_id, text } => {
+                if let Err(err) = self.send_group_message(group_id, text) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::Groups => {
+                let groups = self
+                    .chat_state()
+                    .groups
+                    .list()
+                    .into_iter()
+                    .map(|group| (group.id, group.name.clone(), group.members.clone()))
+                    .collect();
+                self.send_chat_event(ChatEvent::Groups(groups));
+            }
+            ChatCommand::SendContent {
+                server_id,
+                peer_id,
+                content,
+            } => {
+                self.send_chat_content(server_id, peer_id, content);
+            }
+            ChatCommand::SendEphemeral {
+                server_id,
+                peer_id,
+                content,
+                ttl_ms,
+            } => {
+                self.send_ephemeral(server_id, peer_id, content, ttl_ms);
+            }
+            ChatCommand::SendActivity {
+                server_id,
+                peer_id,
+                activity,
+            } => self.send_activity(server_id, peer_id, activity),
+            ChatCommand::GroupHistory(group_id) => {
+                let messages = self
+                    .chat_state()
+                    .groups
+                    .get(group_id)
+                    .map(|group| group.messages().to_vec())
+                    .unwrap_or_default();
+                self.send_chat_event(ChatEvent::GroupHistory { group_id, messages });
+            }
+            ChatCommand::SendFile {
+                server_id,
+                peer_id,
+                name,
+                mime_type,
+                data,
+            } => {
+                self.send_file(server_id, peer_id, name, mime_type, data);
+            }
+            ChatCommand::AcceptFile(transfer_id) => {
+                if let Err(err) = self.accept_file(transfer_id) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::RejectFile(transfer_id) => {
+                if let Err(err) = self.reject_file(transfer_id) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::ResumeTransfer(transfer_id) => {
+                if let Err(err) = self.resume_transfer(transfer_id) {
+                    self.send_chat_event(ChatEvent::CommandFailed(err));
+                }
+            }
+            ChatCommand::Transfer(transfer_id) => match self.get_transfer(transfer_id) {
+                Some(progress) => self.send_chat_event(ChatEvent::TransferProgress(progress)),
+                None => self.send_chat_event(ChatEvent::CommandFailed(format!(
+                    "Unknown transfer {transfer_id}"
+                ))),
+            },
+            ChatCommand::Block(client_id) => self.block_client(client_id),
+            ChatCommand::Unblock(client_id) => self.unblock_client(client_id),
+            ChatCommand::Mute(client_id) => self.mute_client(client_id),
+            ChatCommand::Unmute(client_id) => self.unmute_client(client_id),
+            ChatCommand::SetRateLimit(rate_limit) => self.set_rate_limit(rate_limit),
+            ChatCommand::SenderPolicy => {
+                let policy = self.get_sender_policy().clone();
+                self.send_chat_event(ChatEvent::SenderPolicy(policy));
+            }
+            ChatCommand::Broadcast { server_id, text } => {
+                self.broadcast(server_id, text);
+            }
+            ChatCommand::Search(query) => {
+                let entries = self.search_history(&query);
+                self.send_chat_event(ChatEvent::SearchResults(entries));
+            }
+            ChatCommand::Export {
+                server_id,
+                peer_id,
+                format,
+                path,
+            } => match self.export_conversation(server_id, peer_id, format, &path) {
+                Ok(()) => self.send_chat_event(ChatEvent::Exported(path)),
+                Err(err) => self.send_chat_event(ChatEvent::CommandFailed(err)),
+            },
+        }
+    }
+
+    /// Add a scripted behavior, run on the incoming messages and on every tick
+    fn add_behavior(&mut self, behavior: Box<dyn ChatBehavior>) {
+        self.logger().log(
+            &format!("Adding behavior {}", behavior.name()),
+            LogLevel::DEBUG,
+        );
+        self.chat_state_mut().behaviors.push(behavior);
+    }
+
+    /// What the behaviors see of the client
+    fn behavior_context(&self) -> BehaviorContext {
+        let state = self.chat_state();
+        BehaviorContext {
+            client_id: self.client_id(),
+            now: current_timestamp_ms(),
+            registered_servers: state.registered_servers.clone(),
+            available_clients: state.available_clients.clone(),
+        }
+    }
+
+    /// Invoke every behavior with `invoke`, then do what they asked
+    fn run_behaviors<F>(&mut self, mut invoke: F)
+    where
+        F: FnMut(&mut dyn ChatBehavior, &BehaviorContext) -> Vec<BehaviorAction>,
+    {
+        if self.chat_state().behaviors.is_empty() {
+            return;
+        }
+        let context = self.behavior_context();
+        let mut behaviors = std::mem::take(&mut self.chat_state_mut().behaviors);
+        let actions = behaviors
+            .iter_mut()
+            .map(|behavior| {
+                let actions = invoke(behavior.as_mut(), &context);
+                (behavior.name().to_string(), actions)
+            })
+            .collect::<Vec<_>>();
+        self.chat_state_mut().behaviors = behaviors;
+        for (name, actions) in actions {
+            for action in actions {
+                self.apply_behavior_action(&name, action);
+            }
+        }
+    }
+
+    /// Do what a behavior asked
+    fn apply_behavior_action(&mut self, behavior: &str, action: BehaviorAction) {
+        self.logger()
+            .log(&format!("Behavior {behavior}: {action:?}"), LogLevel::DEBUG);
+        match action {
+            BehaviorAction::Send {
+                server_id,
+                peer_id,
+                text,
+            } => {
+                self.send_chat_message(server_id, peer_id, text);
+            }
+            BehaviorAction::Broadcast(text) => {
+                let mut peers = self
+                    .behavior_context()
+                    .reachable_clients()
+                    .into_iter()
+                    .map(|(_, client_id)| client_id)
+                    .collect::<Vec<NodeId>>();
+                peers.sort_unstable();
+                peers.dedup();
+                for peer_id in peers {
+                    if let Err(err) = self.send_to(peer_id, text.clone()) {
+                        self.logger().log(&err, LogLevel::ERROR);
+                    }
+                }
+            }
+            BehaviorAction::Register(server_id) => self.register(server_id),
+            BehaviorAction::Report(report) => self.send_chat_event(ChatEvent::BehaviorReport {
+                behavior: behavior.to_string(),
+                report,
+            }),
+        }
+    }
+
+    /// Get the blocked and muted clients, and the rate limit
+    fn get_sender_policy(&self) -> &SenderPolicy {
+        self.chat_state().filter.policy()
+    }
+
+    /// Drop everything sent by a client
+    fn block_client(&mut self, client_id: NodeId) {
+        if self.chat_state_mut().filter.block(client_id) {
+            self.on_sender_policy_changed();
+        }
+    }
+
+    fn unblock_client(&mut self, client_id: NodeId) {
+        if self.chat_state_mut().filter.unblock(client_id) {
+            self.on_sender_policy_changed();
+        }
+    }
+
+    /// Save the messages of a client in the history, without reporting them
+    fn mute_client(&mut self, client_id: NodeId) {
+        if self.chat_state_mut().filter.mute(client_id) {
+            self.on_sender_policy_changed();
+        }
+    }
+
+    fn unmute_client(&mut self, client_id: NodeId) {
+        if self.chat_state_mut().filter.unmute(client_id) {
+            self.on_sender_policy_changed();
+        }
+    }
+
+    /// Limit how many messages every client can send, `None` to remove the limit
+    fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) {
+        self.chat_state_mut().filter.set_rate_limit(rate_limit);
+        self.on_sender_policy_changed();
+    }
+
+    /// Save the new sender policy in the configuration, and report it to the chat controller
+    fn on_sender_policy_changed(&mut self) {
+        let state = self.chat_state_mut();
+        state.config.sender_policy = state.filter.policy().clone();
+        let policy = state.config.sender_policy.clone();
+        self.save_config();
+        self.send_chat_event(ChatEvent::SenderPolicy(policy));
+    }
+
+    /// Save the configuration after a chat command changed it
+    fn save_config(&self) {
+        if let Err(err) = self.chat_state().config.save() {
+            self.logger().log(
+                &format!("Couldn't save the configuration: {err}"),
+                LogLevel::ERROR,
+            );
+        }
+    }
+
+    /// Register to the discovered chat servers selected by the auto-join policy.
+    /// The registrations to the servers that became unreachable are dropped,
+    /// so they are registered again when they can be reached
+    fn apply_auto_join(&mut self) {
+        let policy = self.chat_state().auto_join.clone();
+        if !policy.is_enabled() {
+            return;
+        }
+        let client_id = self.client_id();
+        let mut servers = self
+            .chat_state()
+            .available_clients
+            .keys()
+            .copied()
+            .collect::<Vec<NodeId>>();
+        servers.sort_unstable();
+        let mut route_lengths = vec![];
+        for server_id in servers {
+            let route = compute_route(self.topology(), client_id, server_id);
+            let route_length = (!route.is_empty()).then_some(route.len());
+            route_lengths.push((server_id, route_length));
+        }
+
+        for (server_id, route_length) in &route_lengths {
+            let state = self.chat_state().registrations.state(*server_id);
+            if route_length.is_none() && state != RegistrationState::Unregistered {
+                self.logger().log(
+                    &format!("Server {server_id} is unreachable, dropping the registration"),
+                    LogLevel::DEBUG,
+                );
+                let state = self.chat_state_mut();
+                state.registrations.unregister(*server_id);
+                state.registered_servers.retain(|id| id != server_id);
+                self.notify_registration(*server_id);
+            }
+        }
+
+        for server_id in policy.select(&route_lengths) {
+            // The failed registrations are only tried again after the server was unreachable
+            if self.chat_state().registrations.state(server_id) == RegistrationState::Unregistered {
+                self.logger()
+                    .log(&format!("Auto-joining server {server_id}"), LogLevel::DEBUG);
+                self.register(server_id);
+            }
+        }
+    }
+
+    /// Get the registration state for a server
+    fn get_registration_state(&self, server_id: NodeId) -> RegistrationState {
+        self.chat_state().registrations.state(server_id)
+    }
+
+    /// Report the registration state of a server to the chat controller
+    fn notify_registration(&self, server_id: NodeId) {
+        let state = self.chat_state().registrations.state(server_id);
+        self.send_chat_event(ChatEvent::RegistrationChanged { server_id, state });
+    }
+
+    /// Register to a server, unless the client is already registered or waiting for the answer.
+    /// The request is sent again if `ClientRegistered` doesn't arrive in time
+    fn register(&mut self, server_id: NodeId) {
+        let now = current_timestamp_ms();
+        if !self.chat_state_mut().registrations.start(server_id, now) {
+            self.logger().log(
+                &format!("Already registered or registering to server {server_id}"),
+                LogLevel::DEBUG,
+            );
+            return;
+        }
+        self.send_register_request(server_id);
+        self.notify_registration(server_id);
+    }
+
+    /// Send a 'register' message to a server
+    fn send_register_request(&mut self, server_id: NodeId) {
+        let client_id = self.client_id();
+        self.logger().log(
+            &format!("Client {client_id} registering to server {server_id}"),
+            LogLevel::DEBUG,
+        );
+        let request = ChatRequestWrapper::Chat(ChatRequest::Register(client_id));
+        let request_json = serde_json::to_string(&request).unwrap_or_default();
+        self.send_message(server_id, request_json);
+        self.chat_state_mut()
+            .monitor
+            .on_request_sent(server_id, current_timestamp_ms());
+    }
+
+    /// Leave a server. The client stops considering itself registered,
+    /// the server isn't notified as the protocol has no request for it
+    fn unregister(&mut self, server_id: NodeId) {
+        self.logger().log(
+            &format!("Unregistering from server {server_id}"),
+            LogLevel::DEBUG,
+        );
+        let state = self.chat_state_mut();
+        state.registrations.unregister(server_id);
+        state.registered_servers.retain(|id| *id != server_id);
+        self.notify_registration(server_id);
+    }
+
+    /// Fail over the registered servers that can't be reached anymore,
+    /// and the servers that left a request unanswered for longer than `server_timeout_ms`
+    fn check_servers(&mut self, now: u128) {
+        let client_id = self.client_id();
+        let mut down = self.chat_state().monitor.timed_out(now);
+        let servers = self.chat_state().registered_servers.clone();
+        for server_id in servers {
+            if !down.contains(&server_id)
+                && compute_route(self.topology(), client_id, server_id).is_empty()
+            {
+                down.push(server_id);
+            }
+        }
+        for server_id in down {
+            self.fail_over(server_id);
+        }
+    }
+
+    /// The server to use instead of one that went down: the nearest registered server,
+    /// or else the nearest discovered one. The servers down or unreachable aren't considered
+    fn find_alternative_server(&mut self) -> Option<NodeId> {
+        let client_id = self.client_id();
+        let state = self.chat_state();
+        let registered = state.registered_servers.clone();
+        let mut servers = state
+            .available_clients
+            .keys()
+            .chain(registered.iter())
+            .copied()
+            .filter(|server_id| !state.monitor.is_down(*server_id))
+            .collect::<Vec<NodeId>>();
+        servers.sort_unstable();
+        servers.dedup();
+        let mut candidates = vec![];
+        for server_id in servers {
+            let route = compute_route(self.topology(), client_id, server_id);
+            if !route.is_empty() {
+                candidates.push((!registered.contains(&server_id), route.len(), server_id));
+            }
+        }
+        candidates
+            .into_iter()
+            .min()
+            .map(|(_, _, server_id)| server_id)
+    }
+
+    /// A server went down: forget it, register to an alternative server,
+    /// and move there the queued messages, the messages without a receipt and the transfers.
+    /// Without an alternative, the messages without a receipt fail
+    fn fail_over(&mut self, server_id: NodeId) {
+        let state = self.chat_state_mut();
+        if !state.monitor.mark_down(server_id) {
+            return;
+        }
+        let registered = state.registered_servers.contains(&server_id);
+        state.registered_servers.retain(|id| *id != server_id);
+        state.available_clients.remove(&server_id);
+        state.client_lists_updated.remove(&server_id);
+        let undelivered = state.receipts.pending_through(server_id);
+        state.receipts.forget_server(server_id);
+        let routed = state.routed.through(server_id);
+        for id in &routed {
+            if let Some(message) = state.routed.get_mut(*id) {
+                message.server_id = None;
+            }
+        }
+        if registered {
+            state.registrations.unregister(server_id);
+            self.notify_registration(server_id);
+        }
+
+        let alternative = self.find_alternative_server();
+        self.logger().log(
+            &format!("Server {server_id} is down, moving to {alternative:?}"),
+            LogLevel::ERROR,
+        );
+        self.send_chat_event(ChatEvent::ServerDown {
+            server_id,
+            alternative,
+        });
+
+        let Some(alternative) = alternative else {
+            for id in undelivered {
+                let state = self.chat_state_mut();
+                state.undelivered.remove(&id);
+                // The messages of `send_to` are sent again when a client list shows their recipient
+                if state.routed.get(id).is_some() {
+                    continue;
+                }
+                if let Some(message) = state.receipts.on_failed(id) {
+                    self.notify_message_status(&message);
+                }
+            }
+            return;
+        };
+        self.register(alternative);
+
+        let now = current_timestamp_ms();
+        let state = self.chat_state_mut();
+        state.transfers.move_server(server_id, alternative);
+        let mut queued = state.outbox.move_server(server_id, alternative) > 0;
+        for id in undelivered {
+            let state = self.chat_state_mut();
+            let Some((peer_id, content, _)) = state.undelivered.remove(&id) else {
+                continue;
+            };
+            if state.routed.get(id).is_some() {
+                continue;
+            }
+            match content {
+                // Held until the client list of the alternative shows the recipient
+                MessageContent::Text(text) => {
+                    state.outbox.push(QueuedMessage {
+                        id,
+                        server_id: alternative,
+                        peer_id,
+                        text,
+                        queued_at: now,
+                    });
+                    state.receipts.on_message_queued(id, alternative, peer_id);
+                    self.send_chat_event(ChatEvent::MessageStatus {
+                        id,
+                        server_id: alternative,
+                        peer_id,
+                        status: DeliveryStatus::Queued,
+                    });
+                    queued = true;
+                }
+                content => self.transmit_chat_message(id, alternative, peer_id, content),
+            }
+        }
+        for id in routed {
+            self.route_message(id);
+        }
+        // Otherwise the client list is requested once registered
+        if queued && self.chat_state().registered_servers.contains(&alternative) {
+            self.send_client_list_req(alternative);
+        }
+    }
+
+    /// Send a chat message to another client, returns the id of the message.
+    /// Its delivery status is reported with `ChatEvent::MessageStatus`
+    fn send_chat_message(&mut self, server_id: NodeId, to: NodeId, message: String) -> u64 {
+        self.logger().log(
+            &format!("Sending message to {to} using {server_id}"),
+            LogLevel::DEBUG,
+        );
+        self.record_message(server_id, to, Direction::Sent, message.clone());
+        let id = rand::random();
+        // Without a client list for the server, the recipient can't be checked
+        let listed = match self.chat_state().available_clients.get(&server_id) {
+            Some(clients) => clients.contains(&to),
+            None => true,
+        };
+        if listed {
+            self.transmit_chat_message(id, server_id, to, MessageContent::Text(message));
+        } else {
+            self.queue_chat_message(id, server_id, to, message);
+        }
+        id
+    }
+
+    /// Send any content to another client through a server, returns the id of the message.
+    /// Unlike `send_chat_message`, it's sent even if the recipient isn't in the client list
+    fn send_chat_content(&mut self, server_id: NodeId, to: NodeId, content: MessageContent) -> u64 {
+        self.logger().log(
+            &format!("Sending {} to {to} using {server_id}", content.summary()),
+            LogLevel::DEBUG,
+        );
+        self.record_message(server_id, to, Direction::Sent, content.summary());
+        let id = rand::random();
+        self.transmit_chat_message(id, server_id, to, content);
+        id
+    }
+
+    /// Send a message removed from the history of both clients `ttl_ms` after being sent,
+    /// returns the id of the message. The recipient discards it if it arrives later
+    fn send_ephemeral(
+        &mut self,
+        server_id: NodeId,
+        to: NodeId,
+        content: MessageContent,
+        ttl_ms: u64,
+    ) -> u64 {
+        self.logger().log(
+            &format!(
+                "Sending {} to {to} using {server_id}, expiring in {ttl_ms} ms",
+                content.summary()
+            ),
+            LogLevel::DEBUG,
+        );
+        let id = rand::random();
+        let sent_at = current_timestamp_ms();
+        let expires_at = sent_at.saturating_add(u128::from(ttl_ms));
+        let entry = self.record_entry(HistoryEntry {
+            server_id,
+            peer_id: to,
+            direction: Direction::Sent,
+            timestamp: sent_at,
+            text: content.summary(),
+            id: Some(id),
+            expires_at: Some(expires_at),
+        });
+        let state = self.chat_state_mut();
+        state.ephemerals.on_sent(id, sent_at, ttl_ms);
+        state.ephemerals.track(EphemeralMessage {
+            id,
+            entry,
+            expires_at,
+        });
+        self.transmit_chat_message(id, server_id, to, content);
+        id
+    }
+
+    /// Tell a peer whether this client is typing to it. It has no delivery status,
+    /// and isn't saved in the history
+    fn send_activity(&mut self, server_id: NodeId, to: NodeId, activity: Activity) {
+        self.send_chat_payload(server_id, to, &ChatPayload::Activity(activity));
+        self.chat_state_mut().receipts.on_untracked_sent(server_id);
+    }
+
+    /// Whether a peer is typing in a conversation
+    fn is_typing(&self, server_id: NodeId, peer_id: NodeId) -> bool {
+        self.chat_state().activity.is_typing(server_id, peer_id)
+    }
+
+    /// An activity arrived from a peer: report it if it changed.
+    /// It goes through the sender filter like a message: the muted clients aren't reported,
+    /// and the activities of blocked and rate limited clients are dropped
+    fn on_activity_received(&mut self, server_id: NodeId, from: NodeId, activity: Activity) {
+        let now = current_timestamp_ms();
+        let verdict = self.chat_state_mut().filter.check(from, now);
+        if verdict != Verdict::Deliver {
+            self.logger().log(
+                &format!("Dropping activity from {from}: {verdict:?}"),
+                LogLevel::DEBUG,
+            );
+            return;
+        }
+        if self
+            .chat_state_mut()
+            .activity
+            .on_activity(server_id, from, activity, now)
+        {
+            self.send_chat_event(ChatEvent::PeerActivity {
+                server_id,
+                peer_id: from,
+                activity,
+            });
+        }
+    }
+
+    /// Send a message that was already recorded, and start following its delivery.
+    /// An ephemeral message is sent again with its TTL
+    fn transmit_chat_message(
+        &mut self,
+        id: u64,
+        server_id: NodeId,
+        to: NodeId,
+        content: MessageContent,
+    ) {
+        let state = self.chat_state_mut();
+        let message = ChatPayload::message(id, content.clone());
+        let message = match state.ephemerals.ttl(id) {
+            Some((sent_at, ttl_ms)) => ChatPayload::Ephemeral {
+                sent_at,
+                ttl_ms,
+                payload: Box::new(message),
+            },
+            None => message,
+        };
+        let seq = state.sequencer.seq_for(to, id);
+        let payload = ChatPayload::Sequenced {
+            session: state.sequencer.session(),
+            seq,
+            since: state.sequencer.since(to),
+            payload: Box::new(message),
+        };
+        let chat_message_json = self.send_chat_payload(server_id, to, &payload);
+        let state = self.chat_state_mut();
+        state.receipts.on_message_sent(id, server_id, to);
+        state
+            .undelivered
+            .insert(id, (to, content, current_timestamp_ms()));
+
+        // Notify the controller that the message was sent
+        let _res = self
+            .sim_controller_sender()
+            .send(SimControllerResponseWrapper::Event(
+                SimControllerEvent::ChatMessageSent(server_id, to, chat_message_json),
+            ));
+        self.send_chat_event(ChatEvent::MessageStatus {
+            id,
+            server_id,
+            peer_id: to,
+            status: DeliveryStatus::Pending,
+        });
+    }
+
+    /// Send a message to a client through a server both are registered to.
+    /// The nearest server is tried first, then the others if the receipt doesn't arrive.
+    /// If no client list shows the recipient, the lists of the registered servers are requested first.
+    /// Returns the id of the message
+    /// # Errors
+    /// Returns an error if the client isn't registered to any server
+    fn send_to(&mut self, peer_id: NodeId, text: String) -> Result<u64, String> {
+        if self.chat_state().registered_servers.is_empty() {
+            return Err("Not registered to any server".to_string());
+        }
+        let id = rand::random();
+        self.chat_state_mut().routed.insert(RoutedMessage {
+            id,
+            peer_id,
+            text,
+            server_id: None,
+            tried: vec![],
+            since: current_timestamp_ms(),
+        });
+        if !self.route_message(id) {
+            self.logger().log(
+                &format!("No known server with {peer_id}, refreshing the client lists"),
+                LogLevel::DEBUG,
+            );
+            let servers = self.chat_state().registered_servers.clone();
+            for server_id in servers {
+                self.send_client_list_req(server_id);
+            }
+        }
+        Ok(id)
+    }
+
+    /// The reachable registered servers whose client list contains `peer_id`,
+    /// from the one with the shortest route
+    fn get_shared_servers(&mut self, peer_id: NodeId) -> Vec<NodeId> {
+        let state = self.chat_state();
+        let servers = state
+            .registered_servers
+            .iter()
+            .filter(|server_id| {
+                state
+                    .available_clients
+                    .get(server_id)
+                    .is_some_and(|clients| clients.contains(&peer_id))
+            })
+            .copied()
+            .collect::<Vec<NodeId>>();
+        let client_id = self.client_id();
+        let mut shared = vec![];
+        for server_id in servers {
+            let route = compute_route(self.topology(), client_id, server_id);
+            if !route.is_empty() {
+                shared.push((route.len(), server_id));
+            }
+        }
+        shared.sort_unstable();
+        shared.into_iter().map(|(_, server_id)| server_id).collect()
+    }
+
+    /// Send a message of `send_to` through the best shared server it wasn't sent through yet.
+    /// Returns false if there is none
+    fn route_message(&mut self, id: u64) -> bool {
+        let Some(message) = self.chat_state().routed.get(id).cloned() else {
+            return false;
+        };
+        let Some(server_id) = self
+            .get_shared_servers(message.peer_id)
+            .into_iter()
+            .find(|server_id| !message.tried.contains(server_id))
+        else {
+            return false;
+        };
+        // Saved in the conversation of the first server it's sent through
+        if message.tried.is_empty() {
+            self.record_message(
+                server_id,
+                message.peer_id,
+                Direction::Sent,
+                message.text.clone(),
+            );
+        }
+        if let Some(routed) = self.chat_state_mut().routed.get_mut(id) {
+            routed.server_id = Some(server_id);
+            routed.tried.push(server_id);
+            routed.since = current_timestamp_ms();
+        }
+        self.logger().log(
+            &format!(
+                "Sending message {id} to {} using {server_id}",
+                message.peer_id
+            ),
+            LogLevel::DEBUG,
+        );
+        let content = MessageContent::Text(message.text);
+        self.transmit_chat_message(id, server_id, message.peer_id, content);
+        true
+    }
+
+    /// No shared server delivered a message of `send_to`
+    fn fail_routed_message(&mut self, id: u64) {
+        let state = self.chat_state_mut();
+        state.undelivered.remove(&id);
+        let Some(message) = state.routed.remove(id) else {
+            return;
+        };
+        self.logger().log(
+            &format!("Message {id} to {} couldn't be delivered", message.peer_id),
+            LogLevel::ERROR,
+        );
+        if let Some(tracked) = self.chat_state_mut().receipts.on_failed(id) {
+            self.notify_message_status(&tracked);
+        }
+        self.send_chat_event(ChatEvent::SendFailed {
+            id,
+            peer_id: message.peer_id,
+        });
+    }
+
+    /// Send a message to every client of a server, except this one. If the client list is older than
+    /// `client_list_max_age_ms`, it's requested first. The outcome for every recipient is reported
+    /// with a single `ChatEvent::BroadcastResult`. Returns the id of the broadcast
+    fn broadcast(&mut self, server_id: NodeId, text: String) -> u64 {
+        let id = rand::random();
+        let now = current_timestamp_ms();
+        let state = self.chat_state_mut();
+        state.broadcasts.start(id, server_id, text, now);
+        let max_age = u128::from(state.client_list_max_age_ms);
+        let fresh = state
+            .client_lists_updated
+            .get(&server_id)
+            .is_some_and(|updated| updated + max_age > now);
+        if fresh {
+            self.send_broadcast(id);
+        } else {
+            self.logger().log(
+                &format!("Client list of {server_id} is stale, requesting it for broadcast {id}"),
+                LogLevel::DEBUG,
+            );
+            self.send_client_list_req(server_id);
+        }
+        id
+    }
+
+    /// Send a broadcast to the clients in the last client list of its server
+    fn send_broadcast(&mut self, id: u64) {
+        let client_id = self.client_id();
+        let state = self.chat_state();
+        let Some(broadcast) = state.broadcasts.get(id) else {
+            return;
+        };
+        let (server_id, text) = (broadcast.server_id, broadcast.text.clone());
+        let clients = state
+            .available_clients
+            .get(&server_id)
+            .cloned()
+            .unwrap_or_default();
+        let recipients = clients
+            .into_iter()
+            .filter(|peer_id| *peer_id != client_id)
+            .map(|peer_id| {
+                (
+                    peer_id,
+                    self.send_chat_message(server_id, peer_id, text.clone()),
+                )
+            })
+            .collect::<Vec<(NodeId, u64)>>();
+        self.logger().log(
+            &format!("Broadcast {id} sent to {} clients", recipients.len()),
+            LogLevel::DEBUG,
+        );
+        self.chat_state_mut()
+            .broadcasts
+            .on_sent(id, recipients, current_timestamp_ms());
+    }
+
+    /// Get the group conversations
+    fn get_groups(&self) -> &Groups {
+        &self.chat_state().groups
+    }
+
+    /// Create a group with this client and `members`, and tell them about it.
+    /// Returns the id of the group
+    fn create_group(&mut self, name: String, mut members: Vec<NodeId>) -> u64 {
+        let group_id = rand::random();
+        members.push(self.client_id());
+        self.chat_state_mut().groups.upsert(group_id, name, members);
+        self.logger()
+            .log(&format!("Created group {group_id}"), LogLevel::DEBUG);
+        self.announce_group(group_id, &[]);
+        group_id
+    }
+
+    /// Add a member to a group, and tell every member about it
+    /// # Errors
+    /// Returns an error if the client isn't a member of the group
+    fn add_group_member(&mut self, group_id: u64, member: NodeId) -> Result<(), String> {
+        let group = self
+            .chat_state_mut()
+            .groups
+            .get_mut(group_id)
+            .ok_or_else(|| format!("Unknown group {group_id}"))?;
+        let mut members = group.members.clone();
+        members.push(member);
+        group.set_members(members);
+        self.announce_group(group_id, &[]);
+        Ok(())
+    }
+
+    /// Remove a member from a group, and tell every member about it, including the removed one.
+    /// If `member` is this client, it leaves the group
+    /// # Errors
+    /// Returns an error if the client isn't a member of the group
+    fn remove_group_member(&mut self, group_id: u64, member: NodeId) -> Result<(), String> {
+        let group = self
+            .chat_state_mut()
+            .groups
+            .get_mut(group_id)
+            .ok_or_else(|| format!("Unknown group {group_id}"))?;
+        let mut members = group.members.clone();
+        members.retain(|id| *id != member);
+        group.set_members(members);
+        self.announce_group(group_id, &[member]);
+        if member == self.client_id() {
+            let state = self.chat_state_mut();
+            state.groups.remove(group_id);
+            state.group_reorder.remove(&group_id);
+            self.send_chat_event(ChatEvent::GroupLeft(group_id));
+        }
+        Ok(())
+    }
+
+    /// Send a message to every other member of a group. Returns its sequence number
+    /// # Errors
+    /// Returns an error if the client isn't a member of the group
+    fn send_group_message(&mut self, group_id: u64, text: String) -> Result<u64, String> {
+        let client_id = self.client_id();
+        let state = self.chat_state_mut();
+        let session = state.sequencer.session();
+        let group = state
+            .groups
+            .get_mut(group_id)
+            .ok_or_else(|| format!("Unknown group {group_id}"))?;
+        let seq = group.take_seq();
+        group.insert(GroupMessage {
+            from: client_id,
+            seq,
+            timestamp: current_timestamp_ms(),
+            text: text.clone(),
+        });
+        let recipients = group
+            .members
+            .iter()
+            .filter(|member| **member != client_id)
+            .map(|member| (*member, group.since(*member)))
+            .collect::<Vec<(NodeId, u64)>>();
+        let mut unreachable = vec![];
+        for (member, since) in recipients {
+            let payload = ChatPayload::GroupText {
+                group_id,
+                session,
+                seq,
+                since,
+                text: text.clone(),
+            };
+            if !self.send_group_payload(member, &payload) {
+                unreachable.push(member);
+            }
+        }
+        self.report_unreachable_members(group_id, unreachable);
+        Ok(seq)
+    }
+
+    /// Send the name and the members of a group to every member and to `also`,
+    /// and report them to the chat controller
+    fn announce_group(&mut self, group_id: u64, also: &[NodeId]) {
+        let client_id = self.client_id();
+        let Some(group) = self.chat_state().groups.get(group_id) else {
+            return;
+        };
+        let (name, members) = (group.name.clone(), group.members.clone());
+        let payload = ChatPayload::GroupInfo {
+            group_id,
+            name: name.clone(),
+            members: members.clone(),
+        };
+        let mut recipients = members.clone();
+        recipients.extend_from_slice(also);
+        recipients.sort_unstable();
+        recipients.dedup();
+        let mut unreachable = vec![];
+        for member in recipients.into_iter().filter(|member| *member != client_id) {
+            if !self.send_group_payload(member, &payload) {
+                unreachable.push(member);
+            }
+        }
+        self.send_chat_event(ChatEvent::GroupChanged {
+            group_id,
+            name,
+            members,
+        });
+        self.report_unreachable_members(group_id, unreachable);
+    }
+
+    /// Tell the chat controller about the members a group payload couldn't be sent to
+    fn report_unreachable_members(&self, group_id: u64, members: Vec<NodeId>) {
+        if members.is_empty() {
+            return;
+        }
+        self.send_chat_event(ChatEvent::GroupMembersUnreachable { group_id, members });
+    }
+
+    /// Send a group payload to a member through the nearest server they share.
+    /// Returns false if there is no shared server
+    fn send_group_payload(&mut self, member: NodeId, payload: &ChatPayload) -> bool {
+        let Some(server_id) = self.get_shared_servers(member).first().copied() else {
+            self.logger().log(
+                &format!("No shared server with group member {member}"),
+                LogLevel::ERROR,
+            );
+            return false;
+        };
+        self.send_chat_payload(server_id, member, payload);
+        self.chat_state_mut().receipts.on_untracked_sent(server_id);
+        true
+    }
+
+    /// A group payload arrived from `from`: update the group, or add the message to its thread
+    fn on_group_payload_received(&mut self, from: NodeId, payload: ChatPayload) {
+        let client_id = self.client_id();
+        match payload {
+            ChatPayload::GroupInfo {
+                group_id,
+                name,
+                members,
+            } => {
+                // Only the members can change a known group
+                let allowed = match self.chat_state().groups.get(group_id) {
+                    Some(group) => group.members.contains(&from),
+                    None => true,
+                };
+                if !allowed {
+                    self.logger().log(
+                        &format!("{from} can't change group {group_id}, not a member"),
+                        LogLevel::ERROR,
+                    );
+                    return;
+                }
+                if members.contains(&client_id) {
+                    self.chat_state_mut()
+                        .groups
+                        .upsert(group_id, name.clone(), members.clone());
+                    self.send_chat_event(ChatEvent::GroupChanged {
+                        group_id,
+                        name,
+                        members,
+                    });
+                } else if self.chat_state_mut().groups.remove(group_id).is_some() {
+                    self.chat_state_mut().group_reorder.remove(&group_id);
+                    self.send_chat_event(ChatEvent::GroupLeft(group_id));
+                }
+            }
+            ChatPayload::GroupText {
+                group_id,
+                session,
+                seq,
+                since,
+                text,
+            } => {
+                let verdict = self
+                    .chat_state_mut()
+                    .filter
+                    .check(from, current_timestamp_ms());
+                if matches!(verdict, Verdict::Blocked | Verdict::RateLimited) {
+                    self.logger().log(
+                        &format!("Dropping group message from {from}: {verdict:?}"),
+                        LogLevel::DEBUG,
+                    );
+                    return;
+                }
+                let is_member = match self.chat_state().groups.get(group_id) {
+                    Some(group) => group.members.contains(&from),
+                    None => {
+                        self.logger().log(
+                            &format!("Dropping message from {from} for unknown group {group_id}"),
+                            LogLevel::ERROR,
+                        );
+                        return;
+                    }
+                };
+                if !is_member {
+                    self.logger().log(
+                        &format!("Dropping message from {from} for group {group_id}, not a member"),
+                        LogLevel::ERROR,
+                    );
+                    return;
+                }
+                let now = current_timestamp_ms();
+                let message = GroupMessage {
+                    from,
+                    seq,
+                    timestamp: now,
+                    text,
+                };
+                let state = self.chat_state_mut();
+                let (window, timeout_ms) =
+                    (state.config.reorder_window, state.config.reorder_timeout_ms);
+                let ordered = state
+                    .group_reorder
+                    .entry(group_id)
+                    .or_insert_with(|| ReorderBuffer::new(window, timeout_ms))
+                    .receive(from, session, seq, since, now, message);
+                self.on_group_messages_ordered(group_id, ordered);
+            }
+            _ => {}
+        }
+    }
+
+    /// Add the group messages put back in order to the thread, and log the gaps.
+    /// The messages of muted clients aren't reported
+    fn on_group_messages_ordered(&mut self, group_id: u64, ordered: Vec<Ordered<GroupMessage>>) {
+        for result in ordered {
+            match result {
+                Ordered::Deliver { from, message } => {
+                    let state = self.chat_state_mut();
+                    let muted = state.filter.is_muted(from);
+                    let inserted = state
+                        .groups
+                        .get_mut(group_id)
+                        .is_some_and(|group| group.insert(message.clone()));
+                    if inserted && !muted {
+                        self.send_chat_event(ChatEvent::GroupMessage { group_id, message });
+                    }
+                }
+                Ordered::Gap { from, first, last } => {
+                    self.logger().log(
+                        &format!(
+                            "Messages {first} to {last} from {from} in group {group_id} are missing"
+                        ),
+                        LogLevel::ERROR,
+                    );
+                }
+                Ordered::Duplicate { from, message } => {
+                    self.logger().log(
+                        &format!(
+                            "Dropping duplicate message {} from {from} in group {group_id}",
+                            message.seq
+                        ),
+                        LogLevel::DEBUG,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Get the progress of a file transfer, sent or received
+    fn get_transfer(&self, transfer_id: u64) -> Option<TransferProgress> {
+        self.chat_state().transfers.progress(transfer_id)
+    }
+
+    /// Offer a file to another client through a server.
+    /// The chunks are sent once the offer is accepted. Returns the id of the transfer
+    fn send_file(
+        &mut self,
+        server_id: NodeId,
+        to: NodeId,
+        name: String,
+        mime_type: String,
+        data: Vec<u8>,
+    ) -> u64 {
+        let transfer_id = rand::random();
+        let state = self.chat_state_mut();
+        let mut transfer = OutgoingTransfer::new(
+            transfer_id,
+            server_id,
+            to,
+            name,
+            mime_type,
+            data,
+            state.transfers.chunk_size,
+        );
+        let offer = transfer.offer(current_timestamp_ms());
+        let progress = transfer.progress();
+        state.transfers.outgoing.insert(transfer_id, transfer);
+        self.logger().log(
+            &format!("Offering file {transfer_id} to {to} using {server_id}"),
+            LogLevel::DEBUG,
+        );
+        self.send_file_message(server_id, to, offer);
+        self.send_chat_event(ChatEvent::TransferProgress(progress));
+        transfer_id
+    }
+
+    /// Accept a file offered by another client
+    /// # Errors
+    /// Returns an error if the file wasn't offered, or the offer was already answered
+    fn accept_file(&mut self, transfer_id: u64) -> Result<(), String> {
+        let transfer = self
+            .chat_state_mut()
+            .transfers
+            .incoming
+            .get_mut(&transfer_id)
+            .ok_or_else(|| format!("Unknown transfer {transfer_id}"))?;
+        if transfer.state != TransferState::Offered {
+            return Err(format!("Transfer {transfer_id} was already answered"));
+        }
+        let accept = transfer.accept(current_timestamp_ms());
+        let (server_id, peer_id, progress) =
+            (transfer.server_id, transfer.peer_id, transfer.progress());
+        self.send_file_message(server_id, peer_id, accept);
+        self.send_chat_event(ChatEvent::TransferProgress(progress));
+        // An empty file has no chunks to wait for
+        self.complete_incoming_transfer(transfer_id);
+        Ok(())
+    }
+
+    /// Refuse a file offered by another client
+    /// # Errors
+    /// Returns an error if the file wasn't offered, or the offer was already answered
+    fn reject_file(&mut self, transfer_id: u64) -> Result<(), String> {
+        let transfer = self
+            .chat_state_mut()
+            .transfers
+            .incoming
+            .get_mut(&transfer_id)
+            .ok_or_else(|| format!("Unknown transfer {transfer_id}"))?;
+        if transfer.state != TransferState::Offered {
+            return Err(format!("Transfer {transfer_id} was already answered"));
+        }
+        transfer.state = TransferState::Rejected;
+        let (server_id, peer_id, progress) =
+            (transfer.server_id, transfer.peer_id, transfer.progress());
+        self.chat_state_mut().transfers.on_finished(transfer_id);
+        self.send_file_message(server_id, peer_id, FileMessage::Reject { transfer_id });
+        self.send_chat_event(ChatEvent::TransferProgress(progress));
+        Ok(())
+    }
+
+    /// Continue a file transfer that was interrupted, without sending again the chunks already received
+    /// # Errors
+    /// Returns an error if the transfer is unknown, or it isn't in progress or interrupted
+    fn resume_transfer(&mut self, transfer_id: u64) -> Result<(), String> {
+        let now = current_timestamp_ms();
+        let transfers = &mut self.chat_state_mut().transfers;
+        let resumable = |state: &TransferState| {
+            matches!(
+                state,
+                TransferState::Transferring | TransferState::Interrupted
+            )
+        };
+        let (server_id, peer_id, message, progress) =
+            if let Some(transfer) = transfers.outgoing.get_mut(&transfer_id) {
+                if !resumable(&transfer.state) {
+                    return Err(format!("Transfer {transfer_id} can't be resumed"));
+                }
+                let offer = transfer.resume(now);
+                (
+                    transfer.server_id,
+                    transfer.peer_id,
+                    offer,
+                    transfer.progress(),
+                )
+            } else if let Some(transfer) = transfers.incoming.get_mut(&transfer_id) {
+                if !resumable(&transfer.state) {
+                    return Err(format!("Transfer {transfer_id} can't be resumed"));
+                }
+                let accept = transfer.resume(now);
+                (
+                    transfer.server_id,
+                    transfer.peer_id,
+                    accept,
+                    transfer.progress(),
+                )
+            } else {
+                return Err(format!("Unknown transfer {transfer_id}"));
+            };
+        self.logger()
+            .log(&format!("Resuming transfer {transfer_id}"), LogLevel::DEBUG);
+        self.send_file_message(server_id, peer_id, message);
+        self.send_chat_event(ChatEvent::TransferProgress(progress));
+        Ok(())
+    }
+
+    /// Send a message of a file transfer. It gets no receipt, the transfer has its own acknowledgements
+    fn send_file_message(&mut self, server_id: NodeId, to: NodeId, message: FileMessage) {
+        self.send_chat_payload(server_id, to, &ChatPayload::File(message));
+        self.chat_state_mut().receipts.on_untracked_sent(server_id);
+    }
+
+    /// Send some chunks of a file being sent
+    fn send_file_chunks(&mut self, transfer_id: u64, chunks: &[u64]) {
+        let Some(transfer) = self.chat_state().transfers.outgoing.get(&transfer_id) else {
+            return;
+        };
+        let (server_id, peer_id) = (transfer.server_id, transfer.peer_id);
+        let messages = chunks
+            .iter()
+            .map(|index| transfer.chunk(*index))
+            .collect::<Vec<FileMessage>>();
+        for message in messages {
+            self.send_file_message(server_id, peer_id, message);
+        }
+    }
+
+    /// A message of a file transfer arrived from `from`
+    fn on_file_message_received(&mut self, server_id: NodeId, from: NodeId, message: FileMessage) {
+        let now = current_timestamp_ms();
+        match message {
+            offer @ FileMessage::Offer { .. } => self.on_file_offered(server_id, from, &offer),
+            FileMessage::Chunk {
+                transfer_id,
+                index,
+                data,
+            } => self.on_file_chunk_received(from, transfer_id, index, data),
+            FileMessage::Accept { transfer_id, have } => {
+                let transfers = &mut self.chat_state_mut().transfers;
+                let window = transfers.window;
+                let Some(transfer) = transfers
+                    .outgoing
+                    .get_mut(&transfer_id)
+                    .filter(|transfer| transfer.peer_id == from)
+                else {
+                    return;
+                };
+                // A late acceptance also resumes an interrupted transfer
+                if !matches!(
+                    transfer.state,
+                    TransferState::Offered
+                        | TransferState::Transferring
+                        | TransferState::Interrupted
+                ) {
+                    return;
+                }
+                let chunks = transfer.on_accepted(&have, window, now);
+                let progress = transfer.progress();
+                self.send_file_chunks(transfer_id, &chunks);
+                self.send_chat_event(ChatEvent::TransferProgress(progress));
+            }
+            FileMessage::Ack { transfer_id, index } => {
+                let transfers = &mut self.chat_state_mut().transfers;
+                let window = transfers.window;
+                let Some(transfer) = transfers
+                    .outgoing
+                    .get_mut(&transfer_id)
+                    .filter(|transfer| transfer.peer_id == from)
+                else {
+                    return;
+                };
+                let chunks = transfer.on_ack(index, window, now);
+                let progress = transfer.progress();
+                self.send_file_chunks(transfer_id, &chunks);
+                self.send_chat_event(ChatEvent::TransferProgress(progress));
+            }
+            FileMessage::Reject { transfer_id } => {
+                self.finish_outgoing_transfer(from, transfer_id, TransferState::Rejected);
+            }
+            FileMessage::Complete { transfer_id } => {
+                self.finish_outgoing_transfer(from, transfer_id, TransferState::Completed);
+            }
+            FileMessage::Failed {
+                transfer_id,
+                reason,
+            } => {
+                self.finish_outgoing_transfer(from, transfer_id, TransferState::Failed(reason));
+            }
+        }
+    }
+
+    /// A file was offered. If it's known, the sender resumes the transfer:
+    /// answer with the chunks already received, or with how the transfer ended
+    fn on_file_offered(&mut self, server_id: NodeId, from: NodeId, offer: &FileMessage) {
+        let Some(offered) = IncomingTransfer::from_offer(server_id, from, offer) else {
+            return;
+        };
+        let transfer_id = offered.transfer_id;
+        let now = current_timestamp_ms();
+        let Some(transfer) = self
+            .chat_state_mut()
+            .transfers
+            .incoming
+            .get_mut(&transfer_id)
+        else {
+            let event = ChatEvent::FileOffered {
+                transfer_id,
+                from,
+                name: offered.name.clone(),
+                mime_type: offered.mime_type.clone(),
+                size: offered.size,
+            };
+            let progress = offered.progress();
+            self.chat_state_mut()
+                .transfers
+                .incoming
+                .insert(transfer_id, offered);
+            self.send_chat_event(event);
+            self.send_chat_event(ChatEvent::TransferProgress(progress));
+            return;
+        };
+        if transfer.peer_id != from {
+            self.logger().log(
+                &format!("Ignoring offer {transfer_id} from {from}, the id is already used"),
+                LogLevel::ERROR,
+            );
+            return;
+        }
+        // The sender can resume through another server
+        transfer.server_id = server_id;
+        let answer = match transfer.state.clone() {
+            TransferState::Offered => None,
+            TransferState::Transferring | TransferState::Interrupted => Some(transfer.resume(now)),
+            TransferState::Completed => Some(FileMessage::Complete { transfer_id }),
+            TransferState::Rejected => Some(FileMessage::Reject { transfer_id }),
+            TransferState::Failed(reason) => Some(FileMessage::Failed {
+                transfer_id,
+                reason,
+            }),
+        };
+        let progress = transfer.progress();
+        if let Some(answer) = answer {
+            self.send_file_message(server_id, from, answer);
+        }
+        self.send_chat_event(ChatEvent::TransferProgress(progress));
+    }
+
+    /// A chunk of a file arrived: store it, acknowledge it, and check if the file is complete
+    fn on_file_chunk_received(
+        &mut self,
+        from: NodeId,
+        transfer_id: u64,
+        index: u64,
+        data: Vec<u8>,
+    ) {
+        let now = current_timestamp_ms();
+        let Some(transfer) = self
+            .chat_state_mut()
+            .transfers
+            .incoming
+            .get_mut(&transfer_id)
+            .filter(|transfer| transfer.peer_id == from)
+        else {
+            return;
+        };
+        let server_id = transfer.server_id;
+        match transfer.state {
+            TransferState::Transferring | TransferState::Interrupted => {}
+            // The answer was lost, the sender is still sending the chunks
+            TransferState::Completed => {
+                self.send_file_message(server_id, from, FileMessage::Complete { transfer_id });
+                return;
+            }
+            _ => return,
+        }
+        let new = transfer.on_chunk(index, data, now);
+        let progress = transfer.progress();
+        self.send_file_message(server_id, from, FileMessage::Ack { transfer_id, index });
+        if new {
+            self.send_chat_event(ChatEvent::TransferProgress(progress));
+            self.complete_incoming_transfer(transfer_id);
+        }
+    }
+
+    /// If all the chunks of a file arrived, check the file against the offer, and tell the sender.
+    /// The file is sent to the chat controller if it matches
+    fn complete_incoming_transfer(&mut self, transfer_id: u64) {
+        let Some(transfer) = self
+            .chat_state_mut()
+            .transfers
+            .incoming
+            .get_mut(&transfer_id)
+        else {
+            return;
+        };
+        if transfer.state != TransferState::Transferring || !transfer.is_complete() {
+            return;
+        }
+        let result = transfer.assemble();
+        transfer.state = match &result {
+            Ok(_) => TransferState::Completed,
+            Err(reason) => TransferState::Failed(reason.clone()),
+        };
+        let (server_id, from, progress) =
+            (transfer.server_id, transfer.peer_id, transfer.progress());
+        let (name, mime_type) = (transfer.name.clone(), transfer.mime_type.clone());
+        self.chat_state_mut().transfers.on_finished(transfer_id);
+        match result {
+            Ok(data) => {
+                self.logger().log(
+                    &format!("Received file {transfer_id} from {from}"),
+                    LogLevel::DEBUG,
+                );
+                self.send_file_message(server_id, from, FileMessage::Complete { transfer_id });
+                self.send_chat_event(ChatEvent::TransferProgress(progress));
+                self.send_chat_event(ChatEvent::FileReceived {
+                    transfer_id,
+                    from,
+                    name,
+                    mime_type,
+                    data,
+                });
+            }
+            Err(reason) => {
+                self.logger().log(
+                    &format!("File {transfer_id} from {from} is corrupted: {reason}"),
+                    LogLevel::ERROR,
+                );
+                self.send_file_message(
+                    server_id,
+                    from,
+                    FileMessage::Failed {
+                        transfer_id,
+                        reason,
+                    },
+                );
+                self.send_chat_event(ChatEvent::TransferProgress(progress));
+            }
+        }
+    }
+
+    /// The recipient of a file answered how the transfer ended
+    fn finish_outgoing_transfer(&mut self, from: NodeId, transfer_id: u64, state: TransferState) {
+        let Some(transfer) = self
+            .chat_state_mut()
+            .transfers
+            .outgoing
+            .get_mut(&transfer_id)
+            .filter(|transfer| transfer.peer_id == from)
+        else {
+            return;
+        };
+        if transfer.state == state {
+            return;
+        }
+        transfer.state = state;
+        let progress = transfer.progress();
+        self.chat_state_mut().transfers.on_finished(transfer_id);
+        self.send_chat_event(ChatEvent::TransferProgress(progress));
+    }
+
+    /// Send again the messages of the file transfers that got no answer in time,
+    /// and report the ones interrupted
+    fn check_transfer_timeouts(&mut self, now: u128) {
+        let (outgoing, incoming) = self.chat_state().transfers.timed_out(now);
+        for transfer_id in outgoing {
+            let Some(transfer) = self
+                .chat_state_mut()
+                .transfers
+                .outgoing
+                .get_mut(&transfer_id)
+            else {
+                continue;
+            };
+            let messages = transfer.on_timeout(now);
+            let (server_id, peer_id, progress) =
+                (transfer.server_id, transfer.peer_id, transfer.progress());
+            for message in messages {
+                self.send_file_message(server_id, peer_id, message);
+            }
+            if progress.state == TransferState::Interrupted {
+                self.logger().log(
+                    &format!("Transfer {transfer_id} to {peer_id} was interrupted"),
+                    LogLevel::ERROR,
+                );
+                self.send_chat_event(ChatEvent::TransferProgress(progress));
+            }
+        }
+        for transfer_id in incoming {
+            let Some(transfer) = self
+                .chat_state_mut()
+                .transfers
+                .incoming
+                .get_mut(&transfer_id)
+            else {
+                continue;
+            };
+            let message = transfer.on_timeout(now);
+            let (server_id, peer_id, progress) =
+                (transfer.server_id, transfer.peer_id, transfer.progress());
+            if let Some(message) = message {
+                self.send_file_message(server_id, peer_id, message);
+            }
+            if progress.state == TransferState::Interrupted {
+                self.logger().log(
+                    &format!("Transfer {transfer_id} from {peer_id} was interrupted"),
+                    LogLevel::ERROR,
+                );
+                self.send_chat_event(ChatEvent::TransferProgress(progress));
+            }
+        }
+    }
+
+    /// Hold a message to a client that isn't in the client list of the server,
+    /// and ask for the list again in case it's outdated
+    fn queue_chat_message(&mut self, id: u64, server_id: NodeId, to: NodeId, message: String) {
+        self.logger().log(
+            &format!("{to} isn't registered to {server_id}, queueing message {id}"),
+            LogLevel::DEBUG,
+        );
+        let state = self.chat_state_mut();
+        state.outbox.push(QueuedMessage {
+            id,
+            server_id,
+            peer_id: to,
+            text: message,
+            queued_at: current_timestamp_ms(),
+        });
+        state.receipts.on_message_queued(id, server_id, to);
+        self.send_chat_event(ChatEvent::MessageStatus {
+            id,
+            server_id,
+            peer_id: to,
+            status: DeliveryStatus::Queued,
+        });
+        self.send_client_list_req(server_id);
+    }
 
-            available_clients: HashMap::new(),
-            registered_servers: vec![],
+    /// Send the queued messages to the clients in the new client list of a server
+    fn flush_outbox(&mut self, server_id: NodeId) {
+        let state = self.chat_state_mut();
+        let clients = state
+            .available_clients
+            .get(&server_id)
+            .cloned()
+            .unwrap_or_default();
+        let deliverable = state.outbox.take_deliverable(server_id, &clients);
+        for message in deliverable {
+            self.logger().log(
+                &format!(
+                    "{} registered to {server_id}, sending queued message {}",
+                    message.peer_id, message.id
+                ),
+                LogLevel::DEBUG,
+            );
+            let content = MessageContent::Text(message.text);
+            self.transmit_chat_message(message.id, server_id, message.peer_id, content);
         }
     }
 
-    /// Get the list of available clients in the chat server
-    pub fn get_client_list(&mut self) -> &mut HashMap<NodeId, Vec<NodeId>> {
-        &mut self.available_clients
+    /// Send a payload to another client through a server, returns the request sent
+    fn send_chat_payload(
+        &mut self,
+        server_id: NodeId,
+        to: NodeId,
+        payload: &ChatPayload,
+    ) -> String {
+        let chat_message = ChatRequestWrapper::Chat(ChatRequest::SendMessage {
+            from: self.client_id(),
+            to,
+            message: payload.encode(),
+        });
+        let chat_message_json = serde_json::to_string(&chat_message).unwrap_or_default();
+        self.send_message(server_id, chat_message_json.clone());
+        chat_message_json
     }
 
-    /// Send a 'register' message to a server
-    pub fn register(&mut self, server_id: NodeId) {
-        self.logger.log(
-            &format!(
-                "Client {} registering to server {server_id}",
-                self.client_id
-            ),
+    /// Tell the sender of a message that it was received
+    fn send_receipt(&mut self, server_id: NodeId, to: NodeId, id: u64) {
+        self.logger().log(
+            &format!("Sending receipt of message {id} to {to} using {server_id}"),
             LogLevel::DEBUG,
         );
-        let request = ChatRequestWrapper::Chat(ChatRequest::Register(self.client_id));
-        let request_json = serde_json::to_string(&request).unwrap_or_default();
-        self.send_message(server_id, request_json);
+        self.send_chat_payload(server_id, to, &ChatPayload::Receipt { id });
+        self.chat_state_mut().receipts.on_untracked_sent(server_id);
     }
 
-    /// Send a chat message to another client
-    pub fn send_chat_message(&mut self, server_id: NodeId, to: NodeId, message: String) {
-        self.logger.log(
-            &format!("Sending message to {to} using {server_id}"),
+    /// Get the delivery status of a sent message
+    fn get_message_status(&self, id: u64) -> Option<DeliveryStatus> {
+        self.chat_state()
+            .receipts
+            .get(id)
+            .map(|message| message.status)
+    }
+
+    /// Report the new status of a sent message to the chat controller
+    fn notify_message_status(&mut self, message: &TrackedMessage) {
+        self.logger().log(
+            &format!("Message {} is now {:?}", message.id, message.status),
             LogLevel::DEBUG,
         );
-        let chat_message = ChatRequestWrapper::Chat(ChatRequest::SendMessage {
-            from: self.client_id,
-            to,
-            message,
+        // It won't be sent again
+        if message.status.is_final() {
+            self.chat_state_mut().sequencer.forget(message.id);
+        }
+        self.send_chat_event(ChatEvent::MessageStatus {
+            id: message.id,
+            server_id: message.server_id,
+            peer_id: message.peer_id,
+            status: message.status,
         });
-        let chat_message_json = serde_json::to_string(&chat_message).unwrap_or_default();
+    }
 
-        self.send_message(server_id, chat_message_json.clone());
+    /// A message from another client: accept it, unless it expired or its sender is blocked or rate limited.
+    /// Returns whether it was accepted, only then the sender gets a receipt
+    fn on_chat_content_received(
+        &mut self,
+        server_id: NodeId,
+        from: NodeId,
+        id: Option<u64>,
+        content: MessageContent,
+        expires_at: Option<u128>,
+    ) -> bool {
+        let now = current_timestamp_ms();
+        if is_expired(expires_at, now) {
+            self.logger().log(
+                &format!("Dropping expired message {id:?} from {from}"),
+                LogLevel::DEBUG,
+            );
+            return false;
+        }
+        let verdict = self.chat_state_mut().filter.check(from, now);
+        if matches!(verdict, Verdict::Blocked | Verdict::RateLimited) {
+            self.logger().log(
+                &format!("Dropping message from {from}: {verdict:?}"),
+                LogLevel::DEBUG,
+            );
+            return false;
+        }
+        self.accept_chat_content(
+            server_id,
+            from,
+            id,
+            content,
+            expires_at,
+            verdict == Verdict::Mute,
+        );
+        true
+    }
 
-        // Notify the controller that the message was sent
+    /// An accepted message: save it, and send it to the controller and to the chat controller.
+    /// The simulation controller and the history get its summary.
+    /// The messages of muted clients are only saved.
+    /// An ephemeral message is removed from the history at `expires_at`.
+    /// The behaviors run last, so their answers follow the notifications
+    fn accept_chat_content(
+        &mut self,
+        server_id: NodeId,
+        from: NodeId,
+        id: Option<u64>,
+        content: MessageContent,
+        expires_at: Option<u128>,
+        muted: bool,
+    ) {
+        let summary = content.summary();
+        let ephemeral = id.zip(expires_at);
+        let entry = self.record_entry(HistoryEntry {
+            server_id,
+            peer_id: from,
+            direction: Direction::Received,
+            timestamp: current_timestamp_ms(),
+            text: summary.clone(),
+            id: ephemeral.map(|(id, _)| id),
+            expires_at: ephemeral.map(|(_, expires_at)| expires_at),
+        });
+        let state = self.chat_state_mut();
+        state.unread.on_received(server_id, from);
+        if let Some((id, expires_at)) = ephemeral {
+            state.ephemerals.track(EphemeralMessage {
+                id,
+                entry,
+                expires_at,
+            });
+        }
+        if muted {
+            return;
+        }
+        // Its message shows the peer stopped typing
+        if self.chat_state_mut().activity.on_message(server_id, from) {
+            self.send_chat_event(ChatEvent::PeerActivity {
+                server_id,
+                peer_id: from,
+                activity: Activity::Idle,
+            });
+        }
         let _res = self
-            .sim_controller_sender
-            .send(SimControllerResponseWrapper::Event(
-                SimControllerEvent::ChatMessageSent(server_id, to, chat_message_json),
+            .sim_controller_sender()
+            .send(SimControllerResponseWrapper::Message(
+                SimControllerMessage::MessageReceived(server_id, from, summary),
             ));
+        self.send_chat_event(ChatEvent::MessageReceived {
+            server_id,
+            from,
+            id,
+            content: content.clone(),
+        });
+        self.run_behaviors(|behavior, context| {
+            behavior.on_message(context, server_id, from, &content)
+        });
+    }
+
+    /// A message with a sequence number arrived: unless its sender is blocked or rate limited,
+    /// send the receipt right away, and deliver it once the messages before it are delivered.
+    /// A message received again doesn't count against the rate limit
+    fn on_sequenced_received(
+        &mut self,
+        server_id: NodeId,
+        from: NodeId,
+        session: u64,
+        seq: u64,
+        since: u64,
+        payload: ChatPayload,
+    ) {
+        let (payload, expires_at) = payload.into_expiring();
+        let (id, content) = match payload.into_message() {
+            Ok(message) => message,
+            Err(payload) => {
+                self.logger().log(
+                    &format!("Ignoring sequenced payload from {from}, not a message: {payload:?}"),
+                    LogLevel::ERROR,
+                );
+                return;
+            }
+        };
+        let now = current_timestamp_ms();
+        // An expired message still takes its place in the order, so it doesn't leave a gap
+        if is_expired(expires_at, now) {
+            self.logger().log(
+                &format!("Message {id} from {from} arrived expired"),
+                LogLevel::DEBUG,
+            );
+        } else {
+            let state = self.chat_state_mut();
+            // The reorder buffer drops a duplicate, only its receipt is sent again
+            let verdict = if state.reorder.is_duplicate(from, session, seq, since) {
+                if state.filter.is_blocked(from) {
+                    Verdict::Blocked
+                } else {
+                    Verdict::Deliver
+                }
+            } else {
+                state.filter.check(from, now)
+            };
+            if matches!(verdict, Verdict::Blocked | Verdict::RateLimited) {
+                self.logger().log(
+                    &format!("Dropping message {id} from {from}: {verdict:?}"),
+                    LogLevel::DEBUG,
+                );
+                return;
+            }
+            self.send_receipt(server_id, from, id);
+        }
+        let ordered = self.chat_state_mut().reorder.receive(
+            from,
+            session,
+            seq,
+            since,
+            now,
+            (server_id, id, content, expires_at),
+        );
+        self.on_messages_ordered(ordered);
+    }
+
+    /// Deliver the messages put back in order, unless they expired, and report the gaps.
+    /// Their senders were checked when they arrived
+    fn on_messages_ordered(
+        &mut self,
+        ordered: Vec<Ordered<(NodeId, u64, MessageContent, Option<u128>)>>,
+    ) {
+        for result in ordered {
+            match result {
+                Ordered::Deliver {
+                    from,
+                    message: (server_id, id, content, expires_at),
+                } => {
+                    if is_expired(expires_at, current_timestamp_ms()) {
+                        self.logger().log(
+                            &format!("Dropping expired message {id} from {from}"),
+                            LogLevel::DEBUG,
+                        );
+                    } else {
+                        let muted = self.chat_state().filter.is_muted(from);
+                        self.accept_chat_content(
+                            server_id,
+                            from,
+                            Some(id),
+                            content,
+                            expires_at,
+                            muted,
+                        );
+                    }
+                }
+                Ordered::Gap { from, first, last } => {
+                    self.logger().log(
+                        &format!("Messages {first} to {last} from {from} are missing"),
+                        LogLevel::ERROR,
+                    );
+                    self.send_chat_event(ChatEvent::MessagesMissing {
+                        peer_id: from,
+                        first,
+                        last,
+                    });
+                }
+                Ordered::Duplicate {
+                    from,
+                    message: (_, id, _, _),
+                } => {
+                    self.logger().log(
+                        &format!("Dropping duplicate message {id} from {from}"),
+                        LogLevel::DEBUG,
+                    );
+                }
+            }
+        }
     }
 
     /// Send a `ClientList` request to a server, asking for the clients registered to it
-    pub fn send_client_list_req(&mut self, server_id: NodeId) {
-        self.logger.log(
+    fn send_client_list_req(&mut self, server_id: NodeId) {
+        self.logger().log(
             &format!("Sending client list request to {server_id}"),
             LogLevel::DEBUG,
         );
@@ -128,69 +2390,349 @@ impl ChatClient {
         let request = ChatRequestWrapper::Chat(ChatRequest::ClientList);
         let request_json = serde_json::to_string(&request).unwrap_or_default();
         self.send_message(server_id, request_json);
+        let now = current_timestamp_ms();
+        let state = self.chat_state_mut();
+        state.monitor.on_request_sent(server_id, now);
+        state.list_refresher.on_requested(server_id, now);
+    }
+
+    /// When a `ServerTypeResponse` says that a server is a chat server
+    /// Behavior: add it to the available servers (as a key of `available_clients`)
+    fn on_chat_server_discovered(&mut self, server_id: NodeId) {
+        let state = self.chat_state_mut();
+        state.available_clients.insert(server_id, vec![]);
+        if state.monitor.mark_up(server_id) {
+            self.send_chat_event(ChatEvent::ServerUp(server_id));
+        }
+        self.apply_auto_join();
     }
 
     /// Handle a chat response from a server
     fn handle_chat_response(&mut self, response: ChatResponse, server_id: NodeId) {
+        if self.chat_state_mut().monitor.on_response(server_id) {
+            self.logger().log(
+                &format!("Server {server_id} is answering again"),
+                LogLevel::DEBUG,
+            );
+            self.send_chat_event(ChatEvent::ServerUp(server_id));
+        }
         match response {
             // If the response is a client list, add them to the available_clients for that server
             ChatResponse::ClientList(client_list) => {
-                self.logger.log(
+                self.logger().log(
                     &format!("Received client list: {client_list:?} from {server_id}"),
                     LogLevel::DEBUG,
                 );
-                self.available_clients
-                    .insert(server_id, client_list.clone());
+                let state = self.chat_state_mut();
+                state
+                    .client_lists_updated
+                    .insert(server_id, current_timestamp_ms());
+                let previous = state
+                    .available_clients
+                    .insert(server_id, client_list.clone())
+                    .unwrap_or_default();
+                for (client_id, change) in diff_client_lists(&previous, &client_list) {
+                    self.send_chat_event(ChatEvent::PresenceChanged {
+                        server_id,
+                        client_id,
+                        change,
+                    });
+                }
+                self.flush_outbox(server_id);
+                for id in self.chat_state().routed.unresolved() {
+                    self.route_message(id);
+                }
+                for id in self.chat_state().broadcasts.waiting(server_id) {
+                    self.send_broadcast(id);
+                }
                 // Send info to the controller
                 let response = SimControllerMessage::ClientListResponse(server_id, client_list);
                 let _res = self
-                    .sim_controller_sender
+                    .sim_controller_sender()
                     .send(SimControllerResponseWrapper::Message(response));
             }
             // If the response is a message, print it, and send to the controller
             ChatResponse::MessageFrom { from, message } => {
-                let s = match str::from_utf8(message.as_ref()) {
+                // Nothing from a blocked client is handled, not even its receipts
+                if self.chat_state().filter.is_blocked(from) {
+                    self.logger().log(
+                        &format!("Dropping message from blocked client {from}"),
+                        LogLevel::DEBUG,
+                    );
+                    return;
+                }
+                let s = match String::from_utf8(message) {
                     Ok(v) => v,
+                    // Not a payload, pass the data as it is
                     Err(e) => {
-                        self.logger()
-                            .log(&format!("Invalid UTF-8 sequence: {e}"), LogLevel::ERROR);
-                        "Invalid UTF-8 sequence"
+                        self.logger().log(
+                            &format!("Received binary message from {from}"),
+                            LogLevel::DEBUG,
+                        );
+                        let content = MessageContent::Binary {
+                            mime_type: "application/octet-stream".to_string(),
+                            data: e.into_bytes(),
+                        };
+                        self.on_chat_content_received(server_id, from, None, content, None);
+                        return;
                     }
                 };
-                self.logger.log(
+                self.logger().log(
                     &format!("Received message from {from}: {s}"),
                     LogLevel::DEBUG,
                 );
-                // Send the message to the controller
-                let _res = self
-                    .sim_controller_sender
-                    .send(SimControllerResponseWrapper::Message(
-                        SimControllerMessage::MessageReceived(server_id, from, s.to_string()),
-                    ));
+                match ChatPayload::decode(&s) {
+                    // Send the message to the controller, and the receipt to the sender
+                    Some(ChatPayload::Text { id, text }) => {
+                        let content = MessageContent::Text(text);
+                        if self.on_chat_content_received(server_id, from, Some(id), content, None) {
+                            self.send_receipt(server_id, from, id);
+                        }
+                    }
+                    Some(ChatPayload::Content { id, content }) => {
+                        if self.on_chat_content_received(server_id, from, Some(id), content, None) {
+                            self.send_receipt(server_id, from, id);
+                        }
+                    }
+                    Some(payload @ ChatPayload::Ephemeral { .. }) => {
+                        let (payload, expires_at) = payload.into_expiring();
+                        match payload.into_message() {
+                            Ok((id, content)) => {
+                                if self.on_chat_content_received(
+                                    server_id,
+                                    from,
+                                    Some(id),
+                                    content,
+                                    expires_at,
+                                ) {
+                                    self.send_receipt(server_id, from, id);
+                                }
+                            }
+                            Err(payload) => self.logger().log(
+                                &format!("Ignoring ephemeral payload from {from}: {payload:?}"),
+                                LogLevel::ERROR,
+                            ),
+                        }
+                    }
+                    Some(ChatPayload::Sequenced {
+                        session,
+                        seq,
+                        since,
+                        payload,
+                    }) => {
+                        self.on_sequenced_received(server_id, from, session, seq, since, *payload);
+                    }
+                    Some(ChatPayload::Receipt { id }) => {
+                        let delivered = self.chat_state_mut().receipts.on_delivered(from, id);
+                        if let Some(message) = delivered {
+                            let state = self.chat_state_mut();
+                            state.routed.remove(id);
+                            state.undelivered.remove(&id);
+                            self.notify_message_status(&message);
+                        }
+                    }
+                    Some(
+                        payload @ (ChatPayload::GroupText { .. } | ChatPayload::GroupInfo { .. }),
+                    ) => self.on_group_payload_received(from, payload),
+                    Some(ChatPayload::File(message)) => {
+                        self.on_file_message_received(server_id, from, message);
+                    }
+                    Some(ChatPayload::Activity(activity)) => {
+                        self.on_activity_received(server_id, from, activity);
+                    }
+                    // Plain text, from a client without receipts
+                    None => {
+                        let content = MessageContent::Text(s);
+                        self.on_chat_content_received(server_id, from, None, content, None);
+                    }
+                }
             }
-            // The message was sent correctly
+            // The server accepted the oldest message sent through it
             ChatResponse::MessageSent => {
-                self.logger
+                self.logger()
                     .log(&format!("Message sent from {server_id}"), LogLevel::DEBUG);
+                let accepted = self.chat_state_mut().receipts.on_accepted(server_id);
+                if let Some(message) = accepted {
+                    self.notify_message_status(&message);
+                }
             }
             // The client was registered correctly
             ChatResponse::ClientRegistered => {
-                self.logger
+                self.logger()
                     .log(&format!("Registered to {server_id}"), LogLevel::DEBUG);
-                // Add the server to the list of registered servers
-                self.registered_servers.push(server_id);
+                // Add the server to the list of registered servers, only once
+                let state = self.chat_state_mut();
+                if state.registrations.on_registered(server_id) {
+                    if !state.registered_servers.contains(&server_id) {
+                        state.registered_servers.push(server_id);
+                    }
+                    self.notify_registration(server_id);
+                    // Know who can be reached through the servers joined automatically,
+                    // or through the server that replaced one that went down
+                    let state = self.chat_state();
+                    let waiting = state
+                        .outbox
+                        .messages()
+                        .iter()
+                        .any(|message| message.server_id == server_id)
+                        || !state.routed.unresolved().is_empty();
+                    if state.auto_join.is_enabled() || waiting {
+                        self.send_client_list_req(server_id);
+                    }
+                }
             }
         };
     }
 
-    /// Get the servers the client is registered to
-    pub fn get_registered_servers(&mut self) -> &mut Vec<NodeId> {
-        &mut self.registered_servers
+    /// Handle the commands sent by the controller to the chat layer.
+    /// Returns the command back if it's not a chat command
+    fn handle_chat_command(
+        &mut self,
+        command: SimControllerCommand,
+    ) -> Option<SimControllerCommand> {
+        match command {
+            // Send a message to a client
+            SimControllerCommand::SendMessage(message, server_id, to) => {
+                self.logger().log(
+                    &format!("COMMAND: Sending message to {to} using {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.send_chat_message(server_id, to, message);
+            }
+            // Register to a server
+            SimControllerCommand::Register(server_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Registering to server {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.register(server_id);
+            }
+            // Get the list of clients registered to a server
+            SimControllerCommand::ClientList(server_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Getting client list from server {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.send_client_list_req(server_id);
+            }
+            // Get the list of servers the client is registered to
+            SimControllerCommand::RegisteredServers => {
+                self.logger()
+                    .log("COMMAND: Getting registered servers", LogLevel::DEBUG);
+                let registered_servers = self.chat_state().registered_servers.clone();
+                let response = SimControllerMessage::RegisteredServersResponse(registered_servers);
+                let _res = self
+                    .sim_controller_sender()
+                    .send(SimControllerResponseWrapper::Message(response));
+                // The states don't fit in the response, they go to the chat controller
+                let states = self.chat_state().registrations.states();
+                self.send_chat_event(ChatEvent::Registrations(states));
+            }
+            command => return Some(command),
+        }
+        None
     }
+}
 
-    /// Get the available servers
-    pub fn get_available_clients(&mut self) -> &mut HashMap<NodeId, Vec<NodeId>> {
-        &mut self.available_clients
+pub struct ChatClient {
+    // General data for Client
+    client_id: u8,
+    transport: Box<dyn Transport>,
+    topology: Topology,
+    sim_controller_receiver: Receiver<SimControllerCommand>,
+    sim_controller_sender: Sender<SimControllerResponseWrapper>,
+    sent_packets: HashMap<u64, Vec<Packet>>,
+    acked_packets: HashMap<u64, Vec<bool>>,
+    assembler: Assembler,
+    disassembler: Disassembler,
+    running: bool,
+    packets_to_send: HashMap<u8, Packet>,
+    sent_flood_ids: Vec<u64>,
+    last_flood_timestamp: u128,
+    logger: Logger,
+
+    // Chat-specific data
+    chat_state: ChatState,
+}
+
+impl ChatClient {
+    #[must_use]
+    pub fn new(
+        client_id: u8,
+        senders: HashMap<u8, Sender<Packet>>,
+        receiver: Receiver<Packet>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+    ) -> Self {
+        Self::with_transport(
+            client_id,
+            Box::new(CrossbeamTransport::new(senders, receiver)),
+            sim_controller_receiver,
+            sim_controller_sender,
+            debug,
+        )
+    }
+
+    /// Create a client that exchanges packets through a custom transport (e.g. UDP)
+    #[must_use]
+    pub fn with_transport(
+        client_id: u8,
+        transport: Box<dyn Transport>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+    ) -> Self {
+        Self::with_config(
+            client_id,
+            transport,
+            sim_controller_receiver,
+            sim_controller_sender,
+            debug,
+            &ChatConfig::default(),
+        )
+    }
+
+    /// Create a client with custom settings for the chat layer
+    #[must_use]
+    pub fn with_config(
+        client_id: u8,
+        transport: Box<dyn Transport>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+        config: &ChatConfig,
+    ) -> Self {
+        let logger = Logger::new("ChatClient".to_string(), client_id, debug);
+        let chat_state = ChatState::from_config(config, &logger);
+        ChatClient {
+            client_id,
+            transport,
+            topology: Topology::new(),
+            sim_controller_receiver,
+            sim_controller_sender,
+            sent_packets: HashMap::new(),
+            acked_packets: HashMap::new(),
+            assembler: Assembler::new(),
+            disassembler: Disassembler::new(),
+            running: false,
+            packets_to_send: HashMap::new(),
+            sent_flood_ids: Vec::new(),
+            last_flood_timestamp: 0,
+            logger,
+
+            chat_state,
+        }
+    }
+}
+
+impl ChatLayer for ChatClient {
+    fn chat_state(&self) -> &ChatState {
+        &self.chat_state
+    }
+
+    fn chat_state_mut(&mut self) -> &mut ChatState {
+        &mut self.chat_state
     }
 }
 
@@ -203,18 +2745,22 @@ impl Client for ChatClient {
         self.client_id
     }
 
-    fn senders(&self) -> &HashMap<u8, Sender<Packet>> {
-        &self.senders
+    fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
     }
 
-    fn receiver(&self) -> &Receiver<Packet> {
-        &self.receiver
+    fn transport_mut(&mut self) -> &mut dyn Transport {
+        self.transport.as_mut()
     }
 
     fn topology(&mut self) -> &mut Topology {
         &mut self.topology
     }
 
+    fn on_tick(&mut self) {
+        self.on_chat_tick();
+    }
+
     fn handle_response(&mut self, response: Self::ResponseType, server_id: NodeId) {
         match response {
             ChatResponseWrapper::Chat(response) => self.handle_chat_response(response, server_id),
@@ -228,7 +2774,7 @@ impl Client for ChatClient {
                     .set_node_type(server_id, format!("{server_response:?}"));
                 // If it's a chat server, add it to the available servers (as a key of available_clients)
                 if let ServerType::Chat = server_response {
-                    self.available_clients.insert(server_id, vec![]);
+                    self.on_chat_server_discovered(server_id);
                 } else {
                     self.logger.log(
                         &format!(
@@ -269,115 +2815,33 @@ impl Client for ChatClient {
 
     /// Handle the commands sent by the controller
     fn handle_controller_commands(&mut self, command: SimControllerCommand) {
-        match command {
-            // Send a message to a client
-            SimControllerCommand::SendMessage(message, server_id, to) => {
-                self.logger.log(
-                    &format!("COMMAND: Sending message to {to} using {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.send_chat_message(server_id, to, message);
-            }
-            // Register to a server
-            SimControllerCommand::Register(server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Registering to server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.register(server_id);
-            }
-            // Get the list of clients registered to a server
-            SimControllerCommand::ClientList(server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Getting client list from server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.send_client_list_req(server_id);
-            }
-            // Send a flood request
-            SimControllerCommand::FloodRequest => {
-                self.logger
-                    .log("COMMAND: Sending flood request", LogLevel::DEBUG);
-                self.send_flood_request();
-            }
-            // Get the topology as seen by the client
-            SimControllerCommand::Topology => {
-                self.logger
-                    .log("COMMAND: Sending topology", LogLevel::DEBUG);
-                let topology = self.topology.clone();
-                let response = SimControllerMessage::TopologyResponse(topology);
-                let _res = self
-                    .sim_controller_sender
-                    .send(SimControllerResponseWrapper::Message(response));
-            }
-            // Get the list of servers the client is registered to
-            SimControllerCommand::RegisteredServers => {
-                self.logger
-                    .log("COMMAND: Getting registered servers", LogLevel::DEBUG);
-                let response = SimControllerMessage::RegisteredServersResponse(
-                    self.registered_servers.clone(),
-                );
-                let _res = self
-                    .sim_controller_sender
-                    .send(SimControllerResponseWrapper::Message(response));
+        let Some(command) = self.handle_chat_command(command) else {
+            return;
+        };
+        let Some(command) = self.handle_network_command(command) else {
+            return;
+        };
+        // Get the list of known servers
+        if let SimControllerCommand::KnownServers = command {
+            self.logger
+                .log("COMMAND: Getting known servers", LogLevel::DEBUG);
+            // All the registered servers are of type chat
+            let mut map = HashMap::new();
+            for server_id in self.chat_state.available_clients.keys() {
+                map.insert(*server_id, ServerType::Chat);
             }
-            // Get the list of known servers
-            SimControllerCommand::KnownServers => {
-                self.logger
-                    .log("COMMAND: Getting known servers", LogLevel::DEBUG);
-                // All the registered servers are of type chat
-                let mut map = HashMap::new();
-                for server_id in self.available_clients.keys() {
-                    map.insert(*server_id, ServerType::Chat);
-                }
-                // Check the server types, if any are unknown (Server), request the type
-                let node_types = self.topology.get_node_types().clone();
-                for (server_id, server_type) in node_types {
-                    if server_type == "server" {
-                        self.send_server_type_request(server_id);
-                    }
+            // Check the server types, if any are unknown (Server), request the type
+            let node_types = self.topology.get_node_types().clone();
+            for (server_id, server_type) in node_types {
+                if server_type == "server" {
+                    self.send_server_type_request(server_id);
                 }
-                // Then, send the response
-                let response = SimControllerMessage::KnownServers(map);
-                let _res = self
-                    .sim_controller_sender
-                    .send(SimControllerResponseWrapper::Message(response));
-            }
-            // Add a neighbor
-            SimControllerCommand::AddSender(sender_id, sender_channel) => {
-                self.logger.log(
-                    &format!("COMMAND: Adding sender {sender_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.senders.insert(sender_id, sender_channel);
-                self.topology.add_node(sender_id);
-                self.topology.set_node_type(sender_id, "drone".to_string());
-                self.topology.add_edge(self.client_id, sender_id);
-                // Send a flood request to the new neighbor
-                self.send_flood_request();
-            }
-            // Remove a neighbor
-            SimControllerCommand::RemoveSender(sender_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Removing sender {sender_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.topology.remove_edges(self.client_id, sender_id);
-                self.senders.remove(&sender_id);
-            }
-            SimControllerCommand::RequestServerType(server_id) => {
-                self.logger.log(
-                    &format!("COMMAND: Requesting server type from server {server_id}"),
-                    LogLevel::DEBUG,
-                );
-                self.send_server_type_request(server_id);
             }
-            // The simulation controller wants the client to shut down
-            SimControllerCommand::Shutdown => {
-                self.logger.log("COMMAND: Shutting down", LogLevel::DEBUG);
-                process::exit(0);
-            }
-            _ => {}
+            // Then, send the response
+            let response = SimControllerMessage::KnownServers(map);
+            let _res = self
+                .sim_controller_sender
+                .send(SimControllerResponseWrapper::Message(response));
         }
     }
 
@@ -1,4 +1,6 @@
 use std::collections::{HashMap, HashSet};
+use std::process;
+use std::time::Duration;
 
 use rustafarian_shared::logger::{LogLevel, Logger};
 use rustafarian_shared::messages::commander_messages::{
@@ -6,6 +8,7 @@ use rustafarian_shared::messages::commander_messages::{
 };
 use rustafarian_shared::topology::Topology;
 
+use crate::transport::Transport;
 use crossbeam_channel::{select_biased, Receiver, Sender};
 use rustafarian_shared::assembler::{assembler::Assembler, disassembler::Disassembler};
 use rustafarian_shared::messages::general_messages::{DroneSend, Message, Request, Response};
@@ -15,6 +18,17 @@ use wg_2024::packet::{FloodRequest, FloodResponse, Packet, PacketType};
 
 pub const FRAGMENT_DSIZE: usize = 128;
 pub static mut DEBUG: bool = false;
+/// How long `run` waits for a packet or a command before calling `on_tick` anyway
+pub const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The current time, as milliseconds since the UNIX epoch
+#[must_use]
+pub fn current_timestamp_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0))
+        .as_millis()
+}
 
 /// A trait for a client that can send and receive messages
 pub trait Client: Send {
@@ -23,10 +37,10 @@ pub trait Client: Send {
 
     /// Returns the client id
     fn client_id(&self) -> u8;
-    /// Returns the drones connected to the client
-    fn senders(&self) -> &HashMap<u8, Sender<Packet>>;
-    /// The channel where the client can receive messages
-    fn receiver(&self) -> &Receiver<Packet>;
+    /// The transport used to exchange packets with the drones connected to the client
+    fn transport(&self) -> &dyn Transport;
+    /// The transport, mutable, used to add or remove neighbors
+    fn transport_mut(&mut self) -> &mut dyn Transport;
     /// The assembler used to reassemble messages
     fn assembler(&mut self) -> &mut Assembler;
     /// The deassembler used to fragment messages
@@ -344,10 +358,9 @@ pub trait Client: Send {
         request.increment(self.client_id(), NodeType::Client);
 
         // If I only have one neighbor, transform into flood response
-        if self.senders().len() == 1 {
+        if self.transport().neighbors().len() == 1 {
             let response = request.generate_response(packet.session_id);
-            let sender = self.senders().get(&sender_id).unwrap(); // Safe unwrap: checked above
-            let _res = sender.send(response);
+            let _res = self.transport().send(sender_id, response);
             return;
         }
 
@@ -359,9 +372,9 @@ pub trait Client: Send {
         );
 
         // Send the flood request to all neighbors, aside from the sender
-        for (neighbor_id, sender) in self.senders() {
-            if neighbor_id != &sender_id {
-                let _res = sender.send(response.clone());
+        for neighbor_id in self.transport().neighbors() {
+            if neighbor_id != sender_id {
+                let _res = self.transport().send(neighbor_id, response.clone());
             }
         }
     }
@@ -409,6 +422,75 @@ pub trait Client: Send {
         }
     }
 
+    /// Handle the commands about the network, shared by all the clients.
+    /// Returns the command back if it's not about the network
+    fn handle_network_command(
+        &mut self,
+        command: SimControllerCommand,
+    ) -> Option<SimControllerCommand> {
+        match command {
+            // Send a flood request
+            SimControllerCommand::FloodRequest => {
+                self.logger()
+                    .log("COMMAND: Sending flood request", LogLevel::DEBUG);
+                self.send_flood_request();
+            }
+            // Get the topology as seen by the client
+            SimControllerCommand::Topology => {
+                self.logger()
+                    .log("COMMAND: Sending topology", LogLevel::DEBUG);
+                let topology = self.topology().clone();
+                let response = SimControllerMessage::TopologyResponse(topology);
+                let _res = self
+                    .sim_controller_sender()
+                    .send(SimControllerResponseWrapper::Message(response));
+            }
+            // Add a neighbor
+            SimControllerCommand::AddSender(sender_id, sender_channel) => {
+                self.logger().log(
+                    &format!("COMMAND: Adding sender {sender_id}"),
+                    LogLevel::DEBUG,
+                );
+                let client_id = self.client_id();
+                self.transport_mut().add_neighbor(sender_id, sender_channel);
+                self.topology().add_node(sender_id);
+                self.topology()
+                    .set_node_type(sender_id, "drone".to_string());
+                self.topology().add_edge(client_id, sender_id);
+                // Send a flood request to the new neighbor
+                self.send_flood_request();
+            }
+            // Remove a neighbor
+            SimControllerCommand::RemoveSender(sender_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Removing sender {sender_id}"),
+                    LogLevel::DEBUG,
+                );
+                let client_id = self.client_id();
+                self.topology().remove_edges(client_id, sender_id);
+                self.transport_mut().remove_neighbor(sender_id);
+            }
+            SimControllerCommand::RequestServerType(server_id) => {
+                self.logger().log(
+                    &format!("COMMAND: Requesting server type from server {server_id}"),
+                    LogLevel::DEBUG,
+                );
+                self.send_server_type_request(server_id);
+            }
+            // The simulation controller wants the client to shut down
+            SimControllerCommand::Shutdown => {
+                self.logger().log("COMMAND: Shutting down", LogLevel::DEBUG);
+                process::exit(0);
+            }
+            command => return Some(command),
+        }
+        None
+    }
+
+    /// Called by `run` after every message, and every `TICK_INTERVAL` when nothing is received.
+    /// Used by the application layers for their timers and their own command channels
+    fn on_tick(&mut self) {}
+
     /// Handle packets received from the simulation controller
     fn handle_sim_controller_packets(
         &mut self,
@@ -430,9 +512,9 @@ pub trait Client: Send {
     fn run(&mut self, mut ticks: u64) {
         // Add the neighbors to the topology
         if self.topology().edges().is_empty() {
-            let senders = self.senders().clone();
+            let neighbors = self.transport().neighbors();
             let client_id = self.client_id();
-            for (sender_id, _channel) in senders {
+            for sender_id in neighbors {
                 self.topology().add_node(sender_id);
                 self.topology()
                     .set_node_type(sender_id, "drone".to_string());
@@ -450,15 +532,22 @@ pub trait Client: Send {
         // Run the client for a certain number of ticks
         while ticks > 0 {
             // Select the first available message from the receiver or the simulation controller receiver
-            select_biased! {
+            let received = select_biased! {
                 recv(self.sim_controller_receiver()) -> packet => {
                     self.handle_sim_controller_packets(packet);
+                    true
                 }
-                recv(self.receiver()) -> packet => {
+                recv(self.transport().receiver()) -> packet => {
                     self.on_drone_packet_received(packet);
+                    true
                 }
+                default(TICK_INTERVAL) => false,
+            };
+            self.on_tick();
+            // Only the received messages count as ticks
+            if received {
+                ticks -= 1;
             }
-            ticks -= 1;
         }
         *self.running() = false;
         self.logger().log("Client stopped", LogLevel::INFO);
@@ -514,17 +603,18 @@ pub trait Client: Send {
                 .or_insert(vec![false; total_n_fragments_usize]);
         }
         let drone_id = message.routing_header.hops[message.routing_header.hop_index];
-        match self.senders().get(&drone_id) {
-            Some(sender) => {
-                let _res = sender.send(message);
-            }
-            None => {
-                panic!(
-                    "Client {}: No sender found for client {}",
-                    self.client_id(),
-                    drone_id
-                );
-            }
+        if !self.transport().has_neighbor(drone_id) {
+            panic!(
+                "Client {}: No sender found for client {}",
+                self.client_id(),
+                drone_id
+            );
+        }
+        if let Err(err) = self.transport().send(drone_id, message) {
+            self.logger().log(
+                &format!("Error sending packet to {drone_id}: {err}"),
+                LogLevel::ERROR,
+            );
         }
     }
 
@@ -584,10 +674,7 @@ pub trait Client: Send {
 
     /// Send flood request to the neighbors
     fn send_flood_request(&mut self) {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or(std::time::Duration::from_secs(0))
-            .as_millis();
+        let now = current_timestamp_ms();
         let timeout = u128::from(rustafarian_shared::TIMEOUT_BETWEEN_FLOODS_MS);
         // Return if the flood was started less than 500 ms ago
         if *self.last_flood_timestamp() + timeout > now {
@@ -600,7 +687,7 @@ pub trait Client: Send {
         let self_id = self.client_id();
         let flood_id = rand::random();
         self.sent_flood_ids().push(flood_id);
-        for sender in self.senders() {
+        for neighbor_id in self.transport().neighbors() {
             let packet = Packet {
                 pack_type: PacketType::FloodRequest(FloodRequest {
                     initiator_id: self.client_id(),
@@ -613,7 +700,7 @@ pub trait Client: Send {
                     hops: Vec::new(),
                 },
             };
-            let _res = sender.1.send(packet);
+            let _res = self.transport().send(neighbor_id, packet);
         }
         // Notify the simulation controller that a flood request has been sent
         let _res = self
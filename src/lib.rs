@@ -1,6 +1,11 @@
 pub mod browser_client;
+pub mod chat;
 pub mod chat_client;
 pub mod client;
+pub mod protocol;
+pub mod sim;
+pub mod transport;
+pub mod unified_client;
 
 #[cfg(test)]
 mod tests {
@@ -8,5 +13,9 @@ mod tests {
 
     mod browser;
     mod chat;
+    mod protocol_test;
     mod routing_test;
+    mod sim_test;
+    mod transport_test;
+    mod unified_test;
 }
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use crossbeam_channel::Sender;
+use rustafarian_shared::logger::Logger;
+use rustafarian_shared::messages::commander_messages::{
+    SimControllerCommand, SimControllerResponseWrapper,
+};
+use rustafarian_shared::messages::general_messages::ServerType;
+use wg_2024::network::NodeId;
+
+use crate::client::Client;
+
+/// The tag of the protocol spoken by a server of the given type ("Chat", "Text" or "Media").
+/// It's the same string used as node type in the topology
+#[must_use]
+pub fn server_type_tag(server_type: &ServerType) -> String {
+    format!("{server_type:?}")
+}
+
+/// What a protocol handler can do with the client running it
+pub trait ProtocolContext {
+    fn client_id(&self) -> NodeId;
+    /// Send a message to a server, through the transport of the client
+    fn send_message(&mut self, destination_id: NodeId, message: String);
+    fn sim_controller_sender(&self) -> &Sender<SimControllerResponseWrapper>;
+    fn logger(&self) -> &Logger;
+}
+
+impl<C: Client> ProtocolContext for C {
+    fn client_id(&self) -> NodeId {
+        Client::client_id(self)
+    }
+
+    fn send_message(&mut self, destination_id: NodeId, message: String) {
+        Client::send_message(self, destination_id, message);
+    }
+
+    fn sim_controller_sender(&self) -> &Sender<SimControllerResponseWrapper> {
+        Client::sim_controller_sender(self)
+    }
+
+    fn logger(&self) -> &Logger {
+        Client::logger(self)
+    }
+}
+
+/// An application protocol that runs on top of the client transport.
+/// Each handler deserializes its own payloads, so new kinds of servers
+/// can be supported without a new `Client` implementor
+pub trait ProtocolHandler: Send {
+    /// Handle a complete message received from a server speaking this protocol
+    /// # Errors
+    /// Returns an error if the message couldn't be deserialized
+    fn handle_message(
+        &mut self,
+        context: &mut dyn ProtocolContext,
+        server_id: NodeId,
+        raw_content: &str,
+    ) -> Result<(), String>;
+
+    /// Handle a command of the controller.
+    /// Returns the command back if it's not meant for this protocol
+    fn handle_command(
+        &mut self,
+        _context: &mut dyn ProtocolContext,
+        command: SimControllerCommand,
+    ) -> Option<SimControllerCommand> {
+        Some(command)
+    }
+
+    /// When a server speaking this protocol is discovered
+    fn on_server_discovered(&mut self, _context: &mut dyn ProtocolContext, _server_id: NodeId) {}
+}
+
+/// The protocol handlers of a client, registered by tag
+#[derive(Default)]
+pub struct ProtocolRegistry {
+    /// Key: tag, value: the handler of the protocol
+    handlers: HashMap<String, Box<dyn ProtocolHandler>>,
+    /// The tags in registration order, the commands are offered to the handlers in this order
+    order: Vec<String>,
+    /// Key: `server_id`, value: the tag of the protocol spoken by the server
+    server_tags: HashMap<NodeId, String>,
+}
+
+impl ProtocolRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for a tag, returns the handler previously registered for it
+    pub fn register(
+        &mut self,
+        tag: &str,
+        handler: Box<dyn ProtocolHandler>,
+    ) -> Option<Box<dyn ProtocolHandler>> {
+        if !self.order.iter().any(|registered| registered == tag) {
+            self.order.push(tag.to_string());
+        }
+        self.handlers.insert(tag.to_string(), handler)
+    }
+
+    /// Remove the handler of a tag
+    pub fn unregister(&mut self, tag: &str) -> Option<Box<dyn ProtocolHandler>> {
+        self.order.retain(|registered| registered != tag);
+        self.handlers.remove(tag)
+    }
+
+    #[must_use]
+    pub fn has_handler(&self, tag: &str) -> bool {
+        self.handlers.contains_key(tag)
+    }
+
+    /// Set the protocol spoken by a server.
+    /// Needed for custom protocols, as a `ServerTypeResponse` can only contain the built-in types
+    pub fn assign_server(&mut self, server_id: NodeId, tag: &str) {
+        self.server_tags.insert(server_id, tag.to_string());
+    }
+
+    /// The tag of the protocol spoken by a server, if known
+    #[must_use]
+    pub fn tag_of(&self, server_id: NodeId) -> Option<&str> {
+        self.server_tags.get(&server_id).map(String::as_str)
+    }
+
+    /// The tag of the handler for the messages of a server, if one is registered
+    #[must_use]
+    pub fn handler_tag_of(&self, server_id: NodeId) -> Option<String> {
+        self.tag_of(server_id)
+            .filter(|tag| self.has_handler(tag))
+            .map(str::to_string)
+    }
+
+    /// The registered tags, in registration order
+    #[must_use]
+    pub fn tags(&self) -> Vec<String> {
+        self.order.clone()
+    }
+
+    /// Take a handler out of the registry, so it can be run with the client as context.
+    /// It must be put back with `put_back`
+    pub(crate) fn take(&mut self, tag: &str) -> Option<Box<dyn ProtocolHandler>> {
+        self.handlers.remove(tag)
+    }
+
+    pub(crate) fn put_back(&mut self, tag: &str, handler: Box<dyn ProtocolHandler>) {
+        self.handlers.insert(tag.to_string(), handler);
+    }
+}
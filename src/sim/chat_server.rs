@@ -0,0 +1,83 @@
+use rustafarian_shared::messages::chat_messages::{
+    ChatRequest, ChatRequestWrapper, ChatResponse, ChatResponseWrapper,
+};
+use rustafarian_shared::messages::general_messages::{ServerType, ServerTypeResponse};
+use rustafarian_shared::topology::Topology;
+use wg_2024::network::NodeId;
+
+use super::server_node::ServerNode;
+use crate::transport::Transport;
+
+/// A minimal chat server used by the simulated network: it keeps the list of registered clients,
+/// and forwards the messages between them
+pub struct StubChatServer {
+    node: ServerNode,
+    registered_clients: Vec<NodeId>,
+}
+
+impl StubChatServer {
+    #[must_use]
+    pub fn new(server_id: NodeId, transport: Box<dyn Transport>, topology: Topology) -> Self {
+        StubChatServer {
+            node: ServerNode::new(server_id, transport, topology),
+            registered_clients: Vec::new(),
+        }
+    }
+
+    /// Serve requests until the channel is closed
+    pub fn run(&mut self) {
+        while let Ok(packet) = self.node.receiver().recv() {
+            if let Some((source_id, message)) = self.node.on_packet_received(packet) {
+                self.handle_request(source_id, &message);
+            }
+        }
+    }
+
+    fn handle_request(&mut self, source_id: NodeId, raw_request: &str) {
+        let Ok(request) = serde_json::from_str::<ChatRequestWrapper>(raw_request) else {
+            return;
+        };
+        match request {
+            ChatRequestWrapper::ServerType(_) => {
+                let response = ServerTypeResponse::ServerType(ServerType::Chat);
+                self.respond(source_id, &ChatResponseWrapper::ServerType(response));
+            }
+            ChatRequestWrapper::Chat(ChatRequest::Register(client_id)) => {
+                if !self.registered_clients.contains(&client_id) {
+                    self.registered_clients.push(client_id);
+                }
+                self.respond(
+                    source_id,
+                    &ChatResponseWrapper::Chat(ChatResponse::ClientRegistered),
+                );
+            }
+            ChatRequestWrapper::Chat(ChatRequest::ClientList) => {
+                let client_list = self.registered_clients.clone();
+                self.respond(
+                    source_id,
+                    &ChatResponseWrapper::Chat(ChatResponse::ClientList(client_list)),
+                );
+            }
+            ChatRequestWrapper::Chat(ChatRequest::SendMessage { from, to, message }) => {
+                // Messages to clients that are not registered are dropped
+                if !self.registered_clients.contains(&to) {
+                    return;
+                }
+                let message_from = ChatResponse::MessageFrom {
+                    from,
+                    message: message.into_bytes(),
+                };
+                self.respond(to, &ChatResponseWrapper::Chat(message_from));
+                self.respond(
+                    source_id,
+                    &ChatResponseWrapper::Chat(ChatResponse::MessageSent),
+                );
+            }
+        }
+    }
+
+    fn respond(&mut self, destination_id: NodeId, response: &ChatResponseWrapper) {
+        let response_json = serde_json::to_string(response).unwrap_or_default();
+        self.node.send_message(destination_id, &response_json);
+    }
+}
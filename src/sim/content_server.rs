@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use rustafarian_shared::messages::browser_messages::{
+    BrowserRequest, BrowserRequestWrapper, BrowserResponse, BrowserResponseWrapper,
+};
+use rustafarian_shared::messages::general_messages::{ServerType, ServerTypeResponse};
+use rustafarian_shared::topology::Topology;
+use wg_2024::network::NodeId;
+
+use super::server_node::ServerNode;
+use crate::transport::Transport;
+
+/// A minimal content server used by the simulated network, serving text or media files from memory
+pub struct StubContentServer {
+    node: ServerNode,
+    server_type: ServerType,
+    /// Key: `file_id`, value: the content of the file
+    files: HashMap<u8, Vec<u8>>,
+}
+
+impl StubContentServer {
+    /// Create a server of type `Text`
+    #[must_use]
+    pub fn text(
+        server_id: NodeId,
+        transport: Box<dyn Transport>,
+        topology: Topology,
+        files: HashMap<u8, String>,
+    ) -> Self {
+        StubContentServer {
+            node: ServerNode::new(server_id, transport, topology),
+            server_type: ServerType::Text,
+            files: files
+                .into_iter()
+                .map(|(file_id, text)| (file_id, text.into_bytes()))
+                .collect(),
+        }
+    }
+
+    /// Create a server of type `Media`
+    #[must_use]
+    pub fn media(
+        server_id: NodeId,
+        transport: Box<dyn Transport>,
+        topology: Topology,
+        files: HashMap<u8, Vec<u8>>,
+    ) -> Self {
+        StubContentServer {
+            node: ServerNode::new(server_id, transport, topology),
+            server_type: ServerType::Media,
+            files,
+        }
+    }
+
+    /// Serve requests until the channel is closed
+    pub fn run(&mut self) {
+        while let Ok(packet) = self.node.receiver().recv() {
+            if let Some((source_id, message)) = self.node.on_packet_received(packet) {
+                self.handle_request(source_id, &message);
+            }
+        }
+    }
+
+    fn handle_request(&mut self, source_id: NodeId, raw_request: &str) {
+        let Ok(request) = serde_json::from_str::<BrowserRequestWrapper>(raw_request) else {
+            return;
+        };
+        let response = match request {
+            BrowserRequestWrapper::ServerType(_) => BrowserResponseWrapper::ServerType(
+                ServerTypeResponse::ServerType(self.server_type.clone()),
+            ),
+            BrowserRequestWrapper::Chat(BrowserRequest::FileList) => {
+                let mut file_ids = self.files.keys().copied().collect::<Vec<u8>>();
+                file_ids.sort_unstable();
+                BrowserResponseWrapper::Chat(BrowserResponse::FileList(file_ids))
+            }
+            BrowserRequestWrapper::Chat(BrowserRequest::TextFileRequest(file_id)) => {
+                // Unknown files are not answered
+                let Some(content) = self.files.get(&file_id) else {
+                    return;
+                };
+                let text = String::from_utf8_lossy(content).to_string();
+                BrowserResponseWrapper::Chat(BrowserResponse::TextFile(file_id, text))
+            }
+            BrowserRequestWrapper::Chat(BrowserRequest::MediaFileRequest(file_id)) => {
+                let Some(content) = self.files.get(&file_id) else {
+                    return;
+                };
+                BrowserResponseWrapper::Chat(BrowserResponse::MediaFile(file_id, content.clone()))
+            }
+        };
+        let response_json = serde_json::to_string(&response).unwrap_or_default();
+        self.node.send_message(source_id, &response_json);
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Nack, NackType, NodeType, Packet, PacketType};
+
+use crate::transport::Transport;
+
+/// A lossless drone used by the simulated network: it forwards the packets following the source routing header,
+/// and takes part in the flooding
+pub struct SimDrone {
+    id: NodeId,
+    transport: Box<dyn Transport>,
+    /// The floods already seen, as (`initiator_id`, `flood_id`)
+    seen_floods: HashSet<(NodeId, u64)>,
+}
+
+impl SimDrone {
+    #[must_use]
+    pub fn new(id: NodeId, transport: Box<dyn Transport>) -> Self {
+        SimDrone {
+            id,
+            transport,
+            seen_floods: HashSet::new(),
+        }
+    }
+
+    /// Forward packets until the channel is closed
+    pub fn run(&mut self) {
+        while let Ok(packet) = self.transport.receiver().recv() {
+            self.on_packet_received(packet);
+        }
+    }
+
+    fn on_packet_received(&mut self, packet: Packet) {
+        match packet.pack_type.clone() {
+            PacketType::FloodRequest(mut request) => {
+                let Some(&(sender_id, _)) = request.path_trace.last() else {
+                    return;
+                };
+                request.increment(self.id, NodeType::Drone);
+                let first_time = self
+                    .seen_floods
+                    .insert((request.initiator_id, request.flood_id));
+                let next_hops = self
+                    .transport
+                    .neighbors()
+                    .into_iter()
+                    .filter(|neighbor_id| *neighbor_id != sender_id)
+                    .collect::<Vec<NodeId>>();
+                // Already seen, or nowhere else to go: send the response back
+                if !first_time || next_hops.is_empty() {
+                    let response = request.generate_response(packet.session_id);
+                    let _res = self.transport.send(sender_id, response);
+                    return;
+                }
+                let forwarded = Packet::new_flood_request(
+                    SourceRoutingHeader::empty_route(),
+                    packet.session_id,
+                    request,
+                );
+                for neighbor_id in next_hops {
+                    let _res = self.transport.send(neighbor_id, forwarded.clone());
+                }
+            }
+            _ => self.forward(packet),
+        }
+    }
+
+    /// Send the packet to the next hop of its routing header
+    fn forward(&self, mut packet: Packet) {
+        let position = packet.routing_header.hop_index;
+        if packet.routing_header.hops.get(position) != Some(&self.id) {
+            return;
+        }
+        let Some(next_hop) = packet.routing_header.hops.get(position + 1).copied() else {
+            return;
+        };
+        if !self.transport.has_neighbor(next_hop) {
+            self.send_nack(&packet, position, NackType::ErrorInRouting(next_hop));
+            return;
+        }
+        packet.routing_header.increase_hop_index();
+        let _res = self.transport.send(next_hop, packet);
+    }
+
+    /// Send a NACK back to the source of a fragment that couldn't be forwarded
+    fn send_nack(&self, packet: &Packet, position: usize, nack_type: NackType) {
+        let PacketType::MsgFragment(fragment) = &packet.pack_type else {
+            return;
+        };
+        let mut hops = packet.routing_header.hops[..=position].to_vec();
+        hops.reverse();
+        let Some(&previous_hop) = hops.get(1) else {
+            return;
+        };
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: fragment.fragment_index,
+                nack_type,
+            }),
+            session_id: packet.session_id,
+            routing_header: SourceRoutingHeader { hop_index: 1, hops },
+        };
+        let _res = self.transport.send(previous_hop, nack);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod chat_server;
+pub mod content_server;
+pub mod drone;
+pub mod network;
+mod server_node;
+
+pub use chat_server::StubChatServer;
+pub use content_server::StubContentServer;
+pub use drone::SimDrone;
+pub use network::VirtualNetwork;
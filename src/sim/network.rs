@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::thread;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_shared::topology::Topology;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use super::{SimDrone, StubChatServer, StubContentServer};
+use crate::transport::CrossbeamTransport;
+
+/// The kind of a node in the simulated network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimNodeKind {
+    Drone,
+    ChatServer,
+    TextServer,
+    MediaServer,
+    /// A client, started by the caller with the transport returned by `client_transport`
+    Client,
+}
+
+/// Builder for a network of simulated drones and stub servers, running as threads of the current process
+#[derive(Default)]
+pub struct VirtualNetwork {
+    nodes: HashMap<NodeId, SimNodeKind>,
+    /// Undirected edges between the nodes
+    edges: Vec<(NodeId, NodeId)>,
+    /// The channel used by each node to receive packets
+    channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)>,
+    /// The files served by the text servers, by `server_id`
+    text_files: HashMap<NodeId, HashMap<u8, String>>,
+    /// The files served by the media servers, by `server_id`
+    media_files: HashMap<NodeId, HashMap<u8, Vec<u8>>>,
+}
+
+impl VirtualNetwork {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node to the network
+    pub fn add_node(&mut self, node_id: NodeId, kind: SimNodeKind) -> &mut Self {
+        self.nodes.insert(node_id, kind);
+        self.channels.entry(node_id).or_insert_with(unbounded);
+        self
+    }
+
+    /// Add a drone to the network
+    pub fn add_drone(&mut self, node_id: NodeId) -> &mut Self {
+        self.add_node(node_id, SimNodeKind::Drone)
+    }
+
+    /// Add a stub chat server to the network
+    pub fn add_chat_server(&mut self, node_id: NodeId) -> &mut Self {
+        self.add_node(node_id, SimNodeKind::ChatServer)
+    }
+
+    /// Add a stub text server to the network, serving `files`
+    pub fn add_text_server(&mut self, node_id: NodeId, files: HashMap<u8, String>) -> &mut Self {
+        self.text_files.insert(node_id, files);
+        self.add_node(node_id, SimNodeKind::TextServer)
+    }
+
+    /// Add a stub media server to the network, serving `files`
+    pub fn add_media_server(&mut self, node_id: NodeId, files: HashMap<u8, Vec<u8>>) -> &mut Self {
+        self.media_files.insert(node_id, files);
+        self.add_node(node_id, SimNodeKind::MediaServer)
+    }
+
+    /// Add a client to the network
+    pub fn add_client(&mut self, node_id: NodeId) -> &mut Self {
+        self.add_node(node_id, SimNodeKind::Client)
+    }
+
+    /// Connect two nodes in both directions
+    pub fn connect(&mut self, first_id: NodeId, second_id: NodeId) -> &mut Self {
+        self.edges.push((first_id, second_id));
+        self
+    }
+
+    /// The ids of the neighbors of a node
+    #[must_use]
+    pub fn neighbors(&self, node_id: NodeId) -> Vec<NodeId> {
+        self.edges
+            .iter()
+            .filter_map(|(first_id, second_id)| {
+                if *first_id == node_id {
+                    Some(*second_id)
+                } else if *second_id == node_id {
+                    Some(*first_id)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The complete topology of the network, as known by the stub servers
+    #[must_use]
+    pub fn topology(&self) -> Topology {
+        let mut topology = Topology::new();
+        for (node_id, kind) in &self.nodes {
+            topology.add_node(*node_id);
+            let node_type = match kind {
+                SimNodeKind::Drone => "drone",
+                SimNodeKind::Client => "client",
+                SimNodeKind::ChatServer | SimNodeKind::TextServer | SimNodeKind::MediaServer => {
+                    "server"
+                }
+            };
+            topology.set_node_type(*node_id, node_type.to_string());
+        }
+        for (first_id, second_id) in &self.edges {
+            topology.add_edge(*first_id, *second_id);
+            topology.add_edge(*second_id, *first_id);
+        }
+        topology
+    }
+
+    /// The transport a node uses to talk to its neighbors
+    /// # Panics
+    /// Panics if the node wasn't added to the network
+    #[must_use]
+    pub fn transport(&self, node_id: NodeId) -> CrossbeamTransport {
+        let senders = self
+            .neighbors(node_id)
+            .into_iter()
+            .filter_map(|neighbor_id| {
+                self.channels
+                    .get(&neighbor_id)
+                    .map(|channel| (neighbor_id, channel.0.clone()))
+            })
+            .collect::<HashMap<NodeId, Sender<Packet>>>();
+        let receiver = self
+            .channels
+            .get(&node_id)
+            .unwrap_or_else(|| panic!("Node {node_id} is not part of the network"))
+            .1
+            .clone();
+        CrossbeamTransport::new(senders, receiver)
+    }
+
+    /// The channel the simulation uses to send packets to a node, e.g. to inject packets in tests
+    #[must_use]
+    pub fn packet_sender(&self, node_id: NodeId) -> Option<Sender<Packet>> {
+        self.channels.get(&node_id).map(|channel| channel.0.clone())
+    }
+
+    /// Spawn a thread for every drone and server of the network.
+    /// The clients must be started by the caller, using `transport`.
+    pub fn start(&self) {
+        let topology = self.topology();
+        for (node_id, kind) in &self.nodes {
+            match kind {
+                SimNodeKind::Drone => {
+                    let mut drone = SimDrone::new(*node_id, Box::new(self.transport(*node_id)));
+                    thread::spawn(move || drone.run());
+                }
+                SimNodeKind::ChatServer => {
+                    let mut server = StubChatServer::new(
+                        *node_id,
+                        Box::new(self.transport(*node_id)),
+                        topology.clone(),
+                    );
+                    thread::spawn(move || server.run());
+                }
+                SimNodeKind::TextServer => {
+                    let mut server = StubContentServer::text(
+                        *node_id,
+                        Box::new(self.transport(*node_id)),
+                        topology.clone(),
+                        self.text_files.get(node_id).cloned().unwrap_or_default(),
+                    );
+                    thread::spawn(move || server.run());
+                }
+                SimNodeKind::MediaServer => {
+                    let mut server = StubContentServer::media(
+                        *node_id,
+                        Box::new(self.transport(*node_id)),
+                        topology.clone(),
+                        self.media_files.get(node_id).cloned().unwrap_or_default(),
+                    );
+                    thread::spawn(move || server.run());
+                }
+                SimNodeKind::Client => {}
+            }
+        }
+    }
+}
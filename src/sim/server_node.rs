@@ -0,0 +1,86 @@
+use crossbeam_channel::Receiver;
+use rustafarian_shared::assembler::{assembler::Assembler, disassembler::Disassembler};
+use rustafarian_shared::topology::Topology;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Ack, NodeType, Packet, PacketType};
+
+use crate::transport::Transport;
+
+/// The network part shared by the stub servers: fragmentation, ACKs and flood responses.
+/// The servers know the whole topology of the simulated network, so they don't need to flood.
+pub(crate) struct ServerNode {
+    id: NodeId,
+    transport: Box<dyn Transport>,
+    topology: Topology,
+    assembler: Assembler,
+    disassembler: Disassembler,
+}
+
+impl ServerNode {
+    pub(crate) fn new(id: NodeId, transport: Box<dyn Transport>, topology: Topology) -> Self {
+        ServerNode {
+            id,
+            transport,
+            topology,
+            assembler: Assembler::new(),
+            disassembler: Disassembler::new(),
+        }
+    }
+
+    pub(crate) fn receiver(&self) -> &Receiver<Packet> {
+        self.transport.receiver()
+    }
+
+    /// Handle the network side of a packet.
+    /// Returns the id of the source and the content when a message is completed.
+    pub(crate) fn on_packet_received(&mut self, packet: Packet) -> Option<(NodeId, String)> {
+        match packet.pack_type {
+            PacketType::MsgFragment(fragment) => {
+                let source_id = packet.routing_header.hops[0];
+                let fragment_index = fragment.fragment_index;
+                let message = self.assembler.add_fragment(fragment, packet.session_id);
+                self.send_ack(fragment_index, source_id, packet.session_id);
+                message.map(|message| (source_id, String::from_utf8_lossy(&message).to_string()))
+            }
+            PacketType::FloodRequest(mut request) => {
+                let &(sender_id, _) = request.path_trace.last()?;
+                request.increment(self.id, NodeType::Server);
+                let response = request.generate_response(packet.session_id);
+                let _res = self.transport.send(sender_id, response);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Fragment a message and send it to a node
+    pub(crate) fn send_message(&mut self, destination_id: NodeId, message: &str) {
+        let session_id = rand::random();
+        let fragments = self
+            .disassembler
+            .disassemble_message(message.as_bytes().to_vec(), session_id);
+        for fragment in fragments {
+            self.send_packet(Packet {
+                pack_type: PacketType::MsgFragment(fragment),
+                session_id,
+                routing_header: self.topology.get_routing_header(self.id, destination_id),
+            });
+        }
+    }
+
+    fn send_ack(&mut self, fragment_index: u64, destination_id: NodeId, session_id: u64) {
+        self.send_packet(Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index }),
+            session_id,
+            routing_header: self.topology.get_routing_header(self.id, destination_id),
+        });
+    }
+
+    fn send_packet(&self, packet: Packet) {
+        let header = &packet.routing_header;
+        let Some(&next_hop) = header.hops.get(header.hop_index) else {
+            return;
+        };
+        let _res = self.transport.send(next_hop, packet);
+    }
+}
@@ -179,7 +179,7 @@ pub mod command_tests {
 
         browser_client.handle_sim_controller_packets(Ok(as_request));
 
-        assert!(browser_client.senders().contains_key(&3));
+        assert!(browser_client.transport().has_neighbor(3));
 
         assert!(browser_client.topology().nodes().contains(&3));
         assert!(browser_client.topology().edges().contains_key(&1));
@@ -205,7 +205,7 @@ pub mod command_tests {
 
         browser_client.handle_sim_controller_packets(Ok(as_request));
 
-        assert!(!browser_client.senders().contains_key(&2));
+        assert!(!browser_client.transport().has_neighbor(2));
 
         assert!(browser_client.topology().nodes().contains(&2));
         assert!(browser_client.topology().edges().contains_key(&1));
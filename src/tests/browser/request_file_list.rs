@@ -15,7 +15,7 @@ pub mod request_file_list_tests {
         packet::{Packet, PacketType},
     };
 
-    use crate::{client::Client, tests::util::build_browser};
+    use crate::{browser_client::BrowserLayer, client::Client, tests::util::build_browser};
 
     #[test]
     fn request_file_list() {
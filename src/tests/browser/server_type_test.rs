@@ -12,6 +12,7 @@ pub mod server_type_test {
         assembler::disassembler::Disassembler, messages::chat_messages::ChatRequestWrapper,
     };
 
+    use crate::browser_client::BrowserLayer;
     use crate::client::Client;
     use crate::tests::util;
 
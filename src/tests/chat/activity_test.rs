@@ -0,0 +1,119 @@
+#[cfg(test)]
+pub mod activity_test {
+    use crossbeam_channel::{unbounded, Receiver};
+    use rustafarian_shared::messages::chat_messages::{ChatRequest, ChatResponseWrapper};
+
+    use crate::chat::{
+        Activity, ActivityTracker, ChatCommand, ChatConfig, ChatEvent, ChatPayload, RateLimit,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn activity_from(from: u8, activity: Activity) -> ChatResponseWrapper {
+        util::message_from(from, &ChatPayload::Activity(activity))
+    }
+
+    fn activities(events: &Receiver<ChatEvent>) -> Vec<(u8, Activity)> {
+        events
+            .try_iter()
+            .filter_map(|event| match event {
+                ChatEvent::PeerActivity {
+                    peer_id, activity, ..
+                } => Some((peer_id, activity)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn typing_times_out() {
+        let mut tracker = ActivityTracker::new(1000);
+        assert!(tracker.on_activity(21, 3, Activity::Typing, 0));
+        assert!(!tracker.on_activity(21, 3, Activity::Typing, 500));
+        assert!(tracker.on_activity(21, 4, Activity::Typing, 0));
+        assert_eq!(tracker.timed_out(1000), vec![(21, 4)]);
+        assert!(tracker.is_typing(21, 3));
+        assert!(tracker.on_message(21, 3));
+        assert!(!tracker.on_activity(21, 3, Activity::Idle, 1000));
+        assert!(tracker.timed_out(5000).is_empty());
+    }
+
+    #[test]
+    fn activity_is_reported_and_not_saved() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_chat_layer_command(ChatCommand::SendActivity {
+            server_id: 21,
+            peer_id: 3,
+            activity: Activity::Typing,
+        });
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor.1).as_slice(),
+            [(21, ChatRequest::SendMessage { to: 3, .. })]
+        ));
+
+        chat_client.handle_response(activity_from(3, Activity::Typing), 21);
+        chat_client.handle_response(activity_from(3, Activity::Typing), 21);
+        assert!(chat_client.is_typing(21, 3));
+        assert_eq!(activities(&chat_events.1), vec![(3, Activity::Typing)]);
+
+        // The message ends the typing
+        chat_client.handle_response(util::text_from(3, 1, "Hello"), 21);
+        assert_eq!(activities(&chat_events.1), vec![(3, Activity::Idle)]);
+        // Only the message is in the history
+        let history = chat_client.get_history().conversation(21, 3);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].text, "Hello");
+    }
+
+    #[test]
+    fn filtered_activity_is_dropped() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.handle_chat_layer_command(ChatCommand::Block(3));
+        chat_client.handle_chat_layer_command(ChatCommand::Mute(4));
+        chat_client.set_rate_limit(Some(RateLimit {
+            max_messages: 1,
+            interval_ms: 60_000,
+        }));
+
+        chat_client.handle_response(activity_from(3, Activity::Typing), 21);
+        chat_client.handle_response(activity_from(4, Activity::Typing), 21);
+        chat_client.handle_response(activity_from(5, Activity::Typing), 21);
+        chat_client.handle_response(activity_from(5, Activity::Idle), 21);
+        assert!(!chat_client.is_typing(21, 3));
+        assert!(!chat_client.is_typing(21, 4));
+        assert!(chat_client.is_typing(21, 5));
+        assert_eq!(activities(&chat_events.1), vec![(5, Activity::Typing)]);
+    }
+
+    #[test]
+    fn typing_expires_on_tick() {
+        let config = ChatConfig {
+            typing_timeout_ms: 0,
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, _neighbor) = util::build_chat_client(&config, &[]);
+        let chat_events = util::attach_events(&mut chat_client);
+
+        chat_client.handle_response(activity_from(3, Activity::Typing), 21);
+        chat_client.on_tick();
+        assert!(!chat_client.is_typing(21, 3));
+        assert_eq!(
+            activities(&chat_events),
+            vec![(3, Activity::Typing), (3, Activity::Idle)]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+pub mod auto_join_test {
+    use std::fs;
+
+    use rustafarian_shared::messages::chat_messages::{
+        ChatRequest, ChatResponse, ChatResponseWrapper,
+    };
+    use rustafarian_shared::messages::general_messages::{ServerType, ServerTypeResponse};
+
+    use crate::chat::{AutoJoinPolicy, ChatCommand, ChatConfig, RegistrationState};
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn discover(chat_client: &mut ChatClient, server_id: u8) {
+        let response =
+            ChatResponseWrapper::ServerType(ServerTypeResponse::ServerType(ServerType::Chat));
+        chat_client.handle_response(response, server_id);
+    }
+
+    #[test]
+    fn policy_selection() {
+        let servers = [(21, Some(3)), (22, Some(2)), (23, None), (24, Some(3))];
+        assert!(AutoJoinPolicy::Off.select(&servers).is_empty());
+        assert_eq!(AutoJoinPolicy::All.select(&servers), vec![22, 21, 24]);
+        assert_eq!(AutoJoinPolicy::Nearest(2).select(&servers), vec![22, 21]);
+        assert_eq!(
+            AutoJoinPolicy::Allowlist(vec![23, 24]).select(&servers),
+            vec![24]
+        );
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+
+        discover(&mut chat_client, 21);
+        assert!(util::sent_chat_requests(&neighbor.1).is_empty());
+    }
+
+    #[test]
+    fn joins_discovered_servers() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.handle_chat_layer_command(ChatCommand::SetAutoJoin(AutoJoinPolicy::All));
+
+        discover(&mut chat_client, 21);
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor.1).as_slice(),
+            [(21, ChatRequest::Register(1))]
+        ));
+
+        // The client list is requested once registered
+        chat_client.handle_response(
+            ChatResponseWrapper::Chat(ChatResponse::ClientRegistered),
+            21,
+        );
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor.1).as_slice(),
+            [(21, ChatRequest::ClientList)]
+        ));
+    }
+
+    #[test]
+    fn joins_nearest_and_allowed_servers() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.topology().add_node(3);
+        chat_client.topology().add_node(22);
+        chat_client.topology().add_edge(2, 3);
+        chat_client.topology().add_edge(3, 22);
+        discover(&mut chat_client, 22);
+        discover(&mut chat_client, 21);
+
+        chat_client.handle_chat_layer_command(ChatCommand::SetAutoJoin(AutoJoinPolicy::Nearest(1)));
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor.1).as_slice(),
+            [(21, ChatRequest::Register(1))]
+        ));
+
+        chat_client.handle_chat_layer_command(ChatCommand::SetAutoJoin(AutoJoinPolicy::Allowlist(
+            vec![22],
+        )));
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor.1).as_slice(),
+            [(22, ChatRequest::Register(1))]
+        ));
+    }
+
+    #[test]
+    fn rejoins_after_unreachable() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.handle_chat_layer_command(ChatCommand::SetAutoJoin(AutoJoinPolicy::All));
+        discover(&mut chat_client, 21);
+        chat_client.handle_response(
+            ChatResponseWrapper::Chat(ChatResponse::ClientRegistered),
+            21,
+        );
+        let _requests = util::sent_chat_requests(&neighbor.1);
+
+        chat_client.topology().remove_edges(2, 21);
+        chat_client.apply_auto_join();
+        assert_eq!(
+            chat_client.get_registration_state(21),
+            RegistrationState::Unregistered
+        );
+        assert!(util::sent_chat_requests(&neighbor.1).is_empty());
+
+        chat_client.topology().add_edge(2, 21);
+        chat_client.apply_auto_join();
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor.1).as_slice(),
+            [(21, ChatRequest::Register(1))]
+        ));
+    }
+
+    #[test]
+    fn settings_are_saved_in_the_config() {
+        let path = std::env::temp_dir().join(format!("config_{}.json", rand::random::<u64>()));
+        let config = ChatConfig::load(&path).unwrap();
+        let (mut chat_client, _neighbor) = util::build_chat_client(&config, &[]);
+        chat_client.handle_chat_layer_command(ChatCommand::SetAutoJoin(AutoJoinPolicy::Nearest(2)));
+        chat_client.handle_chat_layer_command(ChatCommand::SetPresencePolling(Some(5000)));
+
+        let reloaded = ChatConfig::load(&path).unwrap();
+        assert_eq!(reloaded.auto_join, AutoJoinPolicy::Nearest(2));
+        assert_eq!(reloaded.presence_poll_interval_ms, Some(5000));
+        let _res = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+pub mod behavior_test {
+    use std::collections::HashMap;
+
+    use crossbeam_channel::unbounded;
+
+    use crate::chat::behavior::PING_TIMEOUT_MS;
+    use crate::chat::{
+        BehaviorAction, BehaviorContext, ChatBehavior, ChatEvent, Chatter, Echo, MessageContent,
+        PingPong,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util::{self, SentText};
+
+    fn context(now: u128) -> BehaviorContext {
+        BehaviorContext {
+            client_id: 1,
+            now,
+            registered_servers: vec![21],
+            available_clients: HashMap::from([(21, vec![1, 3]), (22, vec![4])]),
+        }
+    }
+
+    fn text(text: &str) -> MessageContent {
+        MessageContent::Text(text.to_string())
+    }
+
+    #[test]
+    fn echo_answers_once() {
+        let mut echo = Echo;
+        assert_eq!(
+            echo.on_message(&context(0), 21, 3, &text("Hello")),
+            vec![BehaviorAction::Send {
+                server_id: 21,
+                peer_id: 3,
+                text: "echo: Hello".to_string()
+            }]
+        );
+        assert!(echo
+            .on_message(&context(0), 21, 3, &text("echo: Hello"))
+            .is_empty());
+    }
+
+    #[test]
+    fn chatter_joins_and_sends() {
+        let mut chatter = Chatter::new(1000, 10);
+        let actions = chatter.on_tick(&context(5000));
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0], BehaviorAction::Register(22));
+        let BehaviorAction::Send {
+            server_id,
+            peer_id,
+            text,
+        } = &actions[1]
+        else {
+            panic!("The second action should be Send");
+        };
+        assert_eq!((*server_id, *peer_id, text.len()), (21, 3, 10));
+
+        // Too early for the next message, and 22 was already joined
+        assert!(chatter.on_tick(&context(5500)).is_empty());
+        assert_eq!(chatter.on_tick(&context(6000)).len(), 1);
+    }
+
+    #[test]
+    fn ping_pong_measures_rtt() {
+        let mut measurer = PingPong::measurer(21, 3, 1000);
+        let mut responder = PingPong::responder();
+        assert!(responder.on_tick(&context(1000)).is_empty());
+
+        let ping = measurer.on_tick(&context(1000));
+        assert_eq!(
+            ping,
+            vec![BehaviorAction::Send {
+                server_id: 21,
+                peer_id: 3,
+                text: "ping 0".to_string()
+            }]
+        );
+        assert!(measurer.on_tick(&context(1500)).is_empty());
+
+        let pong = responder.on_message(&context(1100), 21, 1, &text("ping 0"));
+        assert_eq!(
+            pong,
+            vec![BehaviorAction::Send {
+                server_id: 21,
+                peer_id: 1,
+                text: "pong 0".to_string()
+            }]
+        );
+        assert_eq!(
+            measurer.on_message(&context(1200), 21, 3, &text("pong 0")),
+            vec![BehaviorAction::Report("RTT to 3: 200 ms".to_string())]
+        );
+        // Only once
+        assert!(measurer
+            .on_message(&context(1300), 21, 3, &text("pong 0"))
+            .is_empty());
+        assert_eq!(measurer.rtts(), &[200]);
+    }
+
+    #[test]
+    fn lost_pings_are_reported() {
+        let mut measurer = PingPong::measurer(21, 3, 1000);
+        assert_eq!(measurer.on_tick(&context(1000)).len(), 1);
+        assert_eq!(measurer.on_tick(&context(2000)).len(), 1);
+        assert_eq!(
+            measurer.on_message(&context(2100), 21, 3, &text("pong 1")),
+            vec![BehaviorAction::Report("RTT to 3: 100 ms".to_string())]
+        );
+
+        let actions = measurer.on_tick(&context(1000 + PING_TIMEOUT_MS));
+        assert_eq!(
+            actions,
+            vec![
+                BehaviorAction::Report("Ping 0 to 3 lost".to_string()),
+                BehaviorAction::Send {
+                    server_id: 21,
+                    peer_id: 3,
+                    text: "ping 2".to_string()
+                }
+            ]
+        );
+        assert_eq!(measurer.lost(), 1);
+        // Too late
+        assert!(measurer
+            .on_message(&context(11_100), 21, 3, &text("pong 0"))
+            .is_empty());
+        assert_eq!(measurer.rtts(), &[100]);
+    }
+
+    #[test]
+    fn behaviors_run_in_the_client() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.add_behavior(Box::new(Echo));
+        chat_client.add_behavior(Box::new(PingPong::measurer(21, 3, 60_000)));
+
+        chat_client.on_tick();
+        assert!(matches!(
+            util::sent_texts(&neighbor.1).as_slice(),
+            [SentText { server_id: 21, to: 3, text, .. }] if text == "ping 0"
+        ));
+
+        chat_client.handle_response(util::text_from(3, 5, "pong 0"), 21);
+        assert!(matches!(
+            util::sent_texts(&neighbor.1).as_slice(),
+            [SentText { server_id: 21, to: 3, text, .. }] if text == "echo: pong 0"
+        ));
+        assert!(chat_events.1.try_iter().any(|event| matches!(
+            event,
+            ChatEvent::BehaviorReport { behavior, .. } if behavior == "ping-pong"
+        )));
+    }
+}
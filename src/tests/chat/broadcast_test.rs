@@ -0,0 +1,103 @@
+#[cfg(test)]
+pub mod broadcast_test {
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::messages::chat_messages::{ChatRequest, ChatResponseWrapper};
+
+    use crate::chat::{
+        BroadcastResult, Broadcasts, ChatCommand, ChatEvent, ChatPayload, DeliveryStatus,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn receipt_from(from: u8, id: u64) -> ChatResponseWrapper {
+        util::message_from(from, &ChatPayload::Receipt { id })
+    }
+
+    #[test]
+    fn broadcasts_finish_when_final_or_timed_out() {
+        let mut broadcasts = Broadcasts::new(1000);
+        broadcasts.start(1, 21, "Hello".to_string(), 0);
+        broadcasts.start(2, 22, "Hello".to_string(), 0);
+        assert_eq!(broadcasts.waiting(21), vec![1]);
+        broadcasts.on_sent(1, vec![(4, 10), (3, 11)], 0);
+        assert!(broadcasts.waiting(21).is_empty());
+        assert_eq!(broadcasts.waiting_timed_out(1000), vec![2]);
+
+        let delivered = |id: u64| match id {
+            10 => Some(DeliveryStatus::Delivered),
+            _ => Some(DeliveryStatus::Pending),
+        };
+        assert!(broadcasts.take_finished(500, delivered).is_empty());
+        let results = broadcasts.take_finished(1000, delivered);
+        assert_eq!(
+            results,
+            vec![BroadcastResult {
+                broadcast_id: 1,
+                server_id: 21,
+                outcomes: vec![(3, DeliveryStatus::Pending), (4, DeliveryStatus::Delivered)]
+            }]
+        );
+        assert_eq!(results[0].delivered(), vec![4]);
+        assert!(broadcasts.get(1).is_none());
+    }
+
+    #[test]
+    fn broadcast_with_fresh_list() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.handle_response(util::client_list(&[1, 3, 4]), 21);
+
+        chat_client.handle_chat_layer_command(ChatCommand::Broadcast {
+            server_id: 21,
+            text: "Hello".to_string(),
+        });
+        let sent = util::sent_texts(&neighbor.1);
+        assert_eq!(
+            sent.iter().map(|sent| sent.to).collect::<Vec<u8>>(),
+            vec![3, 4]
+        );
+
+        chat_client.handle_response(receipt_from(3, sent[0].id), 21);
+        chat_client.on_tick();
+        assert!(!chat_events
+            .1
+            .try_iter()
+            .any(|event| matches!(event, ChatEvent::BroadcastResult(_))));
+
+        chat_client.handle_response(receipt_from(4, sent[1].id), 21);
+        chat_client.on_tick();
+        let result = chat_events
+            .1
+            .try_iter()
+            .find_map(|event| match event {
+                ChatEvent::BroadcastResult(result) => Some(result),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(result.delivered(), vec![3, 4]);
+    }
+
+    #[test]
+    fn broadcast_refreshes_stale_list() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+
+        chat_client.broadcast(21, "Hello".to_string());
+        let requests = util::sent_chat_requests(&neighbor.1);
+        assert_eq!(requests.len(), 1);
+        assert!(matches!(requests[0], (21, ChatRequest::ClientList)));
+
+        chat_client.handle_response(util::client_list(&[1, 3]), 21);
+        let sent = util::sent_texts(&neighbor.1);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, 3);
+
+        // The list is now fresh
+        chat_client.broadcast(21, "World".to_string());
+        assert_eq!(util::sent_texts(&neighbor.1).len(), 1);
+    }
+}
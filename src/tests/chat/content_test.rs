@@ -0,0 +1,129 @@
+#[cfg(test)]
+pub mod content_test {
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::messages::chat_messages::{
+        ChatRequest, ChatResponse, ChatResponseWrapper,
+    };
+    use rustafarian_shared::messages::commander_messages::{
+        SimControllerMessage, SimControllerResponseWrapper,
+    };
+
+    use crate::chat::{ChatEvent, ChatPayload, MessageContent};
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    #[test]
+    fn payload_of_content() {
+        assert_eq!(
+            ChatPayload::message(1, MessageContent::Text("Hello".to_string())),
+            ChatPayload::Text {
+                id: 1,
+                text: "Hello".to_string()
+            }
+        );
+        let reaction = MessageContent::Reaction {
+            message_id: 7,
+            reaction: "+1".to_string(),
+        };
+        let payload = ChatPayload::message(2, reaction.clone());
+        assert_eq!(
+            ChatPayload::decode(&payload.encode()),
+            Some(ChatPayload::Content {
+                id: 2,
+                content: reaction.clone()
+            })
+        );
+        assert_eq!(reaction.summary(), "[reaction to 7] +1");
+        let binary = MessageContent::Binary {
+            mime_type: "image/png".to_string(),
+            data: vec![0, 1, 2],
+        };
+        assert_eq!(binary.summary(), "[image/png, 3 bytes]");
+    }
+
+    #[test]
+    fn content_is_sent() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+
+        let content = MessageContent::Binary {
+            mime_type: "image/png".to_string(),
+            data: vec![137, 80, 78, 71],
+        };
+        let id = chat_client.send_chat_content(21, 3, content.clone());
+
+        let requests = util::sent_chat_requests(&neighbor.1);
+        let [(21, ChatRequest::SendMessage { to: 3, message, .. })] = requests.as_slice() else {
+            panic!("Message should be SendMessage");
+        };
+        assert_eq!(
+            ChatPayload::decode(message).map(ChatPayload::unsequenced),
+            Some(ChatPayload::Content { id, content })
+        );
+        assert_eq!(
+            chat_client.get_history().conversation(21, 3)[0].text,
+            "[image/png, 4 bytes]"
+        );
+    }
+
+    #[test]
+    fn reply_is_received() {
+        let (mut chat_client, neighbor, _controller_channel_commands, controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        let content = MessageContent::Reply {
+            reply_to: 5,
+            text: "Yes".to_string(),
+        };
+        let payload = ChatPayload::message(9, content.clone());
+        chat_client.handle_response(util::message_from(3, &payload), 21);
+
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::MessageReceived { server_id: 21, from: 3, id: Some(9), content: received }
+                if received == content
+        ));
+        assert!(matches!(
+            controller_channel_messages.1.recv().unwrap(),
+            SimControllerResponseWrapper::Message(SimControllerMessage::MessageReceived(21, 3, text))
+                if text == "[reply to 5] Yes"
+        ));
+        // The receipt
+        assert_eq!(util::sent_chat_requests(&neighbor.1).len(), 1);
+    }
+
+    #[test]
+    fn invalid_utf8_is_binary() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_response(
+            ChatResponseWrapper::Chat(ChatResponse::MessageFrom {
+                from: 3,
+                message: vec![0xff, 0xfe, 0x00],
+            }),
+            21,
+        );
+
+        let ChatEvent::MessageReceived { id, content, .. } = chat_events.1.try_recv().unwrap()
+        else {
+            panic!("Event should be MessageReceived");
+        };
+        assert_eq!(id, None);
+        assert_eq!(
+            content,
+            MessageContent::Binary {
+                mime_type: "application/octet-stream".to_string(),
+                data: vec![0xff, 0xfe, 0x00]
+            }
+        );
+        assert!(neighbor.1.try_recv().is_err());
+    }
+}
@@ -14,6 +14,7 @@ pub mod controller_test {
     };
     use wg_2024::packet::{Packet, PacketType};
 
+    use crate::chat_client::ChatLayer;
     use crate::client::Client;
     use crate::tests::util;
 
@@ -107,17 +108,19 @@ pub mod controller_test {
 
         chat_client.handle_sim_controller_packets(Ok(send_message_command));
 
-        let received_packet = neighbor.1.recv().unwrap();
-
-        let fragment = match received_packet.pack_type {
-            PacketType::MsgFragment(fragment) => fragment,
-            _ => panic!("Packet type should be MsgFragment"),
+        // The message can take more than one fragment
+        let mut assembler = Assembler::new();
+        let constructed_message = loop {
+            let received_packet = neighbor.1.recv().unwrap();
+            let fragment = match received_packet.pack_type {
+                PacketType::MsgFragment(fragment) => fragment,
+                _ => panic!("Packet type should be MsgFragment"),
+            };
+            if let Some(message) = assembler.add_fragment(fragment, received_packet.session_id) {
+                break message;
+            }
         };
 
-        let constructed_message = Assembler::new()
-            .add_fragment(fragment, received_packet.session_id)
-            .unwrap();
-
         let parsed_message = serde_json::from_str::<ChatRequestWrapper>(
             std::str::from_utf8(&constructed_message).unwrap(),
         )
@@ -259,7 +262,7 @@ pub mod controller_test {
 
         chat_client.handle_sim_controller_packets(Ok(as_request));
 
-        assert!(chat_client.senders().contains_key(&3));
+        assert!(chat_client.transport().has_neighbor(3));
 
         assert!(chat_client.topology().nodes().contains(&3));
         assert!(chat_client.topology().edges().contains_key(&1));
@@ -280,7 +283,7 @@ pub mod controller_test {
 
         chat_client.handle_sim_controller_packets(Ok(as_request));
 
-        assert!(!chat_client.senders().contains_key(&2));
+        assert!(!chat_client.transport().has_neighbor(2));
 
         assert!(chat_client.topology().nodes().contains(&2));
         assert!(chat_client.topology().edges().contains_key(&1));
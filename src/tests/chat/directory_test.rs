@@ -0,0 +1,114 @@
+#[cfg(test)]
+pub mod directory_test {
+    use std::collections::{BTreeSet, HashMap};
+
+    use crossbeam_channel::{unbounded, Receiver};
+    use rustafarian_shared::messages::chat_messages::ChatRequest;
+    use wg_2024::packet::Packet;
+
+    use crate::chat::{ChatCommand, ChatConfig, ChatEvent, Directory, ListRefresher};
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::tests::util;
+
+    /// A client registered to 21 (1-2-21) and 22 (1-2-22)
+    fn build_client(config: &ChatConfig) -> (ChatClient, Receiver<Packet>) {
+        let (mut chat_client, neighbor) =
+            util::build_chat_client(config, &[(1, 2), (2, 21), (2, 22)]);
+        for server_id in [21, 22] {
+            chat_client.handle_response(util::registered(), server_id);
+        }
+        (chat_client, neighbor)
+    }
+
+    #[test]
+    fn stale_lists_are_due() {
+        let updated = HashMap::from([(21, 500)]);
+        let mut refresher = ListRefresher::new(Some(1000));
+        assert_eq!(refresher.due(&[21, 22], &updated, 1000), vec![22]);
+        // Checked at most every second
+        assert!(refresher.due(&[21, 22], &updated, 1500).is_empty());
+        assert_eq!(refresher.due(&[21, 22], &updated, 2000), vec![21, 22]);
+
+        let mut refresher = ListRefresher::new(None);
+        assert!(refresher.due(&[21, 22], &updated, 5000).is_empty());
+
+        // The lists requested by the client count
+        refresher.set_max_age(Some(1000));
+        refresher.on_requested(22, 4500);
+        assert_eq!(refresher.due(&[21, 22], &updated, 5000), vec![21]);
+        assert!(refresher.due(&[21, 22], &updated, 5500).is_empty());
+        assert_eq!(refresher.due(&[21, 22], &updated, 6000), vec![21, 22]);
+        // Polled on every tick
+        refresher.set_max_age(Some(0));
+        assert_eq!(refresher.due(&[21, 22], &updated, 6000), vec![21, 22]);
+        assert_eq!(refresher.due(&[21, 22], &updated, 6000), vec![21, 22]);
+    }
+
+    #[test]
+    fn one_poller_for_presence_and_directory() {
+        // Nothing is polled by default
+        let mut config = ChatConfig::default();
+        assert_eq!(config.list_refresh_ms(), None);
+        config.directory_max_age_ms = Some(30_000);
+        assert_eq!(config.list_refresh_ms(), Some(30_000));
+        config.presence_poll_interval_ms = Some(5000);
+        assert_eq!(config.list_refresh_ms(), Some(5000));
+        config.directory_max_age_ms = None;
+        assert_eq!(config.list_refresh_ms(), Some(5000));
+        config.presence_poll_interval_ms = None;
+        assert_eq!(config.list_refresh_ms(), None);
+    }
+
+    #[test]
+    fn lists_are_merged() {
+        let (mut chat_client, _neighbor) = build_client(&ChatConfig::default());
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_response(util::client_list(&[1, 3, 4]), 21);
+        chat_client.handle_response(util::client_list(&[3, 5]), 22);
+        // Not registered
+        chat_client.handle_response(util::client_list(&[6]), 23);
+        let _res = chat_events.1.try_iter().count();
+
+        let directory = chat_client.get_directory();
+        assert_eq!(
+            directory.clients.keys().copied().collect::<Vec<u8>>(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(directory.servers(3), Some(&BTreeSet::from([21, 22])));
+        assert_eq!(directory.servers(5), Some(&BTreeSet::from([22])));
+        assert_eq!(directory.multi_server(), vec![3]);
+
+        chat_client.handle_chat_layer_command(ChatCommand::Directory);
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::Directory(event) if event == directory
+        ));
+        assert_eq!(
+            Directory::merge(&HashMap::new(), &[21], 1),
+            Directory::default()
+        );
+    }
+
+    #[test]
+    fn missing_lists_are_refreshed() {
+        let config = ChatConfig {
+            directory_max_age_ms: Some(60_000),
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, neighbor) = build_client(&config);
+        chat_client.handle_response(util::client_list(&[3]), 21);
+
+        chat_client.on_tick();
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor).as_slice(),
+            [(22, ChatRequest::ClientList)]
+        ));
+        // Not requested again while it's waited for
+        chat_client.on_tick();
+        assert!(util::sent_chat_requests(&neighbor).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+#[cfg(test)]
+pub mod ephemeral_test {
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+
+    use rustafarian_shared::messages::chat_messages::{ChatRequest, ChatResponseWrapper};
+
+    use crate::chat::{
+        ChatCommand, ChatConfig, ChatEvent, ChatPayload, ConversationStore, Direction,
+        EphemeralMessage, Ephemerals, HistoryEntry, MessageContent,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::{current_timestamp_ms, Client};
+    use crate::tests::util;
+
+    fn ephemeral_from(from: u8, sent_at: u128, ttl_ms: u64) -> ChatResponseWrapper {
+        let payload = ChatPayload::Sequenced {
+            session: 1,
+            seq: 0,
+            since: 0,
+            payload: Box::new(ChatPayload::Ephemeral {
+                sent_at,
+                ttl_ms,
+                payload: Box::new(ChatPayload::Text {
+                    id: 7,
+                    text: "Secret".to_string(),
+                }),
+            }),
+        };
+        util::message_from(from, &payload)
+    }
+
+    fn entry(timestamp: u128, text: &str) -> HistoryEntry {
+        HistoryEntry {
+            server_id: 21,
+            peer_id: 3,
+            direction: Direction::Received,
+            timestamp,
+            text: text.to_string(),
+            id: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn messages_expire_in_order() {
+        let mut ephemerals = Ephemerals::new();
+        ephemerals.on_sent(1, 0, 300);
+        for (id, expires_at) in [(1, 300), (2, 100), (3, 200)] {
+            ephemerals.track(EphemeralMessage {
+                id,
+                entry: entry(0, "Secret"),
+                expires_at,
+            });
+        }
+        assert_eq!(ephemerals.ttl(1), Some((0, 300)));
+        assert!(ephemerals.take_expired(50).is_empty());
+        let expired = ephemerals.take_expired(250);
+        assert_eq!(
+            expired
+                .iter()
+                .map(|message| message.id)
+                .collect::<Vec<u64>>(),
+            vec![2, 3]
+        );
+        assert_eq!(ephemerals.take_expired(300)[0].id, 1);
+        assert_eq!(ephemerals.ttl(1), None);
+        assert!(ephemerals.messages().is_empty());
+    }
+
+    #[test]
+    fn removed_from_the_saved_history() {
+        let path = std::env::temp_dir().join(format!("history_{}.jsonl", rand::random::<u64>()));
+        let mut store = ConversationStore::open(&path).unwrap();
+        store.record(entry(100, "Hello")).unwrap();
+        store.record(entry(200, "Secret")).unwrap();
+
+        assert_eq!(store.remove(&entry(200, "Secret")), Ok(true));
+        assert_eq!(store.remove(&entry(200, "Secret")), Ok(false));
+        let reopened = ConversationStore::open(&path).unwrap();
+        assert_eq!(reopened.conversation(21, 3), &[entry(100, "Hello")]);
+
+        store.remove(&entry(100, "Hello")).unwrap();
+        assert!(store.conversations().is_empty());
+        let _res = fs::remove_file(path);
+    }
+
+    #[test]
+    fn expires_after_a_restart() {
+        let path = std::env::temp_dir().join(format!("history_{}.jsonl", rand::random::<u64>()));
+        let mut store = ConversationStore::open(&path).unwrap();
+        store.record(entry(100, "Hello")).unwrap();
+        store
+            .record(HistoryEntry {
+                id: Some(7),
+                expires_at: Some(current_timestamp_ms() + 100),
+                ..entry(200, "Secret")
+            })
+            .unwrap();
+
+        let config = ChatConfig {
+            history_path: Some(path.clone()),
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, _neighbor) = util::build_chat_client(&config, &[]);
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 2);
+
+        thread::sleep(Duration::from_millis(150));
+        chat_client.on_tick();
+        assert_eq!(
+            chat_client.get_history().conversation(21, 3),
+            &[entry(100, "Hello")]
+        );
+        let reopened = ConversationStore::open(&path).unwrap();
+        assert_eq!(reopened.conversation(21, 3), &[entry(100, "Hello")]);
+        let _res = fs::remove_file(path);
+    }
+
+    #[test]
+    fn expiry_does_not_overflow() {
+        let payload = ChatPayload::Ephemeral {
+            sent_at: u128::MAX - 1,
+            ttl_ms: 10,
+            payload: Box::new(ChatPayload::Text {
+                id: 7,
+                text: "Secret".to_string(),
+            }),
+        };
+        assert_eq!(payload.into_expiring().1, Some(u128::MAX));
+    }
+
+    #[test]
+    fn received_message_expires() {
+        let (mut chat_client, neighbor, chat_events) = util::build_attached_client();
+        chat_client.handle_response(ephemeral_from(3, current_timestamp_ms(), 100), 21);
+        assert!(matches!(
+            chat_events.try_recv().unwrap(),
+            ChatEvent::MessageReceived { id: Some(7), .. }
+        ));
+        let conversation = chat_client.get_history().conversation(21, 3);
+        assert_eq!(conversation.len(), 1);
+        assert_eq!(conversation[0].id, Some(7));
+        assert!(conversation[0].expires_at.is_some());
+        // The receipt
+        assert_eq!(util::sent_chat_requests(&neighbor).len(), 1);
+
+        thread::sleep(Duration::from_millis(150));
+        chat_client.on_tick();
+        assert!(chat_client.get_history().conversation(21, 3).is_empty());
+        assert!(chat_events.try_iter().any(|event| matches!(
+            event,
+            ChatEvent::MessageExpired {
+                server_id: 21,
+                peer_id: 3,
+                id: 7
+            }
+        )));
+    }
+
+    #[test]
+    fn expired_message_is_discarded() {
+        let (mut chat_client, neighbor, chat_events) = util::build_attached_client();
+        chat_client.handle_response(ephemeral_from(3, 0, 1000), 21);
+        assert!(chat_client.get_history().conversation(21, 3).is_empty());
+        assert!(chat_events.try_recv().is_err());
+        assert!(neighbor.try_recv().is_err());
+    }
+
+    #[test]
+    fn sent_message_expires() {
+        let (mut chat_client, neighbor, chat_events) = util::build_attached_client();
+        chat_client.handle_chat_layer_command(ChatCommand::SendEphemeral {
+            server_id: 21,
+            peer_id: 3,
+            content: MessageContent::Text("Secret".to_string()),
+            ttl_ms: 0,
+        });
+        let requests = util::sent_chat_requests(&neighbor);
+        let [(21, ChatRequest::SendMessage { message, .. })] = requests.as_slice() else {
+            panic!("The message should be sent to 21");
+        };
+        let Some(ChatPayload::Ephemeral {
+            ttl_ms: 0, payload, ..
+        }) = ChatPayload::decode(message).map(ChatPayload::unsequenced)
+        else {
+            panic!("The message should be ephemeral");
+        };
+        let ChatPayload::Text { id, .. } = *payload else {
+            panic!("The payload should be a text");
+        };
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 1);
+
+        chat_client.on_tick();
+        assert!(chat_client.get_history().conversation(21, 3).is_empty());
+        assert!(chat_events.try_iter().any(|event| matches!(
+            event,
+            ChatEvent::MessageExpired { peer_id: 3, id: expired, .. } if expired == id
+        )));
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(test)]
+pub mod failover_test {
+    use crossbeam_channel::Receiver;
+    use rustafarian_shared::messages::chat_messages::{ChatRequest, ChatResponseWrapper};
+    use rustafarian_shared::messages::general_messages::{ServerType, ServerTypeResponse};
+    use wg_2024::packet::Packet;
+
+    use crate::chat::{ChatConfig, ChatEvent, DeliveryStatus, RegistrationState, ServerMonitor};
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::tests::util::{self, SentText};
+
+    /// A client registered to 21 (1-2-21), that discovered 22 (1-2-3-22)
+    fn build_client(config: &ChatConfig) -> (ChatClient, Receiver<Packet>, Receiver<ChatEvent>) {
+        let (mut chat_client, neighbor) =
+            util::build_chat_client(config, &[(1, 2), (2, 21), (2, 3), (3, 22)]);
+        let chat_events = util::attach_events(&mut chat_client);
+
+        chat_client.handle_response(
+            ChatResponseWrapper::ServerType(ServerTypeResponse::ServerType(ServerType::Chat)),
+            22,
+        );
+        chat_client.handle_response(util::registered(), 21);
+        let _res = chat_events.try_iter().count();
+        (chat_client, neighbor, chat_events)
+    }
+
+    #[test]
+    fn monitor_timeouts() {
+        let mut monitor = ServerMonitor::new(1000);
+        monitor.on_request_sent(21, 0);
+        monitor.on_request_sent(21, 500);
+        monitor.on_request_sent(22, 500);
+        assert_eq!(monitor.timed_out(1000), vec![21]);
+        assert_eq!(monitor.timed_out(1500), vec![21, 22]);
+
+        assert!(!monitor.on_response(22));
+        assert!(monitor.mark_down(21));
+        assert!(!monitor.mark_down(21));
+        assert!(monitor.timed_out(5000).is_empty());
+        assert_eq!(monitor.down(), &[21]);
+        assert!(monitor.on_response(21));
+        assert!(!monitor.is_down(21));
+    }
+
+    #[test]
+    fn unreachable_server_is_replaced() {
+        let (mut chat_client, neighbor, chat_events) = build_client(&ChatConfig::default());
+        let id = chat_client.send_chat_message(21, 3, "Hello".to_string());
+        assert_eq!(
+            util::sent_texts(&neighbor),
+            vec![SentText::new(21, 3, id, "Hello")]
+        );
+
+        chat_client.topology().remove_node(21);
+        chat_client.on_tick();
+        let events = chat_events.try_iter().collect::<Vec<ChatEvent>>();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ChatEvent::ServerDown {
+                server_id: 21,
+                alternative: Some(22)
+            }
+        )));
+        assert!(chat_client.get_registered_servers().is_empty());
+        assert!(!chat_client.get_available_clients().contains_key(&21));
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Queued)
+        );
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor).as_slice(),
+            [(22, ChatRequest::Register(1))]
+        ));
+
+        // The message follows once the recipient is in the list of the new server
+        chat_client.handle_response(util::registered(), 22);
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor).as_slice(),
+            [(22, ChatRequest::ClientList)]
+        ));
+        chat_client.handle_response(util::client_list(&[1, 3]), 22);
+        assert_eq!(
+            util::sent_texts(&neighbor),
+            vec![SentText::new(22, 3, id, "Hello")]
+        );
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Pending)
+        );
+    }
+
+    #[test]
+    fn silent_server_is_replaced() {
+        let config = ChatConfig {
+            server_timeout_ms: 0,
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, neighbor, chat_events) = build_client(&config);
+        chat_client.handle_response(util::registered(), 22);
+        chat_client.handle_response(util::client_list(&[1, 5]), 22);
+        let id = chat_client.send_chat_message(21, 5, "Hello".to_string());
+        // The messages aren't waited for, only the requests that always get a response
+        chat_client.send_client_list_req(21);
+        let _requests = util::sent_chat_requests(&neighbor);
+
+        chat_client.on_tick();
+        assert_eq!(
+            chat_client.get_registration_state(21),
+            RegistrationState::Unregistered
+        );
+        assert_eq!(chat_client.get_registered_servers(), &vec![22]);
+        assert!(matches!(
+            util::sent_chat_requests(&neighbor).as_slice(),
+            [(22, ChatRequest::ClientList)]
+        ));
+        chat_client.handle_response(util::client_list(&[1, 5]), 22);
+        assert_eq!(
+            util::sent_texts(&neighbor),
+            vec![SentText::new(22, 5, id, "Hello")]
+        );
+
+        // A late answer brings the server back
+        chat_client.handle_response(util::client_list(&[1]), 21);
+        assert!(chat_events
+            .try_iter()
+            .any(|event| matches!(event, ChatEvent::ServerUp(21))));
+    }
+
+    #[test]
+    fn unanswered_message_keeps_the_server() {
+        let config = ChatConfig {
+            server_timeout_ms: 0,
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, _neighbor, chat_events) = build_client(&config);
+        // The server drops it without an answer if 3 isn't registered
+        chat_client.send_chat_message(21, 3, "Hello".to_string());
+
+        chat_client.on_tick();
+        assert_eq!(chat_client.get_registered_servers(), &vec![21]);
+        assert!(!chat_events
+            .try_iter()
+            .any(|event| matches!(event, ChatEvent::ServerDown { .. })));
+    }
+
+    #[test]
+    fn messages_fail_without_alternative() {
+        let (mut chat_client, neighbor, chat_events) = build_client(&ChatConfig::default());
+        chat_client.topology().remove_node(22);
+        let id = chat_client.send_chat_message(21, 3, "Hello".to_string());
+        let _requests = util::sent_chat_requests(&neighbor);
+
+        chat_client.topology().remove_node(21);
+        chat_client.on_tick();
+        assert!(chat_events.try_iter().any(|event| matches!(
+            event,
+            ChatEvent::ServerDown {
+                server_id: 21,
+                alternative: None
+            }
+        )));
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Failed)
+        );
+        assert!(neighbor.try_recv().is_err());
+    }
+}
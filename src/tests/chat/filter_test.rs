@@ -0,0 +1,209 @@
+#[cfg(test)]
+pub mod filter_test {
+    use std::fs;
+
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::messages::chat_messages::ChatRequest;
+
+    use crate::chat::{
+        ChatCommand, ChatConfig, ChatEvent, ChatPayload, RateLimit, SenderFilter, SenderPolicy,
+        Verdict,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    #[test]
+    fn filter_verdicts() {
+        let mut filter = SenderFilter::new(SenderPolicy {
+            blocked: vec![5],
+            muted: vec![4],
+            rate_limit: Some(RateLimit {
+                max_messages: 2,
+                interval_ms: 1000,
+            }),
+        });
+        assert_eq!(filter.check(5, 0), Verdict::Blocked);
+        assert_eq!(filter.check(4, 0), Verdict::Mute);
+        assert_eq!(filter.check(3, 0), Verdict::Deliver);
+        assert_eq!(filter.check(3, 500), Verdict::Deliver);
+        assert_eq!(filter.check(3, 900), Verdict::RateLimited);
+        assert_eq!(filter.check(3, 1000), Verdict::Deliver);
+
+        assert!(filter.unblock(5));
+        assert!(!filter.unblock(5));
+        assert!(filter.block(3));
+        assert_eq!(filter.check(3, 5000), Verdict::Blocked);
+        assert_eq!(filter.policy().blocked, vec![3]);
+    }
+
+    #[test]
+    fn blocked_client_is_ignored() {
+        let (mut chat_client, neighbor, _controller_channel_commands, controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_chat_layer_command(ChatCommand::Block(3));
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::SenderPolicy(policy) if policy.blocked == vec![3]
+        ));
+
+        chat_client.handle_response(util::text_from(3, 1, "Hello"), 21);
+        assert!(chat_client.get_history().conversation(21, 3).is_empty());
+        assert!(chat_events.1.try_recv().is_err());
+        assert!(controller_channel_messages.1.try_recv().is_err());
+        // Not even the receipt is sent
+        assert!(neighbor.1.try_recv().is_err());
+    }
+
+    #[test]
+    fn blocked_client_gets_no_receipt() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.handle_chat_layer_command(ChatCommand::Block(3));
+
+        let sequenced = ChatPayload::Sequenced {
+            session: 7,
+            seq: 0,
+            since: 0,
+            payload: Box::new(ChatPayload::Text {
+                id: 2,
+                text: "Hello".to_string(),
+            }),
+        };
+        chat_client.handle_response(util::message_from(3, &sequenced), 21);
+        assert!(util::sent_chat_requests(&neighbor.1).is_empty());
+        assert!(chat_client.get_history().conversation(21, 3).is_empty());
+
+        // Only the accepted messages of a rate limited client get their receipt
+        chat_client.set_rate_limit(Some(RateLimit {
+            max_messages: 1,
+            interval_ms: 60_000,
+        }));
+        for id in 0..2 {
+            chat_client.handle_response(util::text_from(4, id, "Spam"), 21);
+        }
+        let receipts = util::sent_chat_requests(&neighbor.1)
+            .into_iter()
+            .filter_map(|(_, request)| match request {
+                ChatRequest::SendMessage { message, .. } => ChatPayload::decode(&message),
+                _ => None,
+            })
+            .collect::<Vec<ChatPayload>>();
+        assert_eq!(receipts, vec![ChatPayload::Receipt { id: 0 }]);
+    }
+
+    #[test]
+    fn muted_client_is_only_recorded() {
+        let (mut chat_client, neighbor, _controller_channel_commands, controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.mute_client(3);
+        let _res = chat_events.1.try_recv();
+
+        chat_client.handle_response(util::text_from(3, 1, "Hello"), 21);
+        assert_eq!(
+            chat_client.get_history().conversation(21, 3)[0].text,
+            "Hello"
+        );
+        assert!(chat_events.1.try_recv().is_err());
+        assert!(controller_channel_messages.1.try_recv().is_err());
+        // The receipt
+        assert_eq!(util::sent_chat_requests(&neighbor.1).len(), 1);
+
+        chat_client.unmute_client(3);
+        let _res = chat_events.1.try_recv();
+        chat_client.handle_response(util::text_from(3, 2, "World"), 21);
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::MessageReceived {
+                from: 3,
+                id: Some(2),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rate_limited_messages_are_dropped() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        chat_client.set_rate_limit(Some(RateLimit {
+            max_messages: 2,
+            interval_ms: 60_000,
+        }));
+
+        for id in 0..4 {
+            chat_client.handle_response(util::text_from(3, id, "Spam"), 21);
+        }
+        chat_client.handle_response(util::text_from(4, 0, "Hello"), 21);
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 2);
+        assert_eq!(chat_client.get_history().conversation(21, 4).len(), 1);
+    }
+
+    #[test]
+    fn duplicates_dont_count_against_the_rate_limit() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.set_rate_limit(Some(RateLimit {
+            max_messages: 2,
+            interval_ms: 60_000,
+        }));
+        let sequenced = |seq: u64| ChatPayload::Sequenced {
+            session: 7,
+            seq,
+            since: 0,
+            payload: Box::new(ChatPayload::Text {
+                id: seq,
+                text: "Hello".to_string(),
+            }),
+        };
+
+        for seq in [0, 0, 1, 2] {
+            chat_client.handle_response(util::message_from(3, &sequenced(seq)), 21);
+        }
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 2);
+        // The duplicate gets its receipt again, the message over the limit none
+        let receipts = util::sent_payloads(&neighbor.1)
+            .into_iter()
+            .map(|(_, payload)| payload)
+            .collect::<Vec<ChatPayload>>();
+        assert_eq!(
+            receipts,
+            vec![
+                ChatPayload::Receipt { id: 0 },
+                ChatPayload::Receipt { id: 0 },
+                ChatPayload::Receipt { id: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn policy_is_saved_in_the_config() {
+        let path = std::env::temp_dir().join(format!("config_{}.json", rand::random::<u64>()));
+        let config = ChatConfig::load(&path).unwrap();
+        assert_eq!(config.sender_policy, SenderPolicy::default());
+
+        let (mut chat_client, _neighbor) = util::build_chat_client(&config, &[]);
+        chat_client.block_client(5);
+        chat_client.mute_client(4);
+
+        let reloaded = ChatConfig::load(&path).unwrap();
+        assert_eq!(reloaded.sender_policy.blocked, vec![5]);
+        assert_eq!(reloaded.sender_policy.muted, vec![4]);
+        assert_eq!(reloaded.reorder_window, config.reorder_window);
+
+        let (chat_client, _neighbor) = util::build_chat_client(&reloaded, &[]);
+        assert_eq!(chat_client.get_sender_policy(), &reloaded.sender_policy);
+        let _res = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(test)]
+pub mod groups_test {
+    use crossbeam_channel::{unbounded, Receiver};
+    use wg_2024::packet::Packet;
+
+    use crate::chat::{ChatCommand, ChatEvent, ChatPayload, Group, GroupMessage};
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::tests::util;
+
+    /// A client registered to 21, where 3 and 4 are also registered
+    fn build_registered_client() -> (ChatClient, Receiver<Packet>) {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.handle_response(util::registered(), 21);
+        chat_client.handle_response(util::client_list(&[1, 3, 4]), 21);
+        (chat_client, neighbor.1)
+    }
+
+    fn group_text(group_id: u64, seq: u64, text: &str) -> ChatPayload {
+        ChatPayload::GroupText {
+            group_id,
+            session: 0,
+            seq,
+            since: 0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn group_thread() {
+        let mut group = Group::new(7, "friends".to_string(), vec![4, 1, 3, 4]);
+        assert_eq!(group.members, vec![1, 3, 4]);
+        assert_eq!(group.take_seq(), 0);
+        assert_eq!(group.take_seq(), 1);
+
+        let message = GroupMessage {
+            from: 3,
+            seq: 0,
+            timestamp: 0,
+            text: "Hello".to_string(),
+        };
+        assert!(group.insert(message.clone()));
+        assert!(!group.insert(message));
+        assert_eq!(group.messages().len(), 1);
+
+        // 5 only gets the messages sent after it joined
+        group.set_members(vec![1, 3, 4, 5]);
+        assert_eq!(group.since(3), 0);
+        assert_eq!(group.since(5), 2);
+        group.set_members(vec![1, 3]);
+        assert_eq!(group.since(5), 2);
+        group.take_seq();
+        group.set_members(vec![1, 3, 5]);
+        assert_eq!(group.since(5), 3);
+    }
+
+    #[test]
+    fn messages_are_sent_to_every_member() {
+        let (mut chat_client, neighbor) = build_registered_client();
+
+        let group_id = chat_client.create_group("friends".to_string(), vec![3, 4]);
+        let info = ChatPayload::GroupInfo {
+            group_id,
+            name: "friends".to_string(),
+            members: vec![1, 3, 4],
+        };
+        assert_eq!(
+            util::sent_payloads(&neighbor),
+            vec![(3, info.clone()), (4, info)]
+        );
+
+        assert_eq!(
+            chat_client.send_group_message(group_id, "Hello".to_string()),
+            Ok(0)
+        );
+        assert_eq!(
+            chat_client.send_group_message(group_id, "World".to_string()),
+            Ok(1)
+        );
+        let sent = util::sent_payloads(&neighbor);
+        assert_eq!(sent.len(), 4);
+        assert!(matches!(
+            &sent[2],
+            (3, ChatPayload::GroupText {
+                group_id: sent_group_id,
+                seq: 1,
+                since: 0,
+                text,
+                ..
+            }) if *sent_group_id == group_id && text == "World"
+        ));
+        let group = chat_client.get_groups().get(group_id).unwrap();
+        assert_eq!(group.messages().len(), 2);
+
+        assert!(chat_client
+            .send_group_message(42, "Hello".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn received_messages_go_to_their_group() {
+        let (mut chat_client, _neighbor) = build_registered_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        let info = ChatPayload::GroupInfo {
+            group_id: 7,
+            name: "friends".to_string(),
+            members: vec![1, 3, 4],
+        };
+        chat_client.handle_response(util::message_from(3, &info), 21);
+        let text = group_text(7, 0, "Hello");
+        chat_client.handle_response(util::message_from(4, &text), 21);
+        // Sent through another server, or twice
+        chat_client.handle_response(util::message_from(4, &text), 21);
+
+        let group = chat_client.get_groups().get(7).unwrap();
+        assert_eq!(group.name, "friends");
+        assert_eq!(group.messages().len(), 1);
+        assert_eq!(group.messages()[0].from, 4);
+        // Not a one-to-one conversation
+        assert!(chat_client.get_history().conversation(21, 4).is_empty());
+
+        // 1 is removed from the group
+        let info = ChatPayload::GroupInfo {
+            group_id: 7,
+            name: "friends".to_string(),
+            members: vec![3, 4],
+        };
+        chat_client.handle_response(util::message_from(3, &info), 21);
+        assert!(chat_client.get_groups().get(7).is_none());
+
+        let events = chat_events.1.try_iter().collect::<Vec<ChatEvent>>();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ChatEvent::GroupChanged { group_id: 7, .. },
+                ChatEvent::GroupMessage { group_id: 7, message },
+                ChatEvent::GroupLeft(7),
+            ] if message.text == "Hello"
+        ));
+    }
+
+    #[test]
+    fn group_commands() {
+        let (mut chat_client, neighbor) = build_registered_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_commands
+            .0
+            .send(ChatCommand::CreateGroup {
+                name: "friends".to_string(),
+                members: vec![3],
+            })
+            .unwrap();
+        chat_commands
+            .0
+            .send(ChatCommand::SendGroupMessage {
+                group_id: 42,
+                text: "Hello".to_string(),
+            })
+            .unwrap();
+        chat_client.on_tick();
+
+        let group_id = match chat_events.1.try_recv().unwrap() {
+            ChatEvent::GroupChanged {
+                group_id, members, ..
+            } => {
+                assert_eq!(members, vec![1, 3]);
+                group_id
+            }
+            event => panic!("Unexpected event {event:?}"),
+        };
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::CommandFailed(_)
+        ));
+
+        chat_client.handle_chat_layer_command(ChatCommand::AddGroupMember {
+            group_id,
+            member: 4,
+        });
+        chat_client.handle_chat_layer_command(ChatCommand::RemoveGroupMember {
+            group_id,
+            member: 1,
+        });
+        assert!(chat_client.get_groups().get(group_id).is_none());
+        // The creation, the addition (to 3 and 4) and the leave (to 3 and 4)
+        assert_eq!(util::sent_payloads(&neighbor).len(), 5);
+    }
+
+    #[test]
+    fn unreachable_members_are_reported() {
+        let (mut chat_client, neighbor) = build_registered_client();
+        let chat_events = util::attach_events(&mut chat_client);
+
+        // 5 shares no server with the client
+        let group_id = chat_client.create_group("friends".to_string(), vec![3, 5]);
+        assert_eq!(
+            chat_client.send_group_message(group_id, "Hello".to_string()),
+            Ok(0)
+        );
+
+        assert_eq!(util::sent_payloads(&neighbor).len(), 2);
+        let unreachable = chat_events
+            .try_iter()
+            .filter_map(|event| match event {
+                ChatEvent::GroupMembersUnreachable {
+                    group_id: id,
+                    members,
+                } if id == group_id => Some(members),
+                _ => None,
+            })
+            .collect::<Vec<Vec<u8>>>();
+        assert_eq!(unreachable, vec![vec![5], vec![5]]);
+    }
+
+    #[test]
+    fn messages_are_ordered_by_sender() {
+        let (mut chat_client, _neighbor) = build_registered_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        let info = ChatPayload::GroupInfo {
+            group_id: 7,
+            name: "friends".to_string(),
+            members: vec![1, 3, 4],
+        };
+        chat_client.handle_response(util::message_from(3, &info), 21);
+
+        chat_client.handle_response(util::message_from(4, &group_text(7, 1, "World")), 21);
+        chat_client.handle_response(util::message_from(3, &group_text(7, 0, "Hi")), 21);
+        chat_client.handle_response(util::message_from(4, &group_text(7, 0, "Hello")), 21);
+        let thread = chat_client.get_groups().get(7).unwrap().messages();
+        assert_eq!(
+            thread
+                .iter()
+                .map(|message| (message.from, message.text.as_str()))
+                .collect::<Vec<(u8, &str)>>(),
+            vec![(3, "Hi"), (4, "Hello"), (4, "World")]
+        );
+
+        // Not a member, and an unknown group
+        chat_client.handle_response(util::message_from(5, &group_text(7, 0, "Spam")), 21);
+        chat_client.handle_response(util::message_from(3, &group_text(8, 0, "Spam")), 21);
+        assert_eq!(chat_client.get_groups().get(7).unwrap().messages().len(), 3);
+        assert!(chat_client.get_groups().get(8).is_none());
+
+        let texts = chat_events
+            .1
+            .try_iter()
+            .filter_map(|event| match event {
+                ChatEvent::GroupMessage { message, .. } => Some(message.text),
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(texts, vec!["Hi", "Hello", "World"]);
+    }
+
+    #[test]
+    fn missing_group_messages_are_skipped() {
+        let (mut chat_client, _neighbor) = build_registered_client();
+        let info = ChatPayload::GroupInfo {
+            group_id: 7,
+            name: "friends".to_string(),
+            members: vec![1, 3],
+        };
+        chat_client.handle_response(util::message_from(3, &info), 21);
+
+        // 1 joined when 3 was at message 2
+        let text = ChatPayload::GroupText {
+            group_id: 7,
+            session: 0,
+            seq: 2,
+            since: 2,
+            text: "Hello".to_string(),
+        };
+        chat_client.handle_response(util::message_from(3, &text), 21);
+        assert_eq!(chat_client.get_groups().get(7).unwrap().messages().len(), 1);
+
+        // 3 and 5 to 20 never arrive, they are given up once 21 is too far after them
+        chat_client.handle_response(util::message_from(3, &group_text(7, 4, "World")), 21);
+        assert_eq!(chat_client.get_groups().get(7).unwrap().messages().len(), 1);
+        chat_client.handle_response(util::message_from(3, &group_text(7, 21, "Again")), 21);
+        let thread = chat_client.get_groups().get(7).unwrap().messages();
+        assert_eq!(
+            thread
+                .iter()
+                .map(|message| message.seq)
+                .collect::<Vec<u64>>(),
+            vec![2, 4, 21]
+        );
+    }
+}
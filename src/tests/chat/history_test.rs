@@ -0,0 +1,138 @@
+#[cfg(test)]
+pub mod history_test {
+    use std::fs;
+
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::messages::chat_messages::{ChatResponse, ChatResponseWrapper};
+
+    use crate::chat::{
+        ChatCommand, ChatConfig, ChatEvent, ConversationStore, Direction, HistoryEntry,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn entry(peer_id: u8, text: &str) -> HistoryEntry {
+        HistoryEntry {
+            server_id: 21,
+            peer_id,
+            direction: Direction::Sent,
+            timestamp: 0,
+            text: text.to_string(),
+            id: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn history_pages() {
+        let mut store = ConversationStore::new();
+        for i in 0..5 {
+            store.record(entry(3, &i.to_string())).unwrap();
+        }
+        store.record(entry(4, "other")).unwrap();
+
+        let texts = |page: usize| {
+            store
+                .page(21, 3, page, 2)
+                .into_iter()
+                .map(|entry| entry.text)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(texts(0), vec!["3", "4"]);
+        assert_eq!(texts(1), vec!["1", "2"]);
+        assert_eq!(texts(2), vec!["0"]);
+        assert!(texts(3).is_empty());
+        assert_eq!(store.conversations(), vec![(21, 3), (21, 4)]);
+    }
+
+    #[test]
+    fn history_is_saved_and_reloaded() {
+        let path = std::env::temp_dir().join(format!("history_{}.jsonl", rand::random::<u64>()));
+        let mut store = ConversationStore::open(&path).unwrap();
+        store.record(entry(3, "Hello")).unwrap();
+        store.record(entry(3, "World")).unwrap();
+
+        let reloaded = ConversationStore::open(&path).unwrap();
+        assert_eq!(reloaded.conversation(21, 3), store.conversation(21, 3));
+        let _res = fs::remove_file(path);
+    }
+
+    #[test]
+    fn client_records_messages() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+
+        chat_client.send_chat_message(21, 3, "Hello".to_string());
+        let response = ChatResponseWrapper::Chat(ChatResponse::MessageFrom {
+            from: 3,
+            message: "Hi".as_bytes().to_vec(),
+        });
+        chat_client.handle_response(response, 21);
+
+        let conversation = chat_client.get_history().conversation(21, 3);
+        assert_eq!(conversation.len(), 2);
+        assert_eq!(conversation[0].direction, Direction::Sent);
+        assert_eq!(conversation[0].text, "Hello");
+        assert_eq!(conversation[1].direction, Direction::Received);
+        assert_eq!(conversation[1].text, "Hi");
+    }
+
+    #[test]
+    fn client_reloads_history_on_construction() {
+        let path = std::env::temp_dir().join(format!("history_{}.jsonl", rand::random::<u64>()));
+        let mut store = ConversationStore::open(&path).unwrap();
+        store.record(entry(3, "Hello")).unwrap();
+
+        let config = ChatConfig {
+            history_path: Some(path.clone()),
+            ..ChatConfig::default()
+        };
+        let (chat_client, _neighbor) = util::build_chat_client(&config, &[]);
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 1);
+        let _res = fs::remove_file(path);
+    }
+
+    #[test]
+    fn history_command() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.send_chat_message(21, 3, "Hello".to_string());
+        chat_commands
+            .0
+            .send(ChatCommand::History {
+                server_id: 21,
+                peer_id: 3,
+                page: 0,
+                page_size: 10,
+            })
+            .unwrap();
+        chat_commands.0.send(ChatCommand::Conversations).unwrap();
+        chat_client.on_tick();
+
+        match chat_events.1.try_recv().unwrap() {
+            ChatEvent::History { entries, page, .. } => {
+                assert_eq!(page, 0);
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].text, "Hello");
+            }
+            event => panic!("Unexpected event {event:?}"),
+        }
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::Conversations(conversations) if conversations == vec![(21, 3)]
+        ));
+    }
+}
@@ -4,6 +4,7 @@ pub mod client_list_test {
     use rustafarian_shared::messages::chat_messages::{ChatResponse, ChatResponseWrapper};
     use wg_2024::packet::{Packet, PacketType};
 
+    use crate::chat_client::ChatLayer;
     use crate::client::Client;
     use crate::tests::util;
 
@@ -1,12 +1,32 @@
 mod ack_test;
+mod activity_test;
+mod auto_join_test;
+mod behavior_test;
+mod broadcast_test;
+mod content_test;
 mod controller_test;
+mod directory_test;
+mod ephemeral_test;
 mod error_tests;
+mod failover_test;
+mod filter_test;
 mod flood_req_test;
 mod flooding_test;
+mod groups_test;
+mod history_test;
 mod list_test;
 mod nack_test;
+mod ordering_test;
+mod outbox_test;
+mod presence_test;
+mod receipts_test;
 mod register_test;
+mod registration_test;
+mod search_test;
 mod send_message_test;
+mod send_to_test;
 mod server_type_test;
 mod test_channels;
 mod test_running;
+mod transfer_test;
+mod unread_test;
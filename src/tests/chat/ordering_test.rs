@@ -0,0 +1,236 @@
+#[cfg(test)]
+pub mod ordering_test {
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::messages::chat_messages::ChatResponseWrapper;
+
+    use crate::chat::{
+        ChatConfig, ChatEvent, ChatPayload, MessageContent, Ordered, ReorderBuffer, Sequencer,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn sequenced(session: u64, seq: u64, since: u64, text: &str) -> ChatResponseWrapper {
+        let payload = ChatPayload::Sequenced {
+            session,
+            seq,
+            since,
+            payload: Box::new(ChatPayload::Text {
+                id: seq + 100,
+                text: text.to_string(),
+            }),
+        };
+        util::message_from(3, &payload)
+    }
+
+    fn received_texts(events: &[ChatEvent]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ChatEvent::MessageReceived {
+                    content: MessageContent::Text(text),
+                    ..
+                } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sequence_numbers() {
+        let mut sequencer = Sequencer::new();
+        assert_eq!(sequencer.seq_for(3, 10), 0);
+        assert_eq!(sequencer.seq_for(3, 11), 1);
+        assert_eq!(sequencer.seq_for(4, 12), 0);
+        // Sent again
+        assert_eq!(sequencer.seq_for(3, 10), 0);
+        assert_eq!(sequencer.seq_for(3, 13), 2);
+
+        // The recipient doesn't wait for the messages that won't be sent again
+        assert_eq!(sequencer.since(3), 0);
+        sequencer.forget(11);
+        assert_eq!(sequencer.since(3), 0);
+        sequencer.forget(10);
+        assert_eq!(sequencer.since(3), 2);
+        sequencer.forget(13);
+        assert_eq!(sequencer.since(3), 3);
+        assert_eq!(sequencer.since(5), 0);
+    }
+
+    #[test]
+    fn numbering_starts_from_since() {
+        let mut buffer = ReorderBuffer::new(3, 1000);
+        // The receiver restarted, the sender is already at 5
+        assert_eq!(
+            buffer.receive(3, 1, 5, 5, 0, "f"),
+            vec![Ordered::Deliver {
+                from: 3,
+                message: "f"
+            }]
+        );
+        assert!(buffer.receive(3, 1, 7, 5, 0, "h").is_empty());
+        // The sender gave up 6
+        assert_eq!(
+            buffer.receive(3, 1, 8, 7, 0, "i"),
+            vec![
+                Ordered::Deliver {
+                    from: 3,
+                    message: "h"
+                },
+                Ordered::Deliver {
+                    from: 3,
+                    message: "i"
+                }
+            ]
+        );
+        assert!(buffer.check_timeouts(5000).is_empty());
+    }
+
+    #[test]
+    fn reorder_and_duplicates() {
+        let mut buffer = ReorderBuffer::new(3, 1000);
+        assert!(buffer.receive(3, 1, 1, 0, 0, "b").is_empty());
+        assert_eq!(
+            buffer.receive(3, 1, 0, 0, 0, "a"),
+            vec![
+                Ordered::Deliver {
+                    from: 3,
+                    message: "a"
+                },
+                Ordered::Deliver {
+                    from: 3,
+                    message: "b"
+                }
+            ]
+        );
+        assert_eq!(
+            buffer.receive(3, 1, 1, 0, 0, "b"),
+            vec![Ordered::Duplicate {
+                from: 3,
+                message: "b"
+            }]
+        );
+        // Another sender has its own numbering
+        assert_eq!(buffer.receive(4, 1, 0, 0, 0, "x").len(), 1);
+        // The sender restarted
+        assert_eq!(
+            buffer.receive(3, 2, 0, 0, 0, "c"),
+            vec![Ordered::Deliver {
+                from: 3,
+                message: "c"
+            }]
+        );
+    }
+
+    #[test]
+    fn gaps_are_skipped() {
+        let mut buffer = ReorderBuffer::new(2, 1000);
+        assert_eq!(buffer.receive(3, 1, 0, 0, 0, 0).len(), 1);
+        assert!(buffer.receive(3, 1, 2, 0, 0, 2).is_empty());
+        // 3 is too far after the missing 1
+        assert_eq!(
+            buffer.receive(3, 1, 3, 0, 0, 3),
+            vec![
+                Ordered::Gap {
+                    from: 3,
+                    first: 1,
+                    last: 1
+                },
+                Ordered::Deliver {
+                    from: 3,
+                    message: 2
+                },
+                Ordered::Deliver {
+                    from: 3,
+                    message: 3
+                }
+            ]
+        );
+
+        assert!(buffer.receive(3, 1, 6, 0, 100, 6).is_empty());
+        assert!(buffer.check_timeouts(500).is_empty());
+        assert_eq!(
+            buffer.check_timeouts(1100),
+            vec![
+                Ordered::Gap {
+                    from: 3,
+                    first: 4,
+                    last: 5
+                },
+                Ordered::Deliver {
+                    from: 3,
+                    message: 6
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn client_delivers_in_order() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_response(sequenced(7, 1, 0, "World"), 21);
+        chat_client.handle_response(sequenced(7, 0, 0, "Hello"), 21);
+        chat_client.handle_response(sequenced(7, 0, 0, "Hello"), 21);
+
+        let events = chat_events.1.try_iter().collect::<Vec<ChatEvent>>();
+        assert_eq!(received_texts(&events), vec!["Hello", "World"]);
+        // Every copy gets a receipt, in case the previous one was lost
+        assert_eq!(util::sent_chat_requests(&neighbor.1).len(), 3);
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 2);
+    }
+
+    #[test]
+    fn client_joins_a_running_session() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        // The messages before 5 were delivered before this client restarted
+        chat_client.handle_response(sequenced(7, 5, 5, "Hello"), 21);
+        chat_client.handle_response(sequenced(7, 6, 5, "World"), 21);
+        chat_client.on_tick();
+
+        let events = chat_events.1.try_iter().collect::<Vec<ChatEvent>>();
+        assert_eq!(received_texts(&events), vec!["Hello", "World"]);
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, ChatEvent::MessagesMissing { .. })));
+    }
+
+    #[test]
+    fn client_reports_gaps() {
+        let config = ChatConfig {
+            reorder_timeout_ms: 0,
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, _neighbor) = util::build_chat_client(&config, util::ONE_SERVER);
+        let chat_events = util::attach_events(&mut chat_client);
+
+        chat_client.handle_response(sequenced(7, 2, 0, "Hello"), 21);
+        chat_client.on_tick();
+
+        let events = chat_events.try_iter().collect::<Vec<ChatEvent>>();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ChatEvent::MessagesMissing {
+                    peer_id: 3,
+                    first: 0,
+                    last: 1
+                },
+                ChatEvent::MessageReceived { .. }
+            ]
+        ));
+    }
+}
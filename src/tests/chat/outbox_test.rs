@@ -0,0 +1,130 @@
+#[cfg(test)]
+pub mod outbox_test {
+    use crossbeam_channel::{unbounded, Receiver};
+    use rustafarian_shared::messages::chat_messages::ChatRequest;
+    use wg_2024::packet::Packet;
+
+    use crate::chat::{ChatConfig, ChatEvent, ChatPayload, DeliveryStatus, Outbox, QueuedMessage};
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn queued(id: u64, peer_id: u8, queued_at: u128) -> QueuedMessage {
+        QueuedMessage {
+            id,
+            server_id: 21,
+            peer_id,
+            text: "Hello".to_string(),
+            queued_at,
+        }
+    }
+
+    fn sent_requests(neighbor: &Receiver<Packet>) -> Vec<ChatRequest> {
+        util::sent_chat_requests(neighbor)
+            .into_iter()
+            .map(|(_, request)| request)
+            .collect()
+    }
+
+    #[test]
+    fn outbox_delivery_and_expiry() {
+        let mut outbox = Outbox::new(Some(100));
+        outbox.push(queued(1, 3, 0));
+        outbox.push(queued(2, 4, 50));
+        outbox.push(queued(3, 3, 50));
+
+        assert!(outbox.take_deliverable(22, &[3]).is_empty());
+        let delivered = outbox.take_deliverable(21, &[3]);
+        assert_eq!(
+            delivered
+                .iter()
+                .map(|message| message.id)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert!(outbox.expire(100).is_empty());
+        assert_eq!(outbox.expire(150), vec![queued(2, 4, 50)]);
+        assert!(outbox.is_empty());
+
+        // Without expiry the messages are kept
+        let mut outbox = Outbox::new(None);
+        outbox.push(queued(1, 3, 0));
+        assert!(outbox.expire(u128::MAX).is_empty());
+    }
+
+    #[test]
+    fn queued_until_listed() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.handle_response(util::client_list(&[4]), 21);
+
+        let id = chat_client.send_chat_message(21, 3, "Hello".to_string());
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Queued)
+        );
+        // Only the client list is requested again
+        assert!(matches!(
+            sent_requests(&neighbor.1).as_slice(),
+            [ChatRequest::ClientList]
+        ));
+
+        chat_client.handle_response(util::client_list(&[3, 4]), 21);
+        let requests = sent_requests(&neighbor.1);
+        let [ChatRequest::SendMessage { from, to, message }] = requests.as_slice() else {
+            panic!("The queued message should be sent");
+        };
+        assert_eq!((*from, *to), (1, 3));
+        assert_eq!(
+            ChatPayload::decode(message).map(ChatPayload::unsequenced),
+            Some(ChatPayload::Text {
+                id,
+                text: "Hello".to_string()
+            })
+        );
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Pending)
+        );
+        // Recorded once, when it was written
+        assert_eq!(chat_client.get_history().conversation(21, 3).len(), 1);
+
+        let statuses = chat_events
+            .1
+            .try_iter()
+            .filter_map(|event| match event {
+                ChatEvent::MessageStatus { status, .. } => Some(status),
+                _ => None,
+            })
+            .collect::<Vec<DeliveryStatus>>();
+        assert_eq!(
+            statuses,
+            vec![DeliveryStatus::Queued, DeliveryStatus::Pending]
+        );
+    }
+
+    #[test]
+    fn queued_messages_expire() {
+        let config = ChatConfig {
+            outbox_expiry_ms: Some(0),
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, neighbor) = util::build_chat_client(&config, util::ONE_SERVER);
+        chat_client.handle_response(util::client_list(&[4]), 21);
+
+        let id = chat_client.send_chat_message(21, 3, "Hello".to_string());
+        chat_client.on_tick();
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Expired)
+        );
+
+        // The client registering later doesn't get it
+        let _requests = sent_requests(&neighbor);
+        chat_client.handle_response(util::client_list(&[3, 4]), 21);
+        assert!(sent_requests(&neighbor).is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+pub mod presence_test {
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::assembler::assembler::Assembler;
+    use rustafarian_shared::messages::chat_messages::{
+        ChatRequest, ChatRequestWrapper, ChatResponse, ChatResponseWrapper,
+    };
+    use wg_2024::packet::PacketType;
+
+    use crate::chat::{diff_client_lists, ChatCommand, ChatEvent, PresenceChange};
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    #[test]
+    fn client_list_diff() {
+        assert_eq!(
+            diff_client_lists(&[3, 4, 5], &[6, 5, 3]),
+            vec![(4, PresenceChange::Left), (6, PresenceChange::Joined)]
+        );
+        assert!(diff_client_lists(&[3, 4], &[4, 3]).is_empty());
+    }
+
+    #[test]
+    fn presence_events_and_query() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_response(util::client_list(&[3, 4]), 21);
+        chat_client.handle_response(util::client_list(&[3]), 22);
+        chat_client.handle_response(util::client_list(&[3, 5]), 21);
+
+        let changes = chat_events
+            .1
+            .try_iter()
+            .map(|event| match event {
+                ChatEvent::PresenceChanged {
+                    server_id,
+                    client_id,
+                    change,
+                } => (server_id, client_id, change),
+                event => panic!("Unexpected event {event:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (21, 3, PresenceChange::Joined),
+                (21, 4, PresenceChange::Joined),
+                (22, 3, PresenceChange::Joined),
+                (21, 4, PresenceChange::Left),
+                (21, 5, PresenceChange::Joined),
+            ]
+        );
+
+        assert_eq!(chat_client.get_presence(3), vec![21, 22]);
+        assert!(chat_client.is_online(5));
+        assert!(!chat_client.is_online(4));
+
+        chat_commands.0.send(ChatCommand::Presence(3)).unwrap();
+        chat_client.on_tick();
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::Presence { client_id: 3, servers } if servers == vec![21, 22]
+        ));
+    }
+
+    #[test]
+    fn polls_registered_servers() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+        chat_client.handle_response(
+            ChatResponseWrapper::Chat(ChatResponse::ClientRegistered),
+            21,
+        );
+        // Nothing is polled by default
+        chat_client.on_tick();
+        assert!(neighbor.1.try_recv().is_err());
+
+        chat_client.handle_chat_layer_command(ChatCommand::SetPresencePolling(Some(0)));
+        chat_client.on_tick();
+
+        let packet = neighbor.1.try_recv().unwrap();
+        let PacketType::MsgFragment(fragment) = packet.pack_type else {
+            panic!("Packet type should be MsgFragment");
+        };
+        let request = Assembler::new()
+            .add_fragment(fragment, packet.session_id)
+            .unwrap();
+        assert!(matches!(
+            serde_json::from_slice::<ChatRequestWrapper>(&request),
+            Ok(ChatRequestWrapper::Chat(ChatRequest::ClientList))
+        ));
+        assert_eq!(packet.routing_header.hops.last(), Some(&21));
+
+        // Polling stops
+        chat_client.handle_chat_layer_command(ChatCommand::SetPresencePolling(None));
+        chat_client.on_tick();
+        assert!(neighbor.1.try_recv().is_err());
+    }
+}
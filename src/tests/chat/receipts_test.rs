@@ -0,0 +1,175 @@
+#[cfg(test)]
+pub mod receipts_test {
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::assembler::assembler::Assembler;
+    use rustafarian_shared::messages::chat_messages::{
+        ChatRequest, ChatRequestWrapper, ChatResponse, ChatResponseWrapper,
+    };
+    use rustafarian_shared::messages::commander_messages::{
+        SimControllerMessage, SimControllerResponseWrapper,
+    };
+    use wg_2024::packet::PacketType;
+
+    use crate::chat::receipts::FINISHED_RETAINED;
+    use crate::chat::{ChatEvent, ChatPayload, DeliveryStatus, ReceiptTracker};
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    #[test]
+    fn tracker_transitions() {
+        let mut tracker = ReceiptTracker::new();
+        tracker.on_message_sent(1, 21, 3);
+        tracker.on_untracked_sent(21);
+        tracker.on_message_sent(2, 21, 3);
+        tracker.on_message_sent(3, 22, 3);
+        assert_eq!(tracker.pending_through(21), vec![1, 2]);
+
+        let accepted = tracker.on_accepted(21).unwrap();
+        assert_eq!(
+            (accepted.id, accepted.status),
+            (1, DeliveryStatus::Accepted)
+        );
+        // The second MessageSent is for the receipt
+        assert!(tracker.on_accepted(21).is_none());
+        assert_eq!(tracker.get(2).unwrap().status, DeliveryStatus::Pending);
+        // Still waiting for its receipt
+        assert_eq!(tracker.pending_through(21), vec![1, 2]);
+
+        // A receipt from another peer is ignored
+        assert!(tracker.on_delivered(4, 2).is_none());
+        let delivered = tracker.on_delivered(3, 2).unwrap();
+        assert_eq!(delivered.status, DeliveryStatus::Delivered);
+        assert!(tracker.on_delivered(3, 2).is_none());
+        // The late MessageSent doesn't go back to accepted
+        assert!(tracker.on_accepted(21).is_none());
+        assert_eq!(tracker.get(2).unwrap().status, DeliveryStatus::Delivered);
+        assert_eq!(tracker.pending_through(21), vec![1]);
+
+        // The MessageSent of another server doesn't count
+        tracker.forget_server(22);
+        assert!(tracker.on_accepted(22).is_none());
+        assert_eq!(tracker.get(3).unwrap().status, DeliveryStatus::Pending);
+    }
+
+    #[test]
+    fn finished_messages_are_forgotten() {
+        let mut tracker = ReceiptTracker::new();
+        let count = u64::try_from(FINISHED_RETAINED).unwrap() + 1;
+        tracker.on_message_sent(0, 21, 3);
+        for id in 1..=count {
+            tracker.on_message_sent(id, 21, 3);
+            tracker.on_delivered(3, id);
+        }
+
+        // Only the oldest finished message is forgotten, the pending one is kept
+        assert!(tracker.get(1).is_none());
+        assert!(tracker.get(2).is_some());
+        assert_eq!(tracker.get(0).unwrap().status, DeliveryStatus::Pending);
+    }
+
+    #[test]
+    fn sender_reports_each_transition() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        let id = chat_client.send_chat_message(21, 3, "Hello".to_string());
+        chat_client.handle_response(ChatResponseWrapper::Chat(ChatResponse::MessageSent), 21);
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Accepted)
+        );
+        chat_client.handle_response(util::message_from(3, &ChatPayload::Receipt { id }), 21);
+
+        let statuses = chat_events
+            .1
+            .try_iter()
+            .map(|event| match event {
+                ChatEvent::MessageStatus {
+                    id: event_id,
+                    server_id,
+                    peer_id,
+                    status,
+                } => {
+                    assert_eq!((event_id, server_id, peer_id), (id, 21, 3));
+                    status
+                }
+                event => panic!("Unexpected event {event:?}"),
+            })
+            .collect::<Vec<DeliveryStatus>>();
+        assert_eq!(
+            statuses,
+            vec![
+                DeliveryStatus::Pending,
+                DeliveryStatus::Accepted,
+                DeliveryStatus::Delivered
+            ]
+        );
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Delivered)
+        );
+    }
+
+    #[test]
+    fn recipient_sends_receipt() {
+        let (mut chat_client, neighbor, _controller_channel_commands, controller_channel_messages) =
+            util::build_client();
+
+        let payload = ChatPayload::Text {
+            id: 42,
+            text: "Hello".to_string(),
+        };
+        chat_client.handle_response(util::message_from(3, &payload), 21);
+
+        // The controller gets the text, not the payload
+        let received = controller_channel_messages.1.recv().unwrap();
+        assert!(matches!(
+            received,
+            SimControllerResponseWrapper::Message(SimControllerMessage::MessageReceived(21, 3, text)) if text == "Hello"
+        ));
+
+        let received_packet = neighbor.1.recv().unwrap();
+        let PacketType::MsgFragment(fragment) = received_packet.pack_type else {
+            panic!("Packet type should be MsgFragment");
+        };
+        let request = Assembler::new()
+            .add_fragment(fragment, received_packet.session_id)
+            .unwrap();
+        let request = serde_json::from_slice::<ChatRequestWrapper>(&request).unwrap();
+        let ChatRequestWrapper::Chat(ChatRequest::SendMessage { from, to, message }) = request
+        else {
+            panic!("Message should be SendMessage");
+        };
+        assert_eq!((from, to), (1, 3));
+        assert_eq!(
+            ChatPayload::decode(&message),
+            Some(ChatPayload::Receipt { id: 42 })
+        );
+    }
+
+    #[test]
+    fn plain_text_has_no_receipt() {
+        let (mut chat_client, neighbor, _controller_channel_commands, controller_channel_messages) =
+            util::build_client();
+
+        let message = ChatResponseWrapper::Chat(ChatResponse::MessageFrom {
+            from: 3,
+            message: "Hello".as_bytes().to_vec(),
+        });
+        chat_client.handle_response(message, 21);
+
+        assert!(matches!(
+            controller_channel_messages.1.recv().unwrap(),
+            SimControllerResponseWrapper::Message(SimControllerMessage::MessageReceived(21, 3, text)) if text == "Hello"
+        ));
+        assert!(neighbor.1.try_recv().is_err());
+    }
+}
@@ -9,6 +9,7 @@ pub mod register_test {
     };
     use wg_2024::packet::PacketType;
 
+    use crate::chat_client::ChatLayer;
     use crate::client::Client;
     use crate::tests::util;
 
@@ -0,0 +1,136 @@
+#[cfg(test)]
+pub mod registration_test {
+    use crossbeam_channel::unbounded;
+    use rustafarian_shared::messages::chat_messages::{ChatResponse, ChatResponseWrapper};
+    use wg_2024::packet::PacketType;
+
+    use crate::chat::{
+        ChatCommand, ChatConfig, ChatEvent, RegistrationState, RegistrationTimeout, Registrations,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    #[test]
+    fn registration_states() {
+        let mut registrations = Registrations::new(100, 2);
+        assert_eq!(registrations.state(21), RegistrationState::Unregistered);
+
+        assert!(registrations.start(21, 0));
+        // Already waiting for the answer
+        assert!(!registrations.start(21, 10));
+        assert!(registrations.check_timeouts(50).is_empty());
+        assert_eq!(
+            registrations.check_timeouts(100),
+            vec![RegistrationTimeout::Retry(21)]
+        );
+        assert_eq!(
+            registrations.state(21),
+            RegistrationState::Pending {
+                since: 100,
+                attempts: 2
+            }
+        );
+        assert_eq!(
+            registrations.check_timeouts(200),
+            vec![RegistrationTimeout::Failed(21)]
+        );
+        assert_eq!(registrations.state(21), RegistrationState::Failed);
+
+        // A failed registration can be started again
+        assert!(registrations.start(21, 300));
+        assert!(registrations.on_registered(21));
+        assert!(!registrations.on_registered(21));
+        assert!(!registrations.start(21, 400));
+        assert_eq!(
+            registrations.states(),
+            vec![(21, RegistrationState::Registered)]
+        );
+
+        assert_eq!(registrations.unregister(21), RegistrationState::Registered);
+        assert_eq!(registrations.state(21), RegistrationState::Unregistered);
+    }
+
+    #[test]
+    fn duplicate_registrations_are_ignored() {
+        let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+            util::build_client();
+
+        chat_client.register(21);
+        chat_client.register(21);
+        assert!(neighbor.1.try_recv().is_ok());
+        assert!(neighbor.1.try_recv().is_err());
+
+        for _ in 0..2 {
+            let registered = ChatResponseWrapper::Chat(ChatResponse::ClientRegistered);
+            chat_client.handle_response(registered, 21);
+        }
+        assert_eq!(chat_client.get_registered_servers(), &vec![21]);
+        assert_eq!(
+            chat_client.get_registration_state(21),
+            RegistrationState::Registered
+        );
+    }
+
+    #[test]
+    fn unregister_command() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.handle_response(
+            ChatResponseWrapper::Chat(ChatResponse::ClientRegistered),
+            21,
+        );
+
+        chat_commands.0.send(ChatCommand::Unregister(21)).unwrap();
+        chat_commands.0.send(ChatCommand::Registrations).unwrap();
+        chat_client.on_tick();
+
+        let events = chat_events.1.try_iter().collect::<Vec<ChatEvent>>();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ChatEvent::RegistrationChanged {
+                    server_id: 21,
+                    state: RegistrationState::Registered
+                },
+                ChatEvent::RegistrationChanged {
+                    server_id: 21,
+                    state: RegistrationState::Unregistered
+                },
+                ChatEvent::Registrations(states),
+            ] if states.is_empty()
+        ));
+        assert!(chat_client.get_registered_servers().is_empty());
+    }
+
+    #[test]
+    fn registration_is_retried_then_fails() {
+        let config = ChatConfig {
+            registration_timeout_ms: 0,
+            registration_attempts: 2,
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, neighbor) = util::build_chat_client(&config, util::ONE_SERVER);
+
+        chat_client.register(21);
+        chat_client.on_tick();
+        chat_client.on_tick();
+
+        let requests = neighbor
+            .try_iter()
+            .filter(|packet| matches!(packet.pack_type, PacketType::MsgFragment(_)))
+            .count();
+        assert_eq!(requests, 2);
+        assert_eq!(
+            chat_client.get_registration_state(21),
+            RegistrationState::Failed
+        );
+    }
+}
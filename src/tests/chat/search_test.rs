@@ -0,0 +1,153 @@
+#[cfg(test)]
+pub mod search_test {
+    use std::fs;
+
+    use crossbeam_channel::unbounded;
+
+    use crate::chat::export::render;
+    use crate::chat::{
+        ChatCommand, ChatEvent, ConversationStore, Direction, ExportFormat, HistoryEntry,
+        SearchQuery,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::tests::util;
+
+    fn entry(server_id: u8, peer_id: u8, timestamp: u128, text: &str) -> HistoryEntry {
+        HistoryEntry {
+            server_id,
+            peer_id,
+            direction: Direction::Received,
+            timestamp,
+            text: text.to_string(),
+            id: None,
+            expires_at: None,
+        }
+    }
+
+    fn store() -> ConversationStore {
+        let mut store = ConversationStore::new();
+        store.record(entry(21, 3, 100, "Hello there")).unwrap();
+        store.record(entry(21, 4, 200, "hello again")).unwrap();
+        store.record(entry(22, 3, 300, "Goodbye")).unwrap();
+        store.record(entry(21, 3, 400, "HELLO <b>")).unwrap();
+        store
+    }
+
+    fn timestamps(entries: &[HistoryEntry]) -> Vec<u128> {
+        entries.iter().map(|entry| entry.timestamp).collect()
+    }
+
+    #[test]
+    fn search_filters() {
+        let store = store();
+        assert_eq!(
+            timestamps(&store.search(&SearchQuery::default())),
+            vec![100, 200, 300, 400]
+        );
+
+        let hello = SearchQuery {
+            text: Some("hello".to_string()),
+            ..SearchQuery::default()
+        };
+        assert_eq!(timestamps(&store.search(&hello)), vec![100, 200, 400]);
+
+        let range = SearchQuery {
+            since: Some(200),
+            until: Some(400),
+            ..SearchQuery::default()
+        };
+        assert_eq!(timestamps(&store.search(&range)), vec![200, 300]);
+
+        let peer = SearchQuery {
+            peer_id: Some(3),
+            ..hello.clone()
+        };
+        assert_eq!(timestamps(&store.search(&peer)), vec![100, 400]);
+
+        let server = SearchQuery {
+            server_id: Some(22),
+            peer_id: Some(3),
+            ..SearchQuery::default()
+        };
+        assert_eq!(timestamps(&store.search(&server)), vec![300]);
+    }
+
+    #[test]
+    fn export_formats() {
+        let mut store = store();
+        store
+            .record(HistoryEntry {
+                direction: Direction::Sent,
+                ..entry(21, 3, 500, "Bye")
+            })
+            .unwrap();
+        let conversation = store.conversation(21, 3);
+
+        assert_eq!(
+            render(conversation, ExportFormat::Text).unwrap(),
+            "[100] 3: Hello there\n[400] 3: HELLO <b>\n[500] me: Bye\n"
+        );
+
+        let json = render(conversation, ExportFormat::Json).unwrap();
+        let parsed = serde_json::from_str::<Vec<HistoryEntry>>(&json).unwrap();
+        assert_eq!(parsed, conversation);
+
+        let html = render(conversation, ExportFormat::Html).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<li data-timestamp=\"400\"><b>3</b>: HELLO &lt;b&gt;</li>"));
+    }
+
+    #[test]
+    fn search_and_export_commands() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.send_chat_message(21, 3, "Hello".to_string());
+        chat_client.send_chat_message(21, 3, "World".to_string());
+        let _res = chat_events.1.try_iter().count();
+
+        chat_client.handle_chat_layer_command(ChatCommand::Search(SearchQuery {
+            text: Some("world".to_string()),
+            ..SearchQuery::default()
+        }));
+        let Ok(ChatEvent::SearchResults(entries)) = chat_events.1.try_recv() else {
+            panic!("Event should be SearchResults");
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].text, "World");
+
+        let path = std::env::temp_dir().join(format!("export_{}.txt", rand::random::<u64>()));
+        chat_client.handle_chat_layer_command(ChatCommand::Export {
+            server_id: 21,
+            peer_id: 3,
+            format: ExportFormat::Text,
+            path: path.clone(),
+        });
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::Exported(exported) if exported == path
+        ));
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("] me: Hello\n"));
+        assert_eq!(content.lines().count(), 2);
+        let _res = fs::remove_file(path);
+
+        let missing = std::env::temp_dir().join("missing_dir_for_export/export.txt");
+        chat_client.handle_chat_layer_command(ChatCommand::Export {
+            server_id: 21,
+            peer_id: 3,
+            format: ExportFormat::Json,
+            path: missing,
+        });
+        assert!(matches!(
+            chat_events.1.try_recv().unwrap(),
+            ChatEvent::CommandFailed(_)
+        ));
+    }
+}
@@ -4,10 +4,10 @@ pub mod send_message_test {
     use wg_2024::packet::{Packet, PacketType};
 
     use rustafarian_shared::assembler::disassembler::Disassembler;
-    use rustafarian_shared::messages::chat_messages::{
-        ChatRequest, ChatRequestWrapper, ChatResponse, ChatResponseWrapper,
-    };
+    use rustafarian_shared::messages::chat_messages::{ChatResponse, ChatResponseWrapper};
 
+    use crate::chat::ChatPayload;
+    use crate::chat_client::ChatLayer;
     use crate::client::Client;
     use crate::tests::util;
 
@@ -17,27 +17,14 @@ pub mod send_message_test {
         let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
             util::build_client();
 
-        chat_client.send_chat_message(21, 3, message.clone());
+        let id = chat_client.send_chat_message(21, 3, message.clone());
 
-        let message_req = ChatRequest::SendMessage {
-            from: 1,
-            to: 3,
-            message,
-        };
-        let serialized_message =
-            serde_json::to_string(&ChatRequestWrapper::Chat(message_req)).unwrap();
-
-        let fragments =
-            Disassembler::new().disassemble_message(serialized_message.as_bytes().to_vec(), 0);
-
-        let received_packet = neighbor.1.recv().unwrap();
-
-        let fragment = match received_packet.pack_type {
-            PacketType::MsgFragment(fragment) => fragment,
-            _ => panic!("Packet type should be MsgFragment"),
-        };
-
-        assert_eq!(fragment.data, fragments[0].data);
+        let sent = util::sent_payloads(&neighbor.1);
+        assert!(matches!(
+            sent.as_slice(),
+            [(3, ChatPayload::Sequenced { seq: 0, payload, .. })]
+                if **payload == ChatPayload::Text { id, text: message }
+        ));
     }
 
     #[test]
@@ -47,29 +34,36 @@ pub mod send_message_test {
         let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
             util::build_client();
 
-        chat_client.send_chat_message(21, 3, message.clone());
-
-        let message_req = ChatRequest::SendMessage {
-            from: 1,
-            to: 3,
-            message,
-        };
-        let serialized_message =
-            serde_json::to_string(&ChatRequestWrapper::Chat(message_req)).unwrap();
-
-        let fragments =
-            Disassembler::new().disassemble_message(serialized_message.as_bytes().to_vec(), 0);
-
-        for frag_index in 0..fragments.len() {
-            let received_packet = neighbor.1.recv().unwrap();
-
-            let fragment = match received_packet.pack_type {
-                PacketType::MsgFragment(fragment) => fragment,
-                _ => panic!("Packet type should be MsgFragment"),
-            };
-
-            assert_eq!(fragment.data, fragments[frag_index].data);
-        }
+        let first = chat_client.send_chat_message(21, 3, message.clone());
+        let second = chat_client.send_chat_message(21, 3, message.clone());
+
+        let sent = util::sent_payloads(&neighbor.1);
+        let sequenced = sent
+            .into_iter()
+            .map(|(_, payload)| match payload {
+                ChatPayload::Sequenced { seq, payload, .. } => (seq, *payload),
+                payload => panic!("Payload should be sequenced, got {payload:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sequenced,
+            vec![
+                (
+                    0,
+                    ChatPayload::Text {
+                        id: first,
+                        text: message.clone()
+                    }
+                ),
+                (
+                    1,
+                    ChatPayload::Text {
+                        id: second,
+                        text: message
+                    }
+                ),
+            ]
+        );
     }
 
     #[test]
@@ -0,0 +1,105 @@
+#[cfg(test)]
+pub mod send_to_test {
+    use crossbeam_channel::{unbounded, Receiver};
+    use rustafarian_shared::messages::chat_messages::ChatRequest;
+    use wg_2024::packet::Packet;
+
+    use crate::chat::{ChatConfig, ChatEvent, DeliveryStatus};
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::tests::util::{self, SentText};
+
+    /// A client registered to 21 (1-2-21) and 22 (1-2-3-22)
+    fn build_client(config: &ChatConfig) -> (ChatClient, Receiver<Packet>) {
+        let (mut chat_client, neighbor) =
+            util::build_chat_client(config, &[(1, 2), (2, 21), (2, 3), (3, 22)]);
+        for server_id in [21, 22] {
+            chat_client.handle_response(util::registered(), server_id);
+        }
+        (chat_client, neighbor)
+    }
+
+    #[test]
+    fn requires_a_registration() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        assert!(chat_client.send_to(3, "Hello".to_string()).is_err());
+    }
+
+    #[test]
+    fn picks_the_nearest_shared_server() {
+        let (mut chat_client, neighbor) = build_client(&ChatConfig::default());
+        chat_client.handle_response(util::client_list(&[1, 3, 5]), 21);
+        chat_client.handle_response(util::client_list(&[1, 4, 5]), 22);
+        assert_eq!(chat_client.get_shared_servers(5), vec![21, 22]);
+
+        let to_5 = chat_client.send_to(5, "Hello".to_string()).unwrap();
+        let to_4 = chat_client.send_to(4, "Hello".to_string()).unwrap();
+        assert_eq!(
+            util::sent_texts(&neighbor),
+            vec![
+                SentText::new(21, 5, to_5, "Hello"),
+                SentText::new(22, 4, to_4, "Hello")
+            ]
+        );
+        assert_eq!(chat_client.get_history().conversation(22, 4).len(), 1);
+    }
+
+    #[test]
+    fn fails_over_to_another_server() {
+        let config = ChatConfig {
+            delivery_timeout_ms: 0,
+            ..ChatConfig::default()
+        };
+        let (mut chat_client, neighbor) = build_client(&config);
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        chat_client.handle_response(util::client_list(&[1, 5]), 21);
+        chat_client.handle_response(util::client_list(&[1, 5]), 22);
+
+        let id = chat_client.send_to(5, "Hello".to_string()).unwrap();
+        chat_client.on_tick();
+        assert_eq!(
+            util::sent_texts(&neighbor),
+            vec![
+                SentText::new(21, 5, id, "Hello"),
+                SentText::new(22, 5, id, "Hello")
+            ]
+        );
+
+        chat_client.on_tick();
+        assert!(util::sent_texts(&neighbor).is_empty());
+        assert_eq!(
+            chat_client.get_message_status(id),
+            Some(DeliveryStatus::Failed)
+        );
+        assert!(chat_events.1.try_iter().any(|event| matches!(
+            event,
+            ChatEvent::SendFailed { id: failed, peer_id: 5 } if failed == id
+        )));
+    }
+
+    #[test]
+    fn refreshes_the_client_lists() {
+        let (mut chat_client, neighbor) = build_client(&ChatConfig::default());
+
+        let id = chat_client.send_to(5, "Hello".to_string()).unwrap();
+        let requests = util::sent_chat_requests(&neighbor);
+        assert!(matches!(
+            requests.as_slice(),
+            [(21, ChatRequest::ClientList), (22, ChatRequest::ClientList)]
+        ));
+
+        chat_client.handle_response(util::client_list(&[1]), 21);
+        chat_client.handle_response(util::client_list(&[1, 5]), 22);
+        assert_eq!(
+            util::sent_texts(&neighbor),
+            vec![SentText::new(22, 5, id, "Hello")]
+        );
+    }
+}
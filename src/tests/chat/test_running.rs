@@ -1,6 +1,6 @@
 #[cfg(test)]
 pub mod test_running {
-    use std::{collections::HashMap, thread};
+    use std::{collections::HashMap, thread, time::Duration};
 
     use crossbeam_channel::{unbounded, Receiver, Sender};
     use rustafarian_shared::{
@@ -12,7 +12,11 @@ pub mod test_running {
     };
     use wg_2024::packet::{Packet, PacketType};
 
-    use crate::{chat_client::ChatClient, client::Client};
+    use crate::{
+        chat::ChatPayload,
+        chat_client::ChatClient,
+        client::{Client, TICK_INTERVAL},
+    };
 
     #[test]
     fn test_message_received() {
@@ -108,21 +112,19 @@ pub mod test_running {
 
         controller_channel_commands.0.send(sim_command).unwrap();
 
-        let packet_received = neighbor.1.recv().unwrap();
-        assert!(matches!(
-            packet_received.pack_type,
-            PacketType::MsgFragment(_)
-        ));
-
-        let fragment = match packet_received.pack_type {
-            PacketType::MsgFragment(fragment) => fragment,
-            _ => panic!("Packet type should be MsgFragment"),
+        // The message can take more than one fragment
+        let mut assembler = Assembler::new();
+        let assembled_message = loop {
+            let packet_received = neighbor.1.recv().unwrap();
+            let fragment = match packet_received.pack_type {
+                PacketType::MsgFragment(fragment) => fragment,
+                _ => panic!("Packet type should be MsgFragment"),
+            };
+            if let Some(message) = assembler.add_fragment(fragment, packet_received.session_id) {
+                break message;
+            }
         };
 
-        let assembled_message = Assembler::new()
-            .add_fragment(fragment, packet_received.session_id)
-            .unwrap();
-
         let parsed_message = match serde_json::from_slice::<ChatRequestWrapper>(&assembled_message)
         {
             Ok(wrapper) => match wrapper {
@@ -135,7 +137,10 @@ pub mod test_running {
             _ => panic!("Message should be MessageTo"),
         };
 
-        assert_eq!(parsed_message.0, "Hello".to_string());
+        assert!(matches!(
+            ChatPayload::decode(&parsed_message.0).map(ChatPayload::unsequenced),
+            Some(ChatPayload::Text { text, .. }) if text == "Hello"
+        ));
         assert_eq!(parsed_message.1, 1);
         assert_eq!(parsed_message.2, 3);
     }
@@ -165,4 +170,34 @@ pub mod test_running {
             assert!(chat_client.topology().edges().contains_key(&2));
         });
     }
+
+    #[test]
+    fn test_idle_ticks_not_counted() {
+        let channel: (Sender<Packet>, Receiver<Packet>) = unbounded();
+        let controller_channel_commands = unbounded();
+        let controller_channel_messages = unbounded();
+
+        let mut chat_client = ChatClient::new(
+            1,
+            HashMap::new(),
+            channel.1,
+            controller_channel_commands.1.clone(),
+            controller_channel_messages.0.clone(),
+            false,
+        );
+
+        let stopped = unbounded();
+        thread::spawn(move || {
+            chat_client.run(1);
+            stopped.0.send(()).unwrap();
+        });
+
+        // Waking up without a message doesn't use a tick
+        assert!(stopped.1.recv_timeout(TICK_INTERVAL * 4).is_err());
+        controller_channel_commands
+            .0
+            .send(SimControllerCommand::Topology)
+            .unwrap();
+        assert!(stopped.1.recv_timeout(Duration::from_secs(1)).is_ok());
+    }
 }
@@ -0,0 +1,279 @@
+#[cfg(test)]
+pub mod transfer_test {
+    use crossbeam_channel::Receiver;
+    use rustafarian_shared::messages::chat_messages::{ChatRequest, ChatResponseWrapper};
+    use wg_2024::packet::Packet;
+
+    use crate::chat::transfer::{
+        checksum, Transfers, FILE_MAX_ATTEMPTS, FINISHED_TRANSFERS_RETAINED,
+    };
+    use crate::chat::{
+        ChatCommand, ChatEvent, ChatPayload, FileMessage, IncomingTransfer, OutgoingTransfer,
+        TransferState,
+    };
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::tests::util;
+
+    /// The file messages sent to the neighbor
+    fn sent_file_messages(neighbor: &Receiver<Packet>) -> Vec<FileMessage> {
+        util::sent_chat_requests(neighbor)
+            .into_iter()
+            .filter_map(|(_, request)| match request {
+                ChatRequest::SendMessage { message, .. } => match ChatPayload::decode(&message)? {
+                    ChatPayload::File(message) => Some(message),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn file_message_from(from: u8, message: FileMessage) -> ChatResponseWrapper {
+        util::message_from(from, &ChatPayload::File(message))
+    }
+
+    /// Pass the file messages sent by one client to the other, as coming from `from`.
+    /// Returns how many were passed
+    fn relay(sender: &Receiver<Packet>, from: u8, receiver: &mut ChatClient) -> usize {
+        let messages = sent_file_messages(sender);
+        let count = messages.len();
+        for message in messages {
+            receiver.handle_response(file_message_from(from, message), 21);
+        }
+        count
+    }
+
+    fn offer(data: &[u8], chunk_size: u64) -> FileMessage {
+        FileMessage::Offer {
+            transfer_id: 7,
+            name: "notes.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            size: data.len() as u64,
+            chunk_size,
+            checksum: checksum(data),
+        }
+    }
+
+    #[test]
+    fn chunks_are_sent_in_a_window() {
+        let data = b"0123456789".to_vec();
+        let mut outgoing = OutgoingTransfer::new(
+            7,
+            21,
+            4,
+            "notes.txt".to_string(),
+            "text/plain".to_string(),
+            data.clone(),
+            4,
+        );
+        assert_eq!(outgoing.total_chunks(), 3);
+        assert_eq!(outgoing.offer(0), offer(&data, 4));
+
+        assert_eq!(outgoing.on_accepted(&[], 2, 0), vec![0, 1]);
+        assert_eq!(outgoing.on_ack(0, 2, 0), vec![2]);
+        assert_eq!(outgoing.on_ack(0, 2, 0), Vec::<u64>::new());
+        assert_eq!(
+            outgoing.chunk(2),
+            FileMessage::Chunk {
+                transfer_id: 7,
+                index: 2,
+                data: b"89".to_vec()
+            }
+        );
+        assert_eq!(outgoing.progress().done_chunks, 1);
+    }
+
+    #[test]
+    fn file_is_checked_on_arrival() {
+        let data = b"0123456789".to_vec();
+        let mut incoming = IncomingTransfer::from_offer(21, 1, &offer(&data, 4)).unwrap();
+        incoming.accept(0);
+        assert!(incoming.on_chunk(2, b"89".to_vec(), 0));
+        assert!(incoming.on_chunk(0, b"0123".to_vec(), 0));
+        assert!(!incoming.on_chunk(0, b"0123".to_vec(), 0));
+        assert!(!incoming.on_chunk(3, b"??".to_vec(), 0));
+        assert!(!incoming.is_complete());
+        assert!(incoming.on_chunk(1, b"4567".to_vec(), 0));
+        assert!(incoming.is_complete());
+        assert_eq!(incoming.assemble(), Ok(data.clone()));
+
+        let mut corrupted = IncomingTransfer::from_offer(21, 1, &offer(&data, 4)).unwrap();
+        corrupted.accept(0);
+        corrupted.on_chunk(0, b"0123".to_vec(), 0);
+        corrupted.on_chunk(1, b"4567".to_vec(), 0);
+        corrupted.on_chunk(2, b"98".to_vec(), 0);
+        assert!(corrupted.assemble().is_err());
+    }
+
+    #[test]
+    fn interrupted_transfer_resumes_from_received_chunks() {
+        let data = b"0123456789".to_vec();
+        let mut incoming = IncomingTransfer::from_offer(21, 1, &offer(&data, 4)).unwrap();
+        incoming.accept(0);
+        incoming.on_chunk(0, b"0123".to_vec(), 0);
+        incoming.on_chunk(1, b"4567".to_vec(), 0);
+        for _ in 1..FILE_MAX_ATTEMPTS {
+            assert!(incoming.on_timeout(0).is_some());
+        }
+        assert_eq!(incoming.on_timeout(0), None);
+        assert_eq!(incoming.state, TransferState::Interrupted);
+
+        let resume = incoming.resume(0);
+        assert_eq!(
+            resume,
+            FileMessage::Accept {
+                transfer_id: 7,
+                have: vec![0, 1]
+            }
+        );
+        let mut outgoing = OutgoingTransfer::new(
+            7,
+            21,
+            1,
+            "notes.txt".to_string(),
+            "text/plain".to_string(),
+            data,
+            4,
+        );
+        assert_eq!(outgoing.on_accepted(&[0, 1], 4, 0), vec![2]);
+    }
+
+    #[test]
+    fn file_is_transferred_between_clients() {
+        let (mut sender, sender_neighbor, sender_events) = util::build_attached_client();
+        let (mut receiver, receiver_neighbor, receiver_events) = util::build_attached_client();
+        let data = (0..3000)
+            .map(|byte| (byte % 251) as u8)
+            .collect::<Vec<u8>>();
+
+        let transfer_id = sender.send_file(
+            21,
+            4,
+            "photo.png".to_string(),
+            "image/png".to_string(),
+            data.clone(),
+        );
+        relay(&sender_neighbor, 1, &mut receiver);
+        assert!(matches!(
+            receiver_events.try_recv().unwrap(),
+            ChatEvent::FileOffered { transfer_id: id, from: 1, size: 3000, .. } if id == transfer_id
+        ));
+
+        receiver.handle_chat_layer_command(ChatCommand::AcceptFile(transfer_id));
+        while relay(&receiver_neighbor, 4, &mut sender) + relay(&sender_neighbor, 1, &mut receiver)
+            > 0
+        {}
+
+        let received = receiver_events
+            .try_iter()
+            .find_map(|event| match event {
+                ChatEvent::FileReceived { name, data, .. } => Some((name, data)),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(received, ("photo.png".to_string(), data));
+
+        let progress = sender.get_transfer(transfer_id).unwrap();
+        assert_eq!(progress.state, TransferState::Completed);
+        assert_eq!((progress.done_chunks, progress.total_chunks), (3, 3));
+        // The file isn't kept once the transfer ended
+        let transfers = &sender.chat_state().transfers;
+        assert_eq!(transfers.outgoing[&transfer_id].buffered(), 0);
+        let transfers = &receiver.chat_state().transfers;
+        assert_eq!(transfers.incoming[&transfer_id].buffered(), 0);
+        assert_eq!(receiver.get_transfer(transfer_id).unwrap().done_chunks, 3);
+        assert!(sender_events.try_iter().any(|event| matches!(
+            event,
+            ChatEvent::TransferProgress(progress) if progress.state == TransferState::Completed
+        )));
+    }
+
+    #[test]
+    fn ended_transfers_are_forgotten() {
+        let mut transfers = Transfers::default();
+        let count = u64::try_from(FINISHED_TRANSFERS_RETAINED).unwrap() + 1;
+        for transfer_id in 0..=count {
+            let mut transfer = OutgoingTransfer::new(
+                transfer_id,
+                21,
+                4,
+                "notes.txt".to_string(),
+                "text/plain".to_string(),
+                b"Hello".to_vec(),
+                4,
+            );
+            // The last one is still being sent
+            if transfer_id < count {
+                transfer.state = TransferState::Completed;
+            }
+            transfers.outgoing.insert(transfer_id, transfer);
+            if transfer_id < count {
+                transfers.on_finished(transfer_id);
+            }
+        }
+        assert!(!transfers.outgoing.contains_key(&0));
+        assert_eq!(transfers.outgoing[&1].buffered(), 0);
+        assert_eq!(transfers.outgoing[&count].buffered(), 5);
+        assert_eq!(transfers.outgoing.len(), FINISHED_TRANSFERS_RETAINED + 1);
+    }
+
+    #[test]
+    fn rejected_offer() {
+        let (mut sender, sender_neighbor, _sender_events) = util::build_attached_client();
+        let (mut receiver, receiver_neighbor, _receiver_events) = util::build_attached_client();
+
+        let transfer_id = sender.send_file(
+            21,
+            4,
+            "notes.txt".to_string(),
+            "text/plain".to_string(),
+            b"Hello".to_vec(),
+        );
+        relay(&sender_neighbor, 1, &mut receiver);
+        assert_eq!(receiver.reject_file(transfer_id), Ok(()));
+        assert!(receiver.accept_file(transfer_id).is_err());
+        relay(&receiver_neighbor, 4, &mut sender);
+
+        assert_eq!(
+            sender.get_transfer(transfer_id).unwrap().state,
+            TransferState::Rejected
+        );
+        assert!(sent_file_messages(&sender_neighbor).is_empty());
+    }
+
+    #[test]
+    fn corrupted_file_fails() {
+        let (mut receiver, receiver_neighbor, receiver_events) = util::build_attached_client();
+        receiver.handle_response(file_message_from(4, offer(b"Hello", 1024)), 21);
+        receiver.accept_file(7).unwrap();
+        receiver.handle_response(
+            file_message_from(
+                4,
+                FileMessage::Chunk {
+                    transfer_id: 7,
+                    index: 0,
+                    data: b"Hallo".to_vec(),
+                },
+            ),
+            21,
+        );
+
+        let sent = sent_file_messages(&receiver_neighbor);
+        assert_eq!(sent.len(), 3);
+        assert_eq!(
+            sent[1],
+            FileMessage::Ack {
+                transfer_id: 7,
+                index: 0
+            }
+        );
+        assert!(matches!(
+            &sent[2],
+            FileMessage::Failed { transfer_id: 7, .. }
+        ));
+        assert!(!receiver_events
+            .try_iter()
+            .any(|event| matches!(event, ChatEvent::FileReceived { .. })));
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+pub mod unread_test {
+    use crossbeam_channel::unbounded;
+
+    use crate::chat::unread::{preview, PREVIEW_LENGTH};
+    use crate::chat::{
+        ChatCommand, ChatEvent, ConversationStore, Direction, HistoryEntry, UnreadCounters,
+    };
+    use crate::chat_client::ChatLayer;
+    use crate::client::Client;
+    use crate::tests::util;
+
+    fn entry(peer_id: u8, direction: Direction, timestamp: u128, text: &str) -> HistoryEntry {
+        HistoryEntry {
+            server_id: 21,
+            peer_id,
+            direction,
+            timestamp,
+            text: text.to_string(),
+            id: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn summaries_from_the_most_recent() {
+        let mut store = ConversationStore::new();
+        store
+            .record(entry(3, Direction::Received, 100, "Hello"))
+            .unwrap();
+        store
+            .record(entry(4, Direction::Received, 200, "Hi"))
+            .unwrap();
+        store
+            .record(entry(3, Direction::Sent, 300, &"a".repeat(50)))
+            .unwrap();
+        let mut unread = UnreadCounters::new();
+        unread.on_received(21, 3);
+        unread.on_received(21, 4);
+
+        let summaries = unread.summaries(&store);
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|summary| (summary.peer_id, summary.unread, summary.last_activity))
+                .collect::<Vec<_>>(),
+            vec![(3, 1, 300), (4, 1, 200)]
+        );
+        assert_eq!(summaries[0].last_direction, Direction::Sent);
+        assert_eq!(
+            summaries[0].preview,
+            format!("{}…", "a".repeat(PREVIEW_LENGTH))
+        );
+        assert_eq!(preview("Hello"), "Hello");
+
+        assert_eq!(unread.mark_read(21, 3), 1);
+        assert_eq!(unread.unread(21, 3), 0);
+    }
+
+    #[test]
+    fn unread_until_marked_read() {
+        let (
+            mut chat_client,
+            _neighbor,
+            _controller_channel_commands,
+            _controller_channel_messages,
+        ) = util::build_client();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+
+        chat_client.handle_response(util::text_from(3, 1, "Hello"), 21);
+        chat_client.handle_response(util::text_from(3, 2, "Are you there?"), 21);
+        chat_client.send_chat_message(21, 4, "Hi".to_string());
+        assert_eq!(chat_client.get_unread(21, 3), 2);
+        assert_eq!(chat_client.get_unread(21, 4), 0);
+        let _res = chat_events.1.try_iter().count();
+
+        chat_client.handle_chat_layer_command(ChatCommand::ConversationList);
+        let Ok(ChatEvent::ConversationList(conversations)) = chat_events.1.try_recv() else {
+            panic!("Event should be ConversationList");
+        };
+        assert_eq!(conversations.len(), 2);
+        let with_3 = conversations
+            .iter()
+            .find(|summary| summary.peer_id == 3)
+            .unwrap();
+        assert_eq!(
+            (with_3.unread, with_3.preview.as_str()),
+            (2, "Are you there?")
+        );
+
+        chat_client.handle_chat_layer_command(ChatCommand::MarkRead {
+            server_id: 21,
+            peer_id: 3,
+        });
+        assert_eq!(chat_client.get_unread(21, 3), 0);
+        chat_client.handle_response(util::text_from(3, 3, "Hello?"), 21);
+        assert_eq!(chat_client.get_unread(21, 3), 1);
+
+        chat_client.handle_chat_layer_command(ChatCommand::MarkRead {
+            server_id: 21,
+            peer_id: 5,
+        });
+        assert!(chat_events
+            .1
+            .try_iter()
+            .any(|event| matches!(event, ChatEvent::CommandFailed(_))));
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+pub mod protocol_test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use rustafarian_shared::assembler::disassembler::Disassembler;
+    use rustafarian_shared::messages::commander_messages::SimControllerCommand;
+    use wg_2024::network::{NodeId, SourceRoutingHeader};
+    use wg_2024::packet::{Packet, PacketType};
+
+    use crate::client::Client;
+    use crate::protocol::{ProtocolContext, ProtocolHandler};
+    use crate::tests::util;
+
+    /// A protocol for a key-value server, which sends "key=value" pairs
+    struct KeyValueProtocol {
+        server_id: NodeId,
+        values: Arc<Mutex<HashMap<String, String>>>,
+    }
+
+    impl ProtocolHandler for KeyValueProtocol {
+        fn handle_message(
+            &mut self,
+            _context: &mut dyn ProtocolContext,
+            _server_id: NodeId,
+            raw_content: &str,
+        ) -> Result<(), String> {
+            let (key, value) = raw_content
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid pair: {raw_content}"))?;
+            self.values
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn handle_command(
+            &mut self,
+            context: &mut dyn ProtocolContext,
+            command: SimControllerCommand,
+        ) -> Option<SimControllerCommand> {
+            match command {
+                SimControllerCommand::RequestFileList(server_id) if server_id == self.server_id => {
+                    context.send_message(server_id, "LIST".to_string());
+                    None
+                }
+                command => Some(command),
+            }
+        }
+    }
+
+    #[test]
+    fn custom_protocol_receives_its_messages() {
+        let (mut client, _neighbor, _, _) = util::build_unified();
+        let values = Arc::new(Mutex::new(HashMap::new()));
+        client.register_protocol(
+            "KeyValue",
+            Box::new(KeyValueProtocol {
+                server_id: 21,
+                values: values.clone(),
+            }),
+        );
+        client.protocols_mut().assign_server(21, "KeyValue");
+
+        client.on_text_response_arrived(21, 0, "color=blue".to_string());
+        // An invalid message is only logged
+        client.on_text_response_arrived(21, 1, "not a pair".to_string());
+
+        assert_eq!(
+            values.lock().unwrap().get("color"),
+            Some(&"blue".to_string())
+        );
+        assert_eq!(values.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn custom_protocol_handles_its_commands() {
+        let (mut client, neighbor, _, _) = util::build_unified();
+        client.register_protocol(
+            "KeyValue",
+            Box::new(KeyValueProtocol {
+                server_id: 21,
+                values: Arc::new(Mutex::new(HashMap::new())),
+            }),
+        );
+
+        client.handle_sim_controller_packets(Ok(SimControllerCommand::RequestFileList(21)));
+
+        let disassembled = Disassembler::new().disassemble_message(b"LIST".to_vec(), 0);
+        let received_packet = neighbor.1.recv().unwrap();
+        let expected_packet = Packet {
+            routing_header: SourceRoutingHeader::new(vec![1, 2, 21], 1),
+            session_id: received_packet.session_id,
+            pack_type: PacketType::MsgFragment(disassembled.first().unwrap().clone()),
+        };
+        assert_eq!(expected_packet, received_packet);
+    }
+
+    #[test]
+    fn unregistered_protocol_is_not_used() {
+        let (mut client, _neighbor, _, _) = util::build_unified();
+        client.register_protocol(
+            "KeyValue",
+            Box::new(KeyValueProtocol {
+                server_id: 21,
+                values: Arc::new(Mutex::new(HashMap::new())),
+            }),
+        );
+        client.protocols_mut().assign_server(21, "KeyValue");
+        assert_eq!(
+            client.protocols().handler_tag_of(21),
+            Some("KeyValue".to_string())
+        );
+
+        client.protocols_mut().unregister("KeyValue");
+        assert_eq!(client.protocols().handler_tag_of(21), None);
+        assert!(client.protocols().tags().is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+#[cfg(test)]
+pub mod sim_test {
+    use std::collections::HashMap;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crossbeam_channel::{unbounded, Receiver, Sender};
+    use rustafarian_shared::messages::commander_messages::{
+        SimControllerCommand, SimControllerMessage, SimControllerResponseWrapper,
+    };
+
+    use crate::browser_client::BrowserClient;
+    use crate::chat::{ChatCommand, ChatConfig, ChatEvent, MessageContent};
+    use crate::chat_client::{ChatClient, ChatLayer};
+    use crate::client::Client;
+    use crate::sim::VirtualNetwork;
+
+    fn start_chat_client(
+        network: &VirtualNetwork,
+        client_id: u8,
+    ) -> (
+        Sender<SimControllerCommand>,
+        Receiver<SimControllerResponseWrapper>,
+    ) {
+        let controller_channel_commands = unbounded();
+        let controller_channel_messages = unbounded();
+        let mut chat_client = ChatClient::with_transport(
+            client_id,
+            Box::new(network.transport(client_id)),
+            controller_channel_commands.1,
+            controller_channel_messages.0,
+            false,
+        );
+        thread::spawn(move || chat_client.run(u64::MAX));
+        (controller_channel_commands.0, controller_channel_messages.1)
+    }
+
+    /// Wait for a controller message matching `filter`, ignoring the others
+    fn wait_for<T>(
+        receiver: &Receiver<SimControllerResponseWrapper>,
+        filter: impl Fn(SimControllerMessage) -> Option<T>,
+    ) -> Option<T> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while let Ok(response) = receiver.recv_deadline(deadline) {
+            if let SimControllerResponseWrapper::Message(message) = response {
+                if let Some(found) = filter(message) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn chat_over_virtual_network() {
+        let mut network = VirtualNetwork::new();
+        network
+            .add_drone(10)
+            .add_drone(11)
+            .add_chat_server(20)
+            .add_client(1)
+            .add_client(2)
+            .connect(1, 10)
+            .connect(10, 11)
+            .connect(11, 20)
+            .connect(2, 11);
+        network.start();
+
+        let (commands_1, messages_1) = start_chat_client(&network, 1);
+        let (commands_2, messages_2) = start_chat_client(&network, 2);
+
+        commands_1.send(SimControllerCommand::Register(20)).unwrap();
+        commands_2.send(SimControllerCommand::Register(20)).unwrap();
+        thread::sleep(Duration::from_millis(300));
+
+        commands_1
+            .send(SimControllerCommand::ClientList(20))
+            .unwrap();
+        let client_list = wait_for(&messages_1, |message| match message {
+            SimControllerMessage::ClientListResponse(20, client_list) => Some(client_list),
+            _ => None,
+        });
+        assert!(client_list.is_some_and(|list| list.contains(&1) && list.contains(&2)));
+
+        commands_1
+            .send(SimControllerCommand::SendMessage(
+                "Hello".to_string(),
+                20,
+                2,
+            ))
+            .unwrap();
+        let received = wait_for(&messages_2, |message| match message {
+            SimControllerMessage::MessageReceived(server_id, from, text) => {
+                Some((server_id, from, text))
+            }
+            _ => None,
+        });
+        assert_eq!(received, Some((20, 1, "Hello".to_string())));
+    }
+
+    #[test]
+    fn message_to_departed_client_keeps_the_server() {
+        let mut network = VirtualNetwork::new();
+        network
+            .add_drone(10)
+            .add_chat_server(20)
+            .add_client(1)
+            .connect(1, 10)
+            .connect(10, 20);
+        network.start();
+
+        let config = ChatConfig {
+            server_timeout_ms: 200,
+            ..ChatConfig::default()
+        };
+        let controller_channel_commands = unbounded();
+        let chat_commands = unbounded();
+        let chat_events = unbounded();
+        let mut chat_client = ChatClient::with_config(
+            1,
+            Box::new(network.transport(1)),
+            controller_channel_commands.1,
+            unbounded().0,
+            false,
+            &config,
+        );
+        chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+        thread::spawn(move || chat_client.run(u64::MAX));
+
+        controller_channel_commands
+            .0
+            .send(SimControllerCommand::Register(20))
+            .unwrap();
+        thread::sleep(Duration::from_millis(300));
+        // 5 isn't registered to the server anymore, which drops the message without an answer
+        chat_commands
+            .0
+            .send(ChatCommand::SendContent {
+                server_id: 20,
+                peer_id: 5,
+                content: MessageContent::Text("Hello".to_string()),
+            })
+            .unwrap();
+        thread::sleep(Duration::from_millis(1500));
+
+        let events = chat_events.1.try_iter().collect::<Vec<ChatEvent>>();
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ChatEvent::MessageStatus { peer_id: 5, .. })));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, ChatEvent::ServerDown { .. })));
+    }
+
+    #[test]
+    fn browse_over_virtual_network() {
+        let mut network = VirtualNetwork::new();
+        network
+            .add_drone(10)
+            .add_text_server(30, HashMap::from([(1, "ref=2\nText".to_string())]))
+            .add_media_server(40, HashMap::from([(2, vec![1, 2, 3])]))
+            .add_client(1)
+            .connect(1, 10)
+            .connect(10, 30)
+            .connect(10, 40);
+        network.start();
+
+        let controller_channel_commands = unbounded();
+        let controller_channel_messages = unbounded();
+        let mut browser_client = BrowserClient::with_transport(
+            1,
+            Box::new(network.transport(1)),
+            controller_channel_commands.1,
+            controller_channel_messages.0,
+            false,
+        );
+        thread::spawn(move || browser_client.run(u64::MAX));
+        thread::sleep(Duration::from_millis(300));
+
+        controller_channel_commands
+            .0
+            .send(SimControllerCommand::RequestTextFile(1, 30))
+            .unwrap();
+        let received = wait_for(&controller_channel_messages.1, |message| match message {
+            SimControllerMessage::TextWithReferences(file_id, text, media) => {
+                Some((file_id, text, media))
+            }
+            _ => None,
+        });
+        assert_eq!(
+            received,
+            Some((
+                1,
+                "ref=2\nText".to_string(),
+                HashMap::from([(2, vec![1, 2, 3])])
+            ))
+        );
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+pub mod transport_test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crossbeam_channel::unbounded;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Packet, PacketType};
+
+    use crate::transport::{CrossbeamTransport, Transport, UdpTransport};
+
+    fn ack_packet(session_id: u64) -> Packet {
+        Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+            session_id,
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 2],
+            },
+        }
+    }
+
+    #[test]
+    fn crossbeam_send() {
+        let neighbor = unbounded();
+        let mut senders = HashMap::new();
+        senders.insert(2, neighbor.0);
+        let transport = CrossbeamTransport::new(senders, unbounded().1);
+
+        transport.send(2, ack_packet(7)).unwrap();
+
+        assert_eq!(neighbor.1.recv().unwrap().session_id, 7);
+        assert!(transport.send(3, ack_packet(7)).is_err());
+    }
+
+    #[test]
+    fn crossbeam_neighbors() {
+        let mut transport = CrossbeamTransport::new(HashMap::new(), unbounded().1);
+
+        transport.add_neighbor(4, unbounded().0);
+        assert!(transport.has_neighbor(4));
+        assert_eq!(transport.neighbors(), vec![4]);
+
+        transport.remove_neighbor(4);
+        assert!(!transport.has_neighbor(4));
+    }
+
+    #[test]
+    fn udp_loopback() {
+        let localhost = "127.0.0.1:0".parse().unwrap();
+        let mut first = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+        let mut second = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+        first.add_peer(2, second.local_address().unwrap());
+        second.add_peer(1, first.local_address().unwrap());
+
+        first.send(2, ack_packet(42)).unwrap();
+        let received = second
+            .receiver()
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(received.session_id, 42);
+        assert!(matches!(received.pack_type, PacketType::Ack(_)));
+
+        second.send(1, ack_packet(43)).unwrap();
+        let received = first
+            .receiver()
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(received.session_id, 43);
+    }
+
+    #[test]
+    fn udp_unknown_source() {
+        let localhost = "127.0.0.1:0".parse().unwrap();
+        let mut first = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+        let second = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+        // Only the second transport knows the other one
+        first.add_peer(2, second.local_address().unwrap());
+
+        first.send(2, ack_packet(42)).unwrap();
+        assert!(second
+            .receiver()
+            .recv_timeout(Duration::from_millis(300))
+            .is_err());
+    }
+
+    #[test]
+    fn udp_neighbors() {
+        let localhost = "127.0.0.1:0".parse().unwrap();
+        let mut transport = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+        transport.add_peer(2, "127.0.0.1:9".parse().unwrap());
+        transport.add_neighbor(2, unbounded().0);
+        transport.add_peer(3, "127.0.0.1:9".parse().unwrap());
+        transport.add_peer(3, "127.0.0.1:10".parse().unwrap());
+
+        let mut neighbors = transport.neighbors();
+        neighbors.sort_unstable();
+        assert_eq!(neighbors, vec![2, 3]);
+    }
+
+    #[test]
+    fn udp_stops_on_drop() {
+        let localhost = "127.0.0.1:0".parse().unwrap();
+        let transport = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+        let address = transport.local_address().unwrap();
+        drop(transport);
+
+        // The listener released the socket
+        assert!(UdpTransport::bind(address, HashMap::new()).is_ok());
+    }
+
+    #[test]
+    fn udp_unknown_peer() {
+        let localhost = "127.0.0.1:0".parse().unwrap();
+        let transport = UdpTransport::bind(localhost, HashMap::new()).unwrap();
+
+        assert!(transport.send(9, ack_packet(0)).is_err());
+    }
+}
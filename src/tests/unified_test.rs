@@ -0,0 +1,122 @@
+#[cfg(test)]
+pub mod unified_test {
+    use std::collections::HashMap;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crossbeam_channel::{unbounded, Receiver};
+    use rustafarian_shared::messages::commander_messages::{
+        SimControllerCommand, SimControllerMessage, SimControllerResponseWrapper,
+    };
+    use rustafarian_shared::messages::general_messages::ServerType;
+
+    use crate::chat_client::ChatClient;
+    use crate::client::Client;
+    use crate::sim::VirtualNetwork;
+    use crate::unified_client::UnifiedClient;
+
+    /// Wait for a controller message matching `filter`, ignoring the others
+    fn wait_for<T>(
+        receiver: &Receiver<SimControllerResponseWrapper>,
+        filter: impl Fn(SimControllerMessage) -> Option<T>,
+    ) -> Option<T> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while let Ok(response) = receiver.recv_deadline(deadline) {
+            if let SimControllerResponseWrapper::Message(message) = response {
+                if let Some(found) = filter(message) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn chat_and_browse_with_one_client() {
+        let mut network = VirtualNetwork::new();
+        network
+            .add_drone(10)
+            .add_chat_server(20)
+            .add_text_server(30, HashMap::from([(1, "Text".to_string())]))
+            .add_client(1)
+            .add_client(2)
+            .connect(1, 10)
+            .connect(2, 10)
+            .connect(10, 20)
+            .connect(10, 30);
+        network.start();
+
+        let unified_commands = unbounded();
+        let unified_messages = unbounded();
+        let mut unified_client = UnifiedClient::with_transport(
+            1,
+            Box::new(network.transport(1)),
+            unified_commands.1,
+            unified_messages.0,
+            false,
+        );
+        thread::spawn(move || unified_client.run(u64::MAX));
+
+        let chat_commands = unbounded();
+        let chat_messages = unbounded();
+        let mut chat_client = ChatClient::with_transport(
+            2,
+            Box::new(network.transport(2)),
+            chat_commands.1,
+            chat_messages.0,
+            false,
+        );
+        thread::spawn(move || chat_client.run(u64::MAX));
+        thread::sleep(Duration::from_millis(300));
+
+        // Both servers are known, with their types
+        unified_commands
+            .0
+            .send(SimControllerCommand::KnownServers)
+            .unwrap();
+        let servers = wait_for(&unified_messages.1, |message| match message {
+            SimControllerMessage::KnownServers(servers) => Some(servers),
+            _ => None,
+        });
+        let servers = servers.unwrap_or_default();
+        assert!(matches!(servers.get(&20), Some(ServerType::Chat)));
+        assert!(matches!(servers.get(&30), Some(ServerType::Text)));
+
+        // Browse
+        unified_commands
+            .0
+            .send(SimControllerCommand::RequestTextFile(1, 30))
+            .unwrap();
+        let text = wait_for(&unified_messages.1, |message| match message {
+            SimControllerMessage::TextFileResponse(1, text) => Some(text),
+            _ => None,
+        });
+        assert_eq!(text, Some("Text".to_string()));
+
+        // Chat
+        unified_commands
+            .0
+            .send(SimControllerCommand::Register(20))
+            .unwrap();
+        chat_commands
+            .0
+            .send(SimControllerCommand::Register(20))
+            .unwrap();
+        thread::sleep(Duration::from_millis(300));
+        chat_commands
+            .0
+            .send(SimControllerCommand::SendMessage(
+                "Hello".to_string(),
+                20,
+                1,
+            ))
+            .unwrap();
+        let received = wait_for(&unified_messages.1, |message| match message {
+            SimControllerMessage::MessageReceived(server_id, from, text) => {
+                Some((server_id, from, text))
+            }
+            _ => None,
+        });
+        assert_eq!(received, Some((20, 2, "Hello".to_string())));
+    }
+}
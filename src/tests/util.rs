@@ -1,12 +1,26 @@
 use std::collections::HashMap;
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
+use rustafarian_shared::assembler::assembler::Assembler;
+use rustafarian_shared::messages::chat_messages::{
+    ChatRequest, ChatRequestWrapper, ChatResponse, ChatResponseWrapper,
+};
 use rustafarian_shared::messages::commander_messages::{
     SimControllerCommand, SimControllerResponseWrapper,
 };
-use wg_2024::packet::Packet;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+use crate::chat::{ChatConfig, ChatEvent, ChatPayload};
+use crate::chat_client::ChatLayer;
+use crate::transport::CrossbeamTransport;
+use crate::{
+    browser_client::BrowserClient, chat_client::ChatClient, client::Client,
+    unified_client::UnifiedClient,
+};
 
-use crate::{browser_client::BrowserClient, chat_client::ChatClient, client::Client};
+/// The topology of `build_client`: 1-2-21
+pub(crate) const ONE_SERVER: &[(NodeId, NodeId)] = &[(1, 2), (2, 21)];
 
 pub(crate) fn build_client() -> (
     ChatClient,
@@ -48,6 +62,77 @@ pub(crate) fn build_client() -> (
     )
 }
 
+/// Chat client 1 with `config`, and the edges of its topology. Its only neighbor is 2,
+/// the packets sent to it are returned with the client
+pub(crate) fn build_chat_client(
+    config: &ChatConfig,
+    edges: &[(NodeId, NodeId)],
+) -> (ChatClient, Receiver<Packet>) {
+    let neighbor = unbounded::<Packet>();
+    let mut chat_client = ChatClient::with_config(
+        1,
+        Box::new(CrossbeamTransport::new(
+            HashMap::from([(2, neighbor.0)]),
+            unbounded().1,
+        )),
+        unbounded().1,
+        unbounded().0,
+        false,
+        config,
+    );
+    let mut nodes = edges
+        .iter()
+        .flat_map(|(first, second)| [*first, *second])
+        .filter(|node| *node != 1)
+        .collect::<Vec<NodeId>>();
+    nodes.sort_unstable();
+    nodes.dedup();
+    for node in nodes {
+        chat_client.topology().add_node(node);
+    }
+    for (first, second) in edges {
+        chat_client.topology().add_edge(*first, *second);
+    }
+    (chat_client, neighbor.1)
+}
+
+/// Attach a chat controller to a client, and return the events it gets
+pub(crate) fn attach_events(chat_client: &mut ChatClient) -> Receiver<ChatEvent> {
+    let chat_commands = unbounded();
+    let chat_events = unbounded();
+    chat_client.attach_chat_controller(chat_commands.1, chat_events.0);
+    chat_events.1
+}
+
+/// `build_client` with a chat controller: the client, its neighbor and the chat events
+pub(crate) fn build_attached_client() -> (ChatClient, Receiver<Packet>, Receiver<ChatEvent>) {
+    let (mut chat_client, neighbor, _controller_channel_commands, _controller_channel_messages) =
+        build_client();
+    let chat_events = attach_events(&mut chat_client);
+    (chat_client, neighbor.1, chat_events)
+}
+
+pub(crate) fn registered() -> ChatResponseWrapper {
+    ChatResponseWrapper::Chat(ChatResponse::ClientRegistered)
+}
+
+pub(crate) fn client_list(clients: &[NodeId]) -> ChatResponseWrapper {
+    ChatResponseWrapper::Chat(ChatResponse::ClientList(clients.to_vec()))
+}
+
+/// A payload from another client
+pub(crate) fn message_from(from: NodeId, payload: &ChatPayload) -> ChatResponseWrapper {
+    ChatResponseWrapper::Chat(ChatResponse::MessageFrom {
+        from,
+        message: payload.encode().into_bytes(),
+    })
+}
+
+pub(crate) fn text_from(from: NodeId, id: u64, text: &str) -> ChatResponseWrapper {
+    let text = text.to_string();
+    message_from(from, &ChatPayload::Text { id, text })
+}
+
 pub(crate) fn build_browser() -> (
     BrowserClient,
     (Sender<Packet>, Receiver<Packet>),
@@ -87,3 +172,117 @@ pub(crate) fn build_browser() -> (
         controller_channel_messages,
     )
 }
+
+pub(crate) fn build_unified() -> (
+    UnifiedClient,
+    (Sender<Packet>, Receiver<Packet>),
+    (Sender<SimControllerCommand>, Receiver<SimControllerCommand>),
+    (
+        Sender<SimControllerResponseWrapper>,
+        Receiver<SimControllerResponseWrapper>,
+    ),
+) {
+    let neighbor: (Sender<Packet>, Receiver<Packet>) = unbounded();
+    let mut neighbors = HashMap::new();
+    neighbors.insert(2 as u8, neighbor.0.clone());
+    let channel: (Sender<Packet>, Receiver<Packet>) = unbounded();
+    let client_id = 1;
+
+    let controller_channel_commands = unbounded();
+    let controller_channel_messages = unbounded();
+
+    let mut unified_client = UnifiedClient::new(
+        client_id,
+        neighbors,
+        channel.1,
+        controller_channel_commands.1.clone(),
+        controller_channel_messages.0.clone(),
+        false,
+    );
+
+    unified_client.topology().add_node(2);
+    unified_client.topology().add_node(21);
+    unified_client.topology().add_edge(2, 21);
+    unified_client.topology().add_edge(1, 2);
+
+    (
+        unified_client,
+        neighbor,
+        controller_channel_commands,
+        controller_channel_messages,
+    )
+}
+
+/// The chat requests sent to a neighbor so far, assembled from their fragments,
+/// with the node they are for
+pub(crate) fn sent_chat_requests(neighbor: &Receiver<Packet>) -> Vec<(NodeId, ChatRequest)> {
+    let mut assembler = Assembler::new();
+    neighbor
+        .try_iter()
+        .filter_map(|packet| {
+            let PacketType::MsgFragment(fragment) = packet.pack_type else {
+                return None;
+            };
+            let destination = *packet.routing_header.hops.last()?;
+            let message = assembler.add_fragment(fragment, packet.session_id)?;
+            match serde_json::from_slice::<ChatRequestWrapper>(&message) {
+                Ok(ChatRequestWrapper::Chat(request)) => Some((destination, request)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The payloads sent to a neighbor so far, with their recipient
+pub(crate) fn sent_payloads(neighbor: &Receiver<Packet>) -> Vec<(NodeId, ChatPayload)> {
+    sent_chat_requests(neighbor)
+        .into_iter()
+        .filter_map(|(_, request)| match request {
+            ChatRequest::SendMessage { to, message, .. } => {
+                Some((to, ChatPayload::decode(&message)?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A text sent to a neighbor, with the server it went through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SentText {
+    pub server_id: NodeId,
+    pub to: NodeId,
+    pub id: u64,
+    pub text: String,
+}
+
+impl SentText {
+    pub(crate) fn new(server_id: NodeId, to: NodeId, id: u64, text: &str) -> Self {
+        SentText {
+            server_id,
+            to,
+            id,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// The texts sent to a neighbor so far, without their sequence number
+pub(crate) fn sent_texts(neighbor: &Receiver<Packet>) -> Vec<SentText> {
+    sent_chat_requests(neighbor)
+        .into_iter()
+        .filter_map(|(server_id, request)| match request {
+            ChatRequest::SendMessage { to, message, .. } => {
+                match ChatPayload::decode(&message)?.unsequenced() {
+                    ChatPayload::Text { id, text } => Some(SentText {
+                        server_id,
+                        to,
+                        id,
+                        text,
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
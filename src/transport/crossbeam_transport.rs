@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use crossbeam_channel::{Receiver, Sender};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use super::Transport;
+
+/// Transport used when all the nodes run as threads of the same process
+pub struct CrossbeamTransport {
+    /// Key: the id of the neighbor, value: the channel used to send packets to it
+    senders: HashMap<NodeId, Sender<Packet>>,
+    receiver: Receiver<Packet>,
+}
+
+impl CrossbeamTransport {
+    #[must_use]
+    pub fn new(senders: HashMap<NodeId, Sender<Packet>>, receiver: Receiver<Packet>) -> Self {
+        CrossbeamTransport { senders, receiver }
+    }
+}
+
+impl Transport for CrossbeamTransport {
+    fn send(&self, neighbor_id: NodeId, packet: Packet) -> Result<(), String> {
+        match self.senders.get(&neighbor_id) {
+            Some(sender) => sender
+                .send(packet)
+                .map_err(|err| format!("Couldn't send packet to {neighbor_id}: {err}")),
+            None => Err(format!("No sender found for neighbor {neighbor_id}")),
+        }
+    }
+
+    fn receiver(&self) -> &Receiver<Packet> {
+        &self.receiver
+    }
+
+    fn neighbors(&self) -> Vec<NodeId> {
+        self.senders.keys().copied().collect()
+    }
+
+    fn add_neighbor(&mut self, neighbor_id: NodeId, sender: Sender<Packet>) {
+        self.senders.insert(neighbor_id, sender);
+    }
+
+    fn remove_neighbor(&mut self, neighbor_id: NodeId) {
+        self.senders.remove(&neighbor_id);
+    }
+
+    fn has_neighbor(&self, neighbor_id: NodeId) -> bool {
+        self.senders.contains_key(&neighbor_id)
+    }
+}
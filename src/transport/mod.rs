@@ -0,0 +1,30 @@
+pub mod crossbeam_transport;
+pub mod udp_transport;
+
+use crossbeam_channel::{Receiver, Sender};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+pub use crossbeam_transport::CrossbeamTransport;
+pub use udp_transport::UdpTransport;
+
+/// A trait for the link layer used by a client to exchange packets with its neighbors
+pub trait Transport: Send {
+    /// Send a packet to a neighbor
+    /// # Errors
+    /// Returns an error if the neighbor is unknown or the packet couldn't be delivered
+    fn send(&self, neighbor_id: NodeId, packet: Packet) -> Result<(), String>;
+    /// The channel where the packets received from the neighbors are delivered
+    fn receiver(&self) -> &Receiver<Packet>;
+    /// The ids of the neighbors reachable through the transport
+    fn neighbors(&self) -> Vec<NodeId>;
+    /// Add a neighbor reachable through a crossbeam channel
+    fn add_neighbor(&mut self, neighbor_id: NodeId, sender: Sender<Packet>);
+    /// Remove a neighbor, packets can no longer be sent to it
+    fn remove_neighbor(&mut self, neighbor_id: NodeId);
+
+    /// Whether a neighbor is reachable through the transport
+    fn has_neighbor(&self, neighbor_id: NodeId) -> bool {
+        self.neighbors().contains(&neighbor_id)
+    }
+}
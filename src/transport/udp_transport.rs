@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+use super::Transport;
+
+/// The biggest payload that fits in a single UDP datagram
+pub const MAX_DATAGRAM_SIZE: usize = 65_507;
+/// How often the listener checks whether the transport was dropped
+const LISTEN_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The UDP neighbors, key: the id of the neighbor, value: the address of its socket
+type Peers = Arc<RwLock<HashMap<NodeId, SocketAddr>>>;
+
+/// Transport that exchanges packets serialized as JSON over UDP, so that the nodes can run as separate processes.
+/// Every node binds its own socket, and knows the address of its neighbors.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// Shared with the listener, which only accepts the datagrams sent from these addresses
+    peers: Peers,
+    /// Neighbors added at runtime through a crossbeam channel (e.g. by the simulation controller)
+    local_peers: HashMap<NodeId, Sender<Packet>>,
+    receiver: Receiver<Packet>,
+    /// Set when the transport is dropped, to stop the listener
+    stopped: Arc<AtomicBool>,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl UdpTransport {
+    /// Bind the socket to `local_address` and start listening for packets
+    /// # Errors
+    /// Returns an error if the socket couldn't be bound
+    pub fn bind(
+        local_address: SocketAddr,
+        peers: HashMap<NodeId, SocketAddr>,
+    ) -> Result<Self, String> {
+        let socket = UdpSocket::bind(local_address)
+            .map_err(|err| format!("Couldn't bind UDP socket to {local_address}: {err}"))?;
+        let listener_socket = socket
+            .try_clone()
+            .map_err(|err| format!("Couldn't clone UDP socket: {err}"))?;
+        listener_socket
+            .set_read_timeout(Some(LISTEN_TIMEOUT))
+            .map_err(|err| format!("Couldn't set UDP socket timeout: {err}"))?;
+        let (sender, receiver) = unbounded();
+        let peers = Arc::new(RwLock::new(peers));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let listener = {
+            let peers = Arc::clone(&peers);
+            let stopped = Arc::clone(&stopped);
+            thread::spawn(move || listen(&listener_socket, &peers, &stopped, &sender))
+        };
+
+        Ok(UdpTransport {
+            socket,
+            peers,
+            local_peers: HashMap::new(),
+            receiver,
+            stopped,
+            listener: Some(listener),
+        })
+    }
+
+    /// The address the socket is bound to
+    /// # Errors
+    /// Returns an error if the address couldn't be read from the socket
+    pub fn local_address(&self) -> Result<SocketAddr, String> {
+        self.socket
+            .local_addr()
+            .map_err(|err| format!("Couldn't read local address: {err}"))
+    }
+
+    /// Add a neighbor reachable at a UDP address, replacing the one with the same id
+    pub fn add_peer(&mut self, neighbor_id: NodeId, address: SocketAddr) {
+        self.local_peers.remove(&neighbor_id);
+        write_peers(&self.peers).insert(neighbor_id, address);
+    }
+}
+
+impl Drop for UdpTransport {
+    /// Stop the listener and wait for it
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(listener) = self.listener.take() {
+            let _res = listener.join();
+        }
+    }
+}
+
+fn read_peers(peers: &Peers) -> RwLockReadGuard<'_, HashMap<NodeId, SocketAddr>> {
+    peers.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_peers(peers: &Peers) -> RwLockWriteGuard<'_, HashMap<NodeId, SocketAddr>> {
+    peers.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Whether a datagram from `source` was sent by a neighbor. A neighbor bound to
+/// an unspecified address (e.g. `0.0.0.0`) is matched by its port only
+fn is_peer(peers: &Peers, source: SocketAddr) -> bool {
+    read_peers(peers).values().any(|address| {
+        *address == source || (address.ip().is_unspecified() && address.port() == source.port())
+    })
+}
+
+/// Receive datagrams from the socket, and forward the packets to the client channel until `stopped` is set.
+/// Datagrams that don't come from a neighbor or can't be deserialized are discarded.
+fn listen(socket: &UdpSocket, peers: &Peers, stopped: &AtomicBool, sender: &Sender<Packet>) {
+    let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
+    while !stopped.load(Ordering::Relaxed) {
+        let (size, source) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue
+            }
+            Err(_) => return,
+        };
+        if !is_peer(peers, source) {
+            continue;
+        }
+        let Ok(packet) = serde_json::from_slice::<Packet>(&buffer[..size]) else {
+            continue;
+        };
+        // The client was dropped, stop listening
+        if sender.send(packet).is_err() {
+            return;
+        }
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&self, neighbor_id: NodeId, packet: Packet) -> Result<(), String> {
+        if let Some(sender) = self.local_peers.get(&neighbor_id) {
+            return sender
+                .send(packet)
+                .map_err(|err| format!("Couldn't send packet to {neighbor_id}: {err}"));
+        }
+        let address = read_peers(&self.peers)
+            .get(&neighbor_id)
+            .copied()
+            .ok_or_else(|| format!("No address found for neighbor {neighbor_id}"))?;
+        let serialized = serde_json::to_vec(&packet)
+            .map_err(|err| format!("Couldn't serialize packet: {err}"))?;
+        if serialized.len() > MAX_DATAGRAM_SIZE {
+            return Err(format!(
+                "Packet too big for a datagram ({} bytes)",
+                serialized.len()
+            ));
+        }
+        self.socket
+            .send_to(&serialized, address)
+            .map(|_| ())
+            .map_err(|err| format!("Couldn't send packet to {neighbor_id} ({address}): {err}"))
+    }
+
+    fn receiver(&self) -> &Receiver<Packet> {
+        &self.receiver
+    }
+
+    fn neighbors(&self) -> Vec<NodeId> {
+        // A neighbor is either a UDP peer or a local one, never both
+        read_peers(&self.peers)
+            .keys()
+            .chain(self.local_peers.keys())
+            .copied()
+            .collect()
+    }
+
+    /// Add a neighbor reachable through a crossbeam channel, replacing the one with the same id
+    fn add_neighbor(&mut self, neighbor_id: NodeId, sender: Sender<Packet>) {
+        write_peers(&self.peers).remove(&neighbor_id);
+        self.local_peers.insert(neighbor_id, sender);
+    }
+
+    fn remove_neighbor(&mut self, neighbor_id: NodeId) {
+        write_peers(&self.peers).remove(&neighbor_id);
+        self.local_peers.remove(&neighbor_id);
+    }
+
+    fn has_neighbor(&self, neighbor_id: NodeId) -> bool {
+        read_peers(&self.peers).contains_key(&neighbor_id)
+            || self.local_peers.contains_key(&neighbor_id)
+    }
+}
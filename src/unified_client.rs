@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+
+use crate::browser_client::{BrowserLayer, BrowserState};
+use crate::chat::ChatConfig;
+use crate::chat_client::{ChatLayer, ChatState};
+use crate::client::Client;
+use crate::protocol::{self, server_type_tag, ProtocolHandler, ProtocolRegistry};
+use crate::transport::{CrossbeamTransport, Transport};
+use rustafarian_shared::assembler::{assembler::Assembler, disassembler::Disassembler};
+use rustafarian_shared::logger::{LogLevel, Logger};
+use rustafarian_shared::messages::browser_messages::BrowserResponseWrapper;
+use rustafarian_shared::messages::chat_messages::{ChatRequestWrapper, ChatResponseWrapper};
+use rustafarian_shared::messages::commander_messages::{
+    SimControllerCommand, SimControllerMessage, SimControllerResponseWrapper,
+};
+use rustafarian_shared::messages::general_messages::{
+    DroneSend, Response, ServerType, ServerTypeRequest, ServerTypeResponse,
+};
+use rustafarian_shared::topology::Topology;
+
+use crossbeam_channel::{Receiver, Sender};
+use wg_2024::{network::NodeId, packet::Packet};
+
+/// A client that can chat and browse at the same time.
+/// Both application layers share the same transport, topology and fragment bookkeeping,
+/// and the responses are dispatched based on the type of the server that sent them
+pub struct UnifiedClient {
+    // Used for general client
+    client_id: u8,
+    transport: Box<dyn Transport>,
+    topology: Topology,
+    sim_controller_receiver: Receiver<SimControllerCommand>,
+    sim_controller_sender: Sender<SimControllerResponseWrapper>,
+    sent_packets: HashMap<u64, Vec<Packet>>,
+    acked_packets: HashMap<u64, Vec<bool>>,
+    assembler: Assembler,
+    disassembler: Disassembler,
+    running: bool,
+    packets_to_send: HashMap<u8, Packet>,
+    sent_flood_ids: Vec<u64>,
+    last_flood_timestamp: u128,
+    logger: Logger,
+
+    // Specific to the application layers
+    chat_state: ChatState,
+    browser_state: BrowserState,
+    /// The types learned from the `ServerTypeResponse`s, used to dispatch the responses
+    server_types: HashMap<NodeId, ServerType>,
+    /// The additional protocols, they take precedence over the chat and browser layers
+    protocols: ProtocolRegistry,
+}
+
+impl UnifiedClient {
+    #[must_use]
+    pub fn new(
+        client_id: u8,
+        senders: HashMap<u8, Sender<Packet>>,
+        receiver: Receiver<Packet>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+    ) -> Self {
+        Self::with_transport(
+            client_id,
+            Box::new(CrossbeamTransport::new(senders, receiver)),
+            sim_controller_receiver,
+            sim_controller_sender,
+            debug,
+        )
+    }
+
+    /// Create a client that exchanges packets through a custom transport (e.g. UDP)
+    #[must_use]
+    pub fn with_transport(
+        client_id: u8,
+        transport: Box<dyn Transport>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+    ) -> Self {
+        Self::with_config(
+            client_id,
+            transport,
+            sim_controller_receiver,
+            sim_controller_sender,
+            debug,
+            &ChatConfig::default(),
+        )
+    }
+
+    /// Create a client with custom settings for the chat layer
+    #[must_use]
+    pub fn with_config(
+        client_id: u8,
+        transport: Box<dyn Transport>,
+        sim_controller_receiver: Receiver<SimControllerCommand>,
+        sim_controller_sender: Sender<SimControllerResponseWrapper>,
+        debug: bool,
+        config: &ChatConfig,
+    ) -> Self {
+        let logger = Logger::new("UnifiedClient".to_string(), client_id, debug);
+        let chat_state = ChatState::from_config(config, &logger);
+        UnifiedClient {
+            client_id,
+            transport,
+            topology: Topology::new(),
+            sim_controller_receiver,
+            sim_controller_sender,
+            sent_packets: HashMap::new(),
+            acked_packets: HashMap::new(),
+            assembler: Assembler::new(),
+            disassembler: Disassembler::new(),
+            running: false,
+            packets_to_send: HashMap::new(),
+            sent_flood_ids: Vec::new(),
+            last_flood_timestamp: 0,
+            logger,
+
+            chat_state,
+            browser_state: BrowserState::default(),
+            server_types: HashMap::new(),
+            protocols: ProtocolRegistry::new(),
+        }
+    }
+
+    /// Register a protocol handler for a tag, returns the handler previously registered for it.
+    /// Registering "Chat", "Text" or "Media" replaces the built-in layer for that server type
+    pub fn register_protocol(
+        &mut self,
+        tag: &str,
+        handler: Box<dyn ProtocolHandler>,
+    ) -> Option<Box<dyn ProtocolHandler>> {
+        self.protocols.register(tag, handler)
+    }
+
+    #[must_use]
+    pub fn protocols(&self) -> &ProtocolRegistry {
+        &self.protocols
+    }
+
+    pub fn protocols_mut(&mut self) -> &mut ProtocolRegistry {
+        &mut self.protocols
+    }
+
+    /// Run the handler of a tag with this client as context
+    fn with_protocol<T>(
+        &mut self,
+        tag: &str,
+        action: impl FnOnce(&mut dyn ProtocolHandler, &mut dyn protocol::ProtocolContext) -> T,
+    ) -> Option<T> {
+        let mut handler = self.protocols.take(tag)?;
+        let result = action(handler.as_mut(), self);
+        self.protocols.put_back(tag, handler);
+        Some(result)
+    }
+
+    /// Offer a command to the registered protocols, returns it back if none of them handled it
+    fn handle_protocol_command(
+        &mut self,
+        mut command: SimControllerCommand,
+    ) -> Option<SimControllerCommand> {
+        for tag in self.protocols.tags() {
+            let Some(mut handler) = self.protocols.take(&tag) else {
+                continue;
+            };
+            let result = handler.handle_command(self, command);
+            self.protocols.put_back(&tag, handler);
+            command = result?;
+        }
+        Some(command)
+    }
+
+    /// The types of the servers learned so far
+    #[must_use]
+    pub fn get_server_types(&self) -> &HashMap<NodeId, ServerType> {
+        &self.server_types
+    }
+
+    /// When a `ServerTypeResponse` is received, notify both layers and the controller
+    fn on_server_type_received(&mut self, server_id: NodeId, server_type: ServerType) {
+        self.logger.log(
+            &format!("Received server type: {server_type:?} from {server_id}"),
+            LogLevel::DEBUG,
+        );
+        self.topology
+            .set_node_type(server_id, format!("{server_type:?}"));
+        self.server_types.insert(server_id, server_type.clone());
+        // Keep the protocol assigned by hand, if any
+        if self.protocols.tag_of(server_id).is_none() {
+            self.protocols
+                .assign_server(server_id, &server_type_tag(&server_type));
+        }
+        if let Some(tag) = self.protocols.handler_tag_of(server_id) {
+            self.with_protocol(&tag, |handler, context| {
+                handler.on_server_discovered(context, server_id);
+            });
+        } else {
+            match server_type {
+                ServerType::Chat => self.on_chat_server_discovered(server_id),
+                ServerType::Text | ServerType::Media => {
+                    self.on_content_server_discovered(server_id, &server_type);
+                }
+            }
+        }
+
+        // Send the server type response to the sim controller
+        let response = SimControllerMessage::ServerTypeResponse(server_id, server_type);
+        let _res = self
+            .sim_controller_sender
+            .send(SimControllerResponseWrapper::Message(response));
+    }
+
+    /// Handle a response coming from a content server
+    fn handle_content_response(&mut self, response: BrowserResponseWrapper, server_id: NodeId) {
+        match response {
+            BrowserResponseWrapper::Chat(response) => {
+                self.handle_browser_response(response, server_id);
+            }
+            BrowserResponseWrapper::ServerType(ServerTypeResponse::ServerType(server_type)) => {
+                self.on_server_type_received(server_id, server_type);
+            }
+        }
+    }
+}
+
+impl ChatLayer for UnifiedClient {
+    fn chat_state(&self) -> &ChatState {
+        &self.chat_state
+    }
+
+    fn chat_state_mut(&mut self) -> &mut ChatState {
+        &mut self.chat_state
+    }
+}
+
+impl BrowserLayer for UnifiedClient {
+    fn browser_state(&self) -> &BrowserState {
+        &self.browser_state
+    }
+
+    fn browser_state_mut(&mut self) -> &mut BrowserState {
+        &mut self.browser_state
+    }
+}
+
+impl Client for UnifiedClient {
+    type RequestType = ChatRequestWrapper;
+    type ResponseType = ChatResponseWrapper;
+
+    fn client_id(&self) -> u8 {
+        self.client_id
+    }
+
+    fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
+    }
+
+    fn transport_mut(&mut self) -> &mut dyn Transport {
+        self.transport.as_mut()
+    }
+
+    fn topology(&mut self) -> &mut Topology {
+        &mut self.topology
+    }
+
+    fn on_tick(&mut self) {
+        self.on_chat_tick();
+    }
+
+    /// Dispatch the message to the protocol handler or the layer matching the server that sent it.
+    /// If the type is still unknown, try the chat format first, then the browser one
+    fn on_text_response_arrived(
+        &mut self,
+        source_id: NodeId,
+        session_id: u64,
+        raw_content: String,
+    ) {
+        if let Some(tag) = self.protocols.handler_tag_of(source_id) {
+            let result = self.with_protocol(&tag, |handler, context| {
+                handler.handle_message(context, source_id, &raw_content)
+            });
+            if let Some(Err(err)) = result {
+                self.logger.log(&format!("ERROR: protocol '{tag}' couldn't handle the message. Error: {err}. Message from {source_id}, packet id: {session_id}, content: {raw_content}"), LogLevel::ERROR);
+            }
+            return;
+        }
+        let result = match self.server_types.get(&source_id).cloned() {
+            Some(ServerType::Text | ServerType::Media) => {
+                BrowserResponseWrapper::from_string(raw_content.clone())
+                    .map(|response| self.handle_content_response(response, source_id))
+            }
+            Some(ServerType::Chat) => ChatResponseWrapper::from_string(raw_content.clone())
+                .map(|response| self.handle_response(response, source_id)),
+            None => match ChatResponseWrapper::from_string(raw_content.clone()) {
+                Ok(response) => {
+                    self.handle_response(response, source_id);
+                    Ok(())
+                }
+                Err(_) => BrowserResponseWrapper::from_string(raw_content.clone())
+                    .map(|response| self.handle_content_response(response, source_id)),
+            },
+        };
+        if let Err(err) = result {
+            self.logger.log(&format!("ERROR: couldn't deserialize message into ResponseType. Error: {err}. Message from {source_id}, packet id: {session_id}, content: {raw_content}"), LogLevel::ERROR);
+        }
+    }
+
+    /// Handle a response from a chat server
+    fn handle_response(&mut self, response: Self::ResponseType, server_id: NodeId) {
+        match response {
+            ChatResponseWrapper::Chat(response) => self.handle_chat_response(response, server_id),
+            ChatResponseWrapper::ServerType(ServerTypeResponse::ServerType(server_type)) => {
+                self.on_server_type_received(server_id, server_type);
+            }
+        }
+    }
+
+    fn sim_controller_receiver(&self) -> &Receiver<SimControllerCommand> {
+        &self.sim_controller_receiver
+    }
+
+    fn assembler(&mut self) -> &mut Assembler {
+        &mut self.assembler
+    }
+
+    fn deassembler(&mut self) -> &mut Disassembler {
+        &mut self.disassembler
+    }
+
+    fn sent_packets(&mut self) -> &mut HashMap<u64, Vec<Packet>> {
+        &mut self.sent_packets
+    }
+
+    fn sim_controller_sender(&self) -> &Sender<SimControllerResponseWrapper> {
+        &self.sim_controller_sender
+    }
+
+    /// Handle the commands sent by the controller, both chat and browser commands are accepted
+    fn handle_controller_commands(&mut self, command: SimControllerCommand) {
+        let Some(command) = self.handle_protocol_command(command) else {
+            return;
+        };
+        let Some(command) = self.handle_chat_command(command) else {
+            return;
+        };
+        let Some(command) = self.handle_browser_command(command) else {
+            return;
+        };
+        let Some(command) = self.handle_network_command(command) else {
+            return;
+        };
+        match command {
+            // Send every known server, whatever its type
+            SimControllerCommand::KnownServers => {
+                self.logger.log(
+                    &format!("COMMAND: Sending known servers ({:?})", self.server_types),
+                    LogLevel::DEBUG,
+                );
+                // Check the server types, if any are unknown (Server), request the type
+                let node_types = self.topology.get_node_types().clone();
+                for (server_id, server_type) in node_types {
+                    if server_type == "server" {
+                        self.send_server_type_request(server_id);
+                    }
+                }
+                // Then, send the response
+                let response = SimControllerMessage::KnownServers(self.server_types.clone());
+                let _res = self
+                    .sim_controller_sender
+                    .send(SimControllerResponseWrapper::Message(response));
+            }
+            _ => {
+                self.logger.log(
+                    &format!("COMMAND: Unrecognized command: {command:?}"),
+                    LogLevel::ERROR,
+                );
+            }
+        }
+    }
+
+    fn acked_packets(&mut self) -> &mut HashMap<u64, Vec<bool>> {
+        &mut self.acked_packets
+    }
+
+    /// Send a `ServerType` request to a server, the request is the same for every server type
+    fn send_server_type_request(&mut self, server_id: NodeId) {
+        self.logger.log(
+            &format!("Sending server type request to {server_id}"),
+            LogLevel::DEBUG,
+        );
+        let request = ChatRequestWrapper::ServerType(ServerTypeRequest::ServerType);
+        let request_json = request.stringify();
+        self.send_message(server_id, request_json);
+    }
+
+    fn running(&mut self) -> &mut bool {
+        &mut self.running
+    }
+
+    fn packets_to_send(&mut self) -> &mut HashMap<u8, Packet> {
+        &mut self.packets_to_send
+    }
+
+    fn sent_flood_ids(&mut self) -> &mut Vec<u64> {
+        &mut self.sent_flood_ids
+    }
+
+    fn last_flood_timestamp(&mut self) -> &mut u128 {
+        &mut self.last_flood_timestamp
+    }
+
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}